@@ -0,0 +1,27 @@
+#version 330 core
+in vec2 TexCoord;
+out vec4 FragColor;
+uniform sampler2D source;
+uniform int pass;       // 0 extracts the bright pixels, 1 blurs them along `direction`
+uniform float threshold; // luminance where the bloom starts
+uniform vec2 direction; // one texel along the blur axis
+
+// 9 tap Gaussian, the center weight plus 4 on each side
+const float weights[5] = float[](0.227027, 0.1945946, 0.1216216, 0.054054, 0.016216);
+
+void main() {
+    if (pass == 0) {
+        vec3 color = texture(source, TexCoord).rgb;
+        float luminance = dot(color, vec3(0.2126, 0.7152, 0.0722));
+        // keep only the part above the threshold so pixels fade into the bloom instead of popping
+        FragColor = vec4(color * max(luminance - threshold, 0.0) / max(luminance, 1e-4), 1.0);
+        return;
+    }
+
+    vec3 sum = texture(source, TexCoord).rgb * weights[0];
+    for (int i = 1; i < 5; ++i) {
+        sum += texture(source, TexCoord + direction * float(i)).rgb * weights[i];
+        sum += texture(source, TexCoord - direction * float(i)).rgb * weights[i];
+    }
+    FragColor = vec4(sum, 1.0);
+}
@@ -0,0 +1,39 @@
+#version 330 core
+in vec2 TexCoord;
+out vec4 FragColor;
+uniform sampler2D source;
+uniform int pass;    // 0 finds the brightest spot of each region of the image, 1 the brightest of the regions
+uniform int regions; // regions along each axis of the image, the size of the pass 0 target
+
+// Writes the luminance of the brightest spot and its texture coordinates. The spots are 2x2
+// texel averages, so a lone hot pixel counts for a quarter of its brightness and the photon
+// ring or the disk wins over a bright star
+void main() {
+    vec4 brightest = vec4(0.0);
+    if (pass == 0) {
+        ivec2 size = textureSize(source, 0);
+        ivec2 region = ivec2(gl_FragCoord.xy);
+        ivec2 start = region * size / regions;
+        ivec2 end = (region + 1) * size / regions;
+        for (int y = start.y; y < end.y; y += 2) {
+            for (int x = start.x; x < end.x; x += 2) {
+                // on the corner between four texels, which the linear filter averages
+                vec2 uv = min(vec2(x + 1, y + 1), vec2(size) - 0.5) / vec2(size);
+                float luminance = dot(texture(source, uv).rgb, vec3(0.2126, 0.7152, 0.0722));
+                if (luminance > brightest.x) {
+                    brightest = vec4(luminance, uv, 1.0);
+                }
+            }
+        }
+    } else {
+        for (int y = 0; y < regions; ++y) {
+            for (int x = 0; x < regions; ++x) {
+                vec4 candidate = texelFetch(source, ivec2(x, y), 0);
+                if (candidate.x > brightest.x) {
+                    brightest = candidate;
+                }
+            }
+        }
+    }
+    FragColor = brightest;
+}
@@ -1,7 +1,18 @@
 #version 430
 layout(local_size_x = 16, local_size_y = 16) in;
 
-layout(binding = 0, rgba8) writeonly uniform image2D outImage;
+layout(binding = 0, rgba16f) writeonly uniform image2D outImage;
+layout(binding = 1, rgba32f) uniform image2D accumImage; // running mean of the still frames
+uniform int frame_index; // frames already in accumImage, 0 after the view changed
+layout(binding = 2, r8) uniform image2D edgeImage; // 1 on the pixels the adaptive AA keeps sampling
+uniform int edge_pass;        // 1 fills edgeImage from accumImage instead of tracing
+uniform float edge_threshold; // compressed luminance step across a pixel's neighbours that marks an edge
+uniform int resample_edges;   // 1 traces only the pixels edgeImage marks, the rest keep their mean
+uniform vec2 jitter;     // sub-pixel offset of this frame's rays, in [-0.5, 0.5) pixels
+uniform ivec4 tile;      // pixels this dispatch traces: xy the first, zw one past the last
+uniform int debug_ray = -1; // pix.y * width + pix.x of the ray whose path goes to DebugRay, -1 for none
+uniform int max_disk_crossings = 1; // a ray stops at this crossing of the thin disk and sees through the ones before
+uniform int render_channel; // CHANNEL_COLOR, or the quantity the other channels map through colormapTex
 layout(std140, binding = 1) uniform Camera {
     vec3 camPos;     float _pad0;
     vec3 camRight;   float _pad1;
@@ -18,6 +29,15 @@ layout(std140, binding = 2) uniform Disk {
     float disk_r2;
     float disk_num;
     float thickness;
+    vec4  disk_color; // rgb tint, w unused
+    vec4  disk_axis;  // xyz unit normal of the disk plane along its orbital angular momentum, w angular velocity in Keplerian units
+    int   doppler_enabled;
+    int   disk_profile;    // 0 built-in gradient, 1 diskTex holds colors, 2 its red channel is a temperature
+    float max_temperature; // kelvin at a red value of 1 in the temperature profile
+    int   disk_model;      // DISK_THIN, DISK_THICK or DISK_NONE
+    float density_scale;   // thick disk: multiplies the gas density, and with it the optical depth
+    float emission_scale;  // thick disk: multiplies the glow per unit of optical depth
+    int   _diskPad1, _diskPad2;
 };
 
 layout(std140, binding = 3) uniform Objects {
@@ -27,8 +47,64 @@ layout(std140, binding = 3) uniform Objects {
     float  mass[16]; 
 };
 
-const float SagA_rs = 1.269e10;
-const float D_LAMBDA = 1e7;
+layout(std140, binding = 4) uniform BlackHole {
+    float spin; // dimensionless a/M in [0, 1), 0 = Schwarzschild
+    float rs;   // Schwarzschild radius 2GM/c^2 in meters
+    int   redshift_enabled;
+    float redshift_exaggeration; // gravitational shift applied as g^exaggeration
+    int   observer_region;       // REGION_EXTERIOR or REGION_INTERIOR
+    int   _bhPad0, _bhPad1, _bhPad2;
+};
+
+layout(std140, binding = 5) uniform Integrator {
+    int   max_steps; // integration steps per ray (attempts, in adaptive mode)
+    float step_size; // affine parameter step, roughly meters of path per step
+    int   integration_mode; // MODE_FIXED or MODE_ADAPTIVE
+    float tolerance; // adaptive mode: allowed relative position error per step
+    float horizon_epsilon; // rays within this fraction of rs above the horizon count as captured
+};
+
+layout(std140, binding = 6) uniform Sky {
+    float star_density;    // chance that a sky cell holds a star, 0..1
+    float star_brightness;
+    uint  star_seed;
+    int   starfield_enabled;
+    int   background_enabled; // backgroundTex holds an equirectangular panorama
+    int   _skyPad0, _skyPad1, _skyPad2;
+    vec4  background_color; // rgb behind escaped rays when no panorama is bound, w unused
+};
+// The path of debug_ray, a point per step from the camera's on. That ray writes nothing else.
+layout(std430, binding = 0) buffer DebugRay {
+    int  debug_count;
+    int  _debugPad0, _debugPad1, _debugPad2;
+    vec4 debug_points[]; // xyz position in meters, w affine parameter
+};
+layout(binding = 1) uniform sampler2D backgroundTex;
+layout(binding = 2) uniform sampler2D diskTex; // emission over the disk: u from the inner to the outer edge, v around it
+layout(binding = 3) uniform sampler2D colormapTex; // display colors from 0 to 1 along u
+
+const int DISK_THIN  = 0; // a sheet in the equatorial plane, shaded where rays cross it
+const int DISK_THICK = 1; // a slab `thickness` tall that rays march through
+const int DISK_NONE  = 2;
+
+const float THICK_TAU = 3.0; // optical depth straight through the thick disk at its inner edge, at a density_scale of 1
+
+const int PROFILE_GRADIENT    = 0;
+const int PROFILE_COLOR       = 1;
+const int PROFILE_TEMPERATURE = 2;
+
+const int CHANNEL_COLOR       = 0;
+const int CHANNEL_DEFLECTION  = 1; // total bending of the ray, a full turn at the top of the map
+const int CHANNEL_REDSHIFT    = 2; // frequency ratio g of the light the ray ends on, 1 in the middle
+const int CHANNEL_TRAVEL_TIME = 3; // affine parameter the ray covers, two escape radii at the top
+const int CHANNEL_TERMINATION = 4; // how the ray ended, see terminationValue
+
+const int MODE_FIXED    = 0;
+const int MODE_ADAPTIVE = 1;
+
+const int REGION_EXTERIOR = 0; // a static observer, the integrators below by integration_mode and spin
+const int REGION_INTERIOR = 1; // a falling observer who may be inside the horizon, see RainRay
+
 const double ESCAPE_R = 1e30;
 
 // Globals to store hit info
@@ -36,6 +112,9 @@ vec4 objectColor = vec4(0.0);
 vec3 hitCenter = vec3(0.0);
 float hitRadius = 0.0;
 
+bool recording = false; // this invocation traces debug_ray
+int recorded = 0;
+
 struct Ray {
     float x, y, z, r, theta, phi;
     float dr, dtheta, dphi;
@@ -54,7 +133,7 @@ Ray initRay(vec3 pos, vec3 dir) {
     ray.dphi   = (-sin(ray.phi)*dx + cos(ray.phi)*dy) / (ray.r * sin(ray.theta));
 
     ray.L = ray.r * ray.r * sin(ray.theta) * ray.dphi;
-    float f = 1.0 - SagA_rs / ray.r;
+    float f = 1.0 - rs / ray.r;
     float dt_dL = sqrt((ray.dr*ray.dr)/f + ray.r*ray.r*(ray.dtheta*ray.dtheta + sin(ray.theta)*sin(ray.theta)*ray.dphi*ray.dphi));
     ray.E = f * dt_dL;
 
@@ -83,12 +162,12 @@ bool interceptObject(Ray ray) {
 void geodesicRHS(Ray ray, out vec3 d1, out vec3 d2) {
     float r = ray.r, theta = ray.theta;
     float dr = ray.dr, dtheta = ray.dtheta, dphi = ray.dphi;
-    float f = 1.0 - SagA_rs / r;
+    float f = 1.0 - rs / r;
     float dt_dL = ray.E / f;
 
     d1 = vec3(dr, dtheta, dphi);
-    d2.x = - (SagA_rs / (2.0 * r*r)) * f * dt_dL * dt_dL
-         + (SagA_rs / (2.0 * r*r * f)) * dr * dr
+    d2.x = - (rs / (2.0 * r*r)) * f * dt_dL * dt_dL
+         + (rs / (2.0 * r*r * f)) * dr * dr
          + r * (dtheta*dtheta + sin(theta)*sin(theta)*dphi*dphi);
     d2.y = -2.0*dr*dtheta/r + sin(theta)*cos(theta)*dphi*dphi;
     d2.z = -2.0*dr*dphi/r - 2.0*cos(theta)/(sin(theta)) * dtheta * dphi;
@@ -108,54 +187,663 @@ void rk4Step(inout Ray ray, float dL) {
     ray.y = ray.r * sin(ray.theta) * sin(ray.phi);
     ray.z = ray.r * cos(ray.theta);
 }
+// The disk's frame: height above its plane, along disk_axis, and distance from its axis. With no
+// inclination the plane is the equatorial one, y = 0.
+float diskHeight(vec3 pos) {
+    return dot(pos, disk_axis.xyz);
+}
+
+float diskRadius(vec3 pos) {
+    return length(pos - disk_axis.xyz * diskHeight(pos));
+}
+
 bool crossesEquatorialPlane(vec3 oldPos, vec3 newPos) {
-    bool crossed = (oldPos.y * newPos.y < 0.0);
-    float r = length(vec2(newPos.x, newPos.z));
+    if (disk_model != DISK_THIN) return false;
+    bool crossed = (diskHeight(oldPos) * diskHeight(newPos) < 0.0);
+    float r = diskRadius(newPos);
     return crossed && (r >= disk_r1 && r <= disk_r2);
 }
 
+// --- Kerr ------------------------------------------------------------------ //
+// Rotating black holes are integrated in Boyer-Lindquist coordinates using the
+// Hamiltonian form of the null geodesic equations, with the spin axis along +y
+// so the accretion disk lies in the equatorial plane. Lengths are measured in
+// units of rs (M = 0.5) to keep everything well inside float precision. The
+// conserved energy E and angular momentum L are carried along, the remaining
+// momenta (p_r, p_theta) are evolved from dp/dlambda = -dH/dx.
+// A spin of exactly 0 keeps using the Schwarzschild integrator above, so the
+// non-rotating image is unchanged; note that this path enforces H = 0 on the
+// initial momentum, so a tiny spin is not pixel-identical to it.
+struct KerrRay {
+    float r, theta, phi;
+    float pr, ptheta;
+    float E, L;
+};
+
+const float KERR_M = 0.5;
+
+// Position in world space (meters) of a Boyer-Lindquist point.
+vec3 kerrCartesian(KerrRay ray, float a) {
+    float rho = sqrt(ray.r * ray.r + a * a) * sin(ray.theta);
+    return rs * vec3(rho * cos(ray.phi), ray.r * cos(ray.theta), rho * sin(ray.phi));
+}
+
+KerrRay initKerrRay(vec3 pos, vec3 dir, float a) {
+    KerrRay ray;
+    vec3 p = pos / rs;
+
+    // invert x^2 + z^2 = (r^2 + a^2) sin^2(theta), y = r cos(theta)
+    float rho2 = dot(p, p) - a * a;
+    ray.r     = sqrt(0.5 * (rho2 + sqrt(rho2 * rho2 + 4.0 * a * a * p.y * p.y)));
+    ray.theta = acos(clamp(p.y / ray.r, -1.0, 1.0));
+    ray.phi   = atan(p.z, p.x);
+
+    float st = sin(ray.theta), ct = cos(ray.theta);
+    float sp = sin(ray.phi),   cp = cos(ray.phi);
+    // local flat-space velocities along the coordinate directions
+    float dr     = dot(dir, vec3(st * cp, ct, st * sp));
+    float dtheta = dot(dir, vec3(ct * cp, -st, ct * sp)) / ray.r;
+    float dphi   = dot(dir, vec3(-sp, 0.0, cp)) / (ray.r * st);
+
+    float r2 = ray.r * ray.r;
+    float sigma = r2 + a * a * ct * ct;
+    float delta = r2 - 2.0 * KERR_M * ray.r + a * a;
+    float A = r2 + a * a;
+    float gphiphi = (A * A - a * a * delta * st * st) * st * st / sigma;
+
+    ray.pr     = sigma / delta * dr;
+    ray.ptheta = sigma * dtheta;
+    ray.L      = gphiphi * dphi;
+
+    // pick E so that the momentum is null (H = 0); quadratic in E
+    float qa = a * a * st * st - A * A / delta;
+    float qb = 2.0 * a * ray.L * (A / delta - 1.0);
+    float qc = ray.L * ray.L / (st * st) - a * a * ray.L * ray.L / delta
+             + delta * ray.pr * ray.pr + ray.ptheta * ray.ptheta;
+    ray.E = (-qb - sqrt(max(qb * qb - 4.0 * qa * qc, 0.0))) / (2.0 * qa);
+
+    return ray;
+}
+
+// Derivatives of (r, theta, phi, p_r, p_theta) with respect to the affine parameter.
+void kerrRHS(KerrRay ray, float a, out vec3 dx, out vec2 dp) {
+    float r = ray.r;
+    float st = sin(ray.theta), ct = cos(ray.theta);
+    float E = ray.E, L = ray.L;
+
+    float sigma = r * r + a * a * ct * ct;
+    float delta = r * r - 2.0 * KERR_M * r + a * a;
+    float P = (r * r + a * a) * E - a * L;
+    float W = L / st - a * E * st;
+
+    // 2 H sigma = N
+    float N = delta * ray.pr * ray.pr + ray.ptheta * ray.ptheta - P * P / delta + W * W;
+
+    float dDelta = 2.0 * r - 2.0 * KERR_M;
+    float dN_dr = dDelta * ray.pr * ray.pr
+                - (2.0 * P * 2.0 * r * E * delta - P * P * dDelta) / (delta * delta);
+    float dN_dtheta = 2.0 * W * (-L * ct / (st * st) - a * E * ct);
+
+    float dSigma_dr = 2.0 * r;
+    float dSigma_dtheta = -2.0 * a * a * st * ct;
+    float inv2s2 = 0.5 / (sigma * sigma);
+
+    dx = vec3(delta * ray.pr / sigma,
+              ray.ptheta / sigma,
+              (a * P / delta + W / st) / sigma);
+    dp = vec2(-(dN_dr * sigma - N * dSigma_dr) * inv2s2,
+              -(dN_dtheta * sigma - N * dSigma_dtheta) * inv2s2);
+}
+
+void kerrStep(inout KerrRay ray, float a, float dL) {
+    vec3 dx; vec2 dp;
+    kerrRHS(ray, a, dx, dp);
+
+    ray.r      += dL * dx.x;
+    ray.theta  += dL * dx.y;
+    ray.phi    += dL * dx.z;
+    ray.pr     += dL * dp.x;
+    ray.ptheta += dL * dp.y;
+}
+
+float kerrHorizon(float a) {
+    return KERR_M + sqrt(KERR_M * KERR_M - a * a);
+}
+
+// --- Painleve-Gullstrand --------------------------------------------------- //
+// The interior view integrates the Schwarzschild geometry in Painleve-Gullstrand
+// coordinates: space is flat and falls inwards at the Newtonian escape velocity,
+// v = -sqrt(rs / r) along the radius, and nothing is singular at the horizon,
+// so the camera can cross it. The camera falls with that flow, from rest far
+// away, and the coordinate axes are its local frame: a photon arriving along -d
+// has the covariant spatial momentum p = d and the conserved energy E = 1 + v.d.
+// The Hamiltonian is H = (|p|^2 - (v.p - E)^2) / 2, rays run back in time from
+// the camera. Inside the horizon E can be negative, that light never crossed it.
+struct RainRay {
+    vec3 pos; // meters
+    vec3 p;
+    float E;
+};
+
+RainRay initRainRay(vec3 pos, vec3 dir) {
+    RainRay ray;
+    ray.pos = pos;
+    ray.p = -dir; // the light travels against the view
+    ray.E = 1.0 - sqrt(rs / length(pos)) * dot(normalize(pos), ray.p);
+    return ray;
+}
+
+// Derivatives of the position and momentum with respect to the affine parameter, forwards in time.
+void rainRHS(RainRay ray, out vec3 dx, out vec3 dp) {
+    float r = length(ray.pos);
+    vec3 n = ray.pos / r;
+    float s = sqrt(rs / r);
+    vec3 v = -s * n;
+    float q = dot(v, ray.p) - ray.E;
+    dx = ray.p - q * v;
+    dp = q * (s / r) * (1.5 * dot(n, ray.p) * n - ray.p);
+}
+
+// --- Disk shading ---------------------------------------------------------- //
+// Frequency ratio g = f_observed / f_emitted of light leaving the disk at `pos`
+// towards the camera along `photonDir`. The gas moves on circular orbits around
+// `disk_axis` at the speed a static observer measures for a Kepler orbit in
+// Schwarzschild, beta = sqrt(rs / 2(r - rs)), scaled by disk_axis.w.
+float dopplerFactor(vec3 pos, vec3 photonDir) {
+    float r = length(pos);
+    float beta = clamp(abs(disk_axis.w) * sqrt(rs / (2.0 * max(r - rs, 1e-3 * rs))), 0.0, 0.99);
+    vec3 orbitDir = normalize(cross(disk_axis.xyz, pos)) * sign(disk_axis.w);
+    float gamma = inversesqrt(1.0 - beta * beta);
+    return 1.0 / (gamma * (1.0 - beta * dot(orbitDir, photonDir)));
+}
+
+// Frequency ratio between light emitted by a static source at radius r and
+// received by a static observer at the camera, sqrt((1 - rs/r) / (1 - rs/r_cam)).
+// Uses the Schwarzschild factor for rotating holes too; raised to
+// redshift_exaggeration to make the effect easier to see.
+float gravitationalShift(float r) {
+    float emitted = max(1.0 - rs / r, 0.0);
+    float received = max(1.0 - rs / length(cam.camPos), 1e-4);
+    return pow(sqrt(emitted / received), redshift_exaggeration);
+}
+
+// Linear color of a blackbody at `kelvin` with its brightest channel at 1,
+// Tanner Helland's fit to the CIE tables for 1000 K to 40000 K decoded from sRGB.
+vec3 blackbody(float kelvin) {
+    float t = clamp(kelvin, 1000.0, 40000.0) / 100.0;
+    vec3 c;
+    c.r = t <= 66.0 ? 255.0 : 329.698727446 * pow(t - 60.0, -0.1332047592);
+    c.g = t <= 66.0 ? 99.4708025861 * log(t) - 161.1195681661 : 288.1221695283 * pow(t - 60.0, -0.0755148492);
+    c.b = t >= 66.0 ? 255.0 : (t <= 19.0 ? 0.0 : 138.5177312231 * log(t - 10.0) - 305.0447927307);
+    c = clamp(c / 255.0, 0.0, 1.0);
+    return mix(c / 12.92, pow((c + 0.055) / 1.055, vec3(2.4)), step(0.04045, c));
+}
+
+// Emitted color of the disk at `pos` in the disk plane, before any frequency shift.
+// The texture is clamped across the disk, so the edge texels carry on to the
+// edges, and wraps around it.
+vec3 diskEmission(vec3 pos) {
+    float r = length(pos);
+    if (disk_profile == PROFILE_GRADIENT) {
+        return vec3(1.0, r / disk_r2, 0.2);
+    }
+    float u = (r - disk_r1) / (disk_r2 - disk_r1);
+    const float TAU = 6.28318530718;
+    // around the disk from an in-plane x axis, the world's x while the disk isn't tilted
+    vec3 n = disk_axis.xyz;
+    vec3 e1 = cross(n, vec3(0.0, 0.0, 1.0));
+    e1 = dot(e1, e1) > 1e-6 ? normalize(e1) : vec3(1.0, 0.0, 0.0);
+    float v = atan(dot(pos, cross(e1, n)), dot(pos, e1)) / TAU + 0.5;
+    vec4 texel = textureLod(diskTex, vec2(u, v), 0.0);
+    return disk_profile == PROFILE_COLOR ? texel.rgb : blackbody(texel.r * max_temperature);
+}
+
+// Relativistic beaming scales the intensity by g^3 and the color is pushed
+// towards blue for g > 1 (blueshift) and towards red for g < 1 (redshift).
+// The result is linear radiance and isn't clamped, the tone mapping of the
+// fullscreen pass compresses the bright approaching side.
+vec3 applyFrequencyShift(vec3 color, float g) {
+    vec3 tint = g >= 1.0 ? vec3(1.0 / (g * g), 1.0 / g, 1.0) : vec3(1.0, g, g * g);
+    return color * tint * g * g * g;
+}
+
+// Color the disk at `pos` sends back along a ray traced in `rayDir`, tinted and shifted.
+vec3 diskColorAt(vec3 pos, vec3 rayDir) {
+    vec3 diskColor = diskEmission(pos) * disk_color.rgb;
+    // Doppler and gravitational shifts compose by multiplying their frequency ratios
+    float g = 1.0;
+    if (doppler_enabled != 0) {
+        // the light travels opposite to the traced ray
+        g *= dopplerFactor(pos, -normalize(rayDir));
+    }
+    if (redshift_enabled != 0) {
+        g *= gravitationalShift(length(pos));
+    }
+    if (doppler_enabled != 0 || redshift_enabled != 0) {
+        diskColor = applyFrequencyShift(diskColor, g);
+    }
+    return diskColor;
+}
+
+// Density of the thick disk at `pos` relative to the middle of its inner edge: a gaussian in the
+// height above the plane, with `thickness` spanning four standard deviations, times a fall off
+// as 1/r that fades to nothing over the innermost and outermost fifth of the disk. The faded
+// edges are what lets the lensed sky show through around the rims.
+float thickDiskDensity(vec3 pos) {
+    float r = diskRadius(pos);
+    float fade = 0.2 * (disk_r2 - disk_r1);
+    float radial = disk_r1 / r * smoothstep(disk_r1, disk_r1 + fade, r) * (1.0 - smoothstep(disk_r2 - fade, disk_r2, r));
+    float y = 4.0 * diskHeight(pos) / thickness; // in standard deviations
+    return radial * exp(-0.5 * y * y);
+}
+
+// Integrates the segment from a to b through the thick disk, the slab |y| < thickness / 2
+// between the disk radii, front to back: the segment adds its glow as seen through the gas in
+// front of it and dims whatever lies behind. Emission and absorption share the density, so
+// where the gas is opaque it glows with the disk's color and where it is thin the sky comes
+// through. The part of the segment inside the slab is sampled once, at its middle: the volume
+// costs one sample per integration step and never a loop of its own, which also keeps the
+// integration loops free of nested ones. Returns false once the ray is absorbed.
+bool sampleThickDisk(vec3 a, vec3 b, inout vec3 glow, inout float transmittance) {
+    float h = 0.5 * thickness;
+    // clip the segment to the slab
+    float t0 = 0.0, t1 = 1.0;
+    float ya = diskHeight(a);
+    float dy = diskHeight(b) - ya;
+    if (abs(dy) < 1e-6 * h) {
+        if (abs(ya) >= h) return true;
+    } else {
+        float ta = (-h - ya) / dy, tb = (h - ya) / dy;
+        t0 = max(t0, min(ta, tb));
+        t1 = min(t1, max(ta, tb));
+        if (t0 >= t1) return true;
+    }
+    vec3 p = mix(a, b, 0.5 * (t0 + t1));
+    float r = diskRadius(p);
+    if (r < disk_r1 || r > disk_r2) return true;
+    float kappa = density_scale * THICK_TAU / (1.2 * h); // the gaussian integrates to about 1.2 h across the slab
+    float absorbed = 1.0 - exp(-kappa * thickDiskDensity(p) * length(b - a) * (t1 - t0));
+    glow += transmittance * absorbed * emission_scale * diskColorAt(p, b - a);
+    transmittance *= 1.0 - absorbed;
+    return transmittance >= 0.01;
+}
+
+// --- Starfield ------------------------------------------------------------- //
+// Stars are scattered over a grid of cells in direction space: each cell the
+// escaped direction passes through holds at most one star at a hashed position,
+// so the sky is fixed in world space and the lensing visibly drags it around.
+uint pcgHash(uint v) {
+    uint state = v * 747796405u + 2891336453u;
+    uint word = ((state >> ((state >> 28u) + 4u)) ^ state) * 277803737u;
+    return (word >> 22u) ^ word;
+}
+
+uint cellHash(ivec3 cell, uint seed) {
+    return pcgHash(uint(cell.x) ^ pcgHash(uint(cell.y) ^ pcgHash(uint(cell.z) ^ pcgHash(seed))));
+}
+
+float unitFloat(uint h) {
+    return float(h) * (1.0 / 4294967295.0);
+}
+
+vec3 starLayer(vec3 dir, float cellsPerRadian, uint seed) {
+    vec3 p = dir * cellsPerRadian;
+    ivec3 cell = ivec3(floor(p));
+    uint h = cellHash(cell, seed);
+    if (unitFloat(h) > star_density) return vec3(0.0);
+
+    uint h1 = pcgHash(h), h2 = pcgHash(h1), h3 = pcgHash(h2), h4 = pcgHash(h3);
+    vec3 star = vec3(cell) + vec3(unitFloat(h1), unitFloat(h2), unitFloat(h3));
+    float d = length(p - star);
+    float magnitude = unitFloat(h4);
+    float glow = smoothstep(0.35, 0.0, d) * magnitude * magnitude;
+    // cool red to hot blue-white stars
+    vec3 tint = mix(vec3(1.0, 0.75, 0.55), vec3(0.75, 0.85, 1.0), unitFloat(pcgHash(h4)));
+    return tint * glow;
+}
+
+vec3 starfield(vec3 dir) {
+    vec3 stars = starLayer(dir, 150.0, star_seed) + 0.6 * starLayer(dir, 400.0, star_seed + 1u);
+    return stars * star_brightness;
+}
+
+// Equirectangular lookup with y as the north pole: u runs once around the
+// equator and wraps at the seam through the texture's repeat mode, v goes
+// from the north pole at the top row down to the south pole.
+vec3 background(vec3 dir) {
+    const float PI = 3.14159265359;
+    float u = 0.5 + atan(dir.z, dir.x) / (2.0 * PI);
+    float v = acos(clamp(dir.y, -1.0, 1.0)) / PI;
+    // no derivatives in a compute shader, always sample the full resolution level
+    return textureLod(backgroundTex, vec2(u, v), 0.0).rgb;
+}
+
+// --- Adaptive integration -------------------------------------------------- //
+// Cash-Karp RK45 on a state shared by both metrics: x = (r, theta, phi) and v
+// is the Schwarzschild coordinate velocity or the Kerr momenta (p_r, p_theta, 0).
+// The embedded 4th order solution estimates the error of each step, which is
+// kept below `tolerance` relative to r by growing or shrinking the step.
+float adaptiveE, adaptiveL, adaptiveA;
+
+void stateRHS(vec3 x, vec3 v, out vec3 dx, out vec3 dv) {
+    if (spin == 0.0) {
+        Ray ray;
+        ray.r = x.x; ray.theta = x.y; ray.phi = x.z;
+        ray.dr = v.x; ray.dtheta = v.y; ray.dphi = v.z;
+        ray.E = adaptiveE; ray.L = adaptiveL;
+        geodesicRHS(ray, dx, dv);
+    } else {
+        KerrRay ray;
+        ray.r = x.x; ray.theta = x.y; ray.phi = x.z;
+        ray.pr = v.x; ray.ptheta = v.y;
+        ray.E = adaptiveE; ray.L = adaptiveL;
+        vec2 dp;
+        kerrRHS(ray, adaptiveA, dx, dp);
+        dv = vec3(dp, 0.0);
+    }
+}
+
+// Advances (x, v) by h and returns the estimated error relative to r.
+float rk45Step(inout vec3 x, inout vec3 v, float h) {
+    vec3 kx1, kv1, kx2, kv2, kx3, kv3, kx4, kv4, kx5, kv5, kx6, kv6;
+    stateRHS(x, v, kx1, kv1);
+    stateRHS(x + h * (0.2 * kx1),
+             v + h * (0.2 * kv1), kx2, kv2);
+    stateRHS(x + h * (3.0/40.0 * kx1 + 9.0/40.0 * kx2),
+             v + h * (3.0/40.0 * kv1 + 9.0/40.0 * kv2), kx3, kv3);
+    stateRHS(x + h * (0.3 * kx1 - 0.9 * kx2 + 1.2 * kx3),
+             v + h * (0.3 * kv1 - 0.9 * kv2 + 1.2 * kv3), kx4, kv4);
+    stateRHS(x + h * (-11.0/54.0 * kx1 + 2.5 * kx2 - 70.0/27.0 * kx3 + 35.0/27.0 * kx4),
+             v + h * (-11.0/54.0 * kv1 + 2.5 * kv2 - 70.0/27.0 * kv3 + 35.0/27.0 * kv4), kx5, kv5);
+    stateRHS(x + h * (1631.0/55296.0 * kx1 + 175.0/512.0 * kx2 + 575.0/13824.0 * kx3 + 44275.0/110592.0 * kx4 + 253.0/4096.0 * kx5),
+             v + h * (1631.0/55296.0 * kv1 + 175.0/512.0 * kv2 + 575.0/13824.0 * kv3 + 44275.0/110592.0 * kv4 + 253.0/4096.0 * kv5), kx6, kv6);
+
+    // 5th order weights, and their difference to the embedded 4th order ones
+    const float b1 = 37.0/378.0, b3 = 250.0/621.0, b4 = 125.0/594.0, b6 = 512.0/1771.0;
+    const float e1 = b1 - 2825.0/27648.0, e3 = b3 - 18575.0/48384.0, e4 = b4 - 13525.0/55296.0,
+                e5 = -277.0/14336.0, e6 = b6 - 0.25;
+    vec3 err = h * (e1 * kx1 + e3 * kx3 + e4 * kx4 + e5 * kx5 + e6 * kx6);
+
+    float r = x.x;
+    x += h * (b1 * kx1 + b3 * kx3 + b4 * kx4 + b6 * kx6);
+    v += h * (b1 * kv1 + b3 * kv3 + b4 * kv4 + b6 * kv6);
+    return length(vec3(err.x / r, err.y, err.z * sin(x.y)));
+}
+
+// Where the segment crosses the equatorial plane, when that is on the disk.
+bool diskCrossing(vec3 oldPos, vec3 newPos, out vec3 hitPos) {
+    float oldHeight = diskHeight(oldPos), newHeight = diskHeight(newPos);
+    if (disk_model != DISK_THIN || oldHeight * newHeight >= 0.0) return false;
+    hitPos = mix(oldPos, newPos, oldHeight / (oldHeight - newHeight));
+    float r = diskRadius(hitPos);
+    return r >= disk_r1 && r <= disk_r2;
+}
+
+vec3 diskLight = vec3(0.0); // emission summed over the disk crossings of the ray so far
+int diskCrossings = 0;
+
+// Adds what the thin disk sends back where the ray crosses it at pos going along rayDir, true
+// once that was the last crossing max_disk_crossings lets the ray make. The n-th crossing is the
+// n-th order image: 1 the disk seen directly, 2 the thin ring lensed over and under the shadow
+// by rays that wound half way round the hole, and so on, each fainter and closer to the shadow.
+bool crossDisk(vec3 pos, vec3 rayDir) {
+    diskLight += diskColorAt(pos, rayDir);
+    return ++diskCrossings >= max_disk_crossings;
+}
+
+// Beyond this distance an outgoing ray can't reach the disk, an object or come back.
+float escapeRadius() {
+    float r = max(disk_r2, length(cam.camPos));
+    for (int i = 0; i < numObjects; ++i) {
+        r = max(r, length(objPosRadius[i].xyz) + objPosRadius[i].w);
+    }
+    return max(r, 3.0 * rs) * 1.01;
+}
+
+// Angle between a and b as seen from the black hole, accurate for the tiny ones between steps
+float sweptAngle(vec3 a, vec3 b) {
+    return atan(length(cross(a, b)), dot(a, b));
+}
+
+// Where the channel puts this ray in [0, 1]. `swept` is the angle the ray's position turned
+// around the hole, so its bending is that plus what a straight line from its last position
+// would still turn, minus what a straight line from the camera turns in all. Rays grazing
+// the photon sphere wind around it before they leave, the bending diverges towards its edge.
+float channelValue(vec3 pos, vec3 rayDir, vec3 endDir, float swept, float lambda, bool captured, bool hitDisk, bool hitObject) {
+    if (render_channel == CHANNEL_DEFLECTION) {
+        const float TAU = 6.28318530718;
+        float bending = swept + (captured ? 0.0 : sweptAngle(pos, endDir)) - sweptAngle(cam.camPos, rayDir);
+        return bending / TAU;
+    }
+    if (render_channel == CHANNEL_REDSHIFT) {
+        // the source's shift whatever the Doppler and redshift switches say, 0 for the hole
+        float g = 0.0;
+        if (hitDisk) g = dopplerFactor(pos, -normalize(endDir)) * gravitationalShift(length(pos));
+        else if (hitObject) g = gravitationalShift(length(pos));
+        else if (!captured) g = gravitationalShift(1e30);
+        return 0.5 * g;
+    }
+    return lambda / (2.0 * escapeRadius());
+}
+
+// Where CHANNEL_TERMINATION puts a ray: the horizon at the bottom of the map, then the sky it
+// escaped to, then the disk, an object or the thick disk's gas it stopped on, and at the top
+// the rays whose steps ran out before any of those, the ones that speckle the shadow's edge
+float terminationValue(bool horizon, bool hit, bool escaped) {
+    if (horizon) return 0.0;
+    if (escaped) return 0.3;
+    if (hit) return 0.6;
+    return 1.0;
+}
+
+void recordPoint(vec3 pos, float lambda) {
+    if (!recording || recorded >= debug_points.length()) return;
+    debug_points[recorded] = vec4(pos, lambda);
+    recorded++;
+}
+
+// Marks pix in edgeImage when the luminance of its 3x3 neighbourhood, compressed to [0, 1) as
+// L / (1 + L) so the threshold holds at any brightness, spans more than edge_threshold. The
+// photon ring and the rims of the disk cross it, smooth sky and disk stay unmarked.
+void markEdge(ivec2 pix, ivec2 size) {
+    float lo = 1.0;
+    float hi = 0.0;
+    for (int dy = -1; dy <= 1; ++dy) {
+        for (int dx = -1; dx <= 1; ++dx) {
+            vec3 c = imageLoad(accumImage, clamp(pix + ivec2(dx, dy), ivec2(0), size - 1)).rgb;
+            float l = dot(c, vec3(0.2126, 0.7152, 0.0722));
+            l /= 1.0 + l;
+            lo = min(lo, l);
+            hi = max(hi, l);
+        }
+    }
+    imageStore(edgeImage, pix, vec4(hi - lo > edge_threshold ? 1.0 : 0.0));
+}
+
 void main() {
-    int WIDTH  = cam.moving ? 200 : 200;
-    int HEIGHT = cam.moving ? 150 : 150;
+    // the output image is sized by the engine to the compute resolution
+    ivec2 size = imageSize(outImage);
+    int WIDTH  = size.x;
+    int HEIGHT = size.y;
 
-    ivec2 pix = ivec2(gl_GlobalInvocationID.xy);
-    if (pix.x >= WIDTH || pix.y >= HEIGHT) return;
+    ivec2 pix = tile.xy + ivec2(gl_GlobalInvocationID.xy);
+    // an invocation past the tile would add a second sample to its neighbour's pixel
+    if (pix.x >= min(tile.z, WIDTH) || pix.y >= min(tile.w, HEIGHT)) return;
+    if (edge_pass == 1) {
+        markEdge(pix, size);
+        return;
+    }
+    // every marked pixel has had frame_index samples, so the running mean below still holds
+    if (resample_edges == 1 && imageLoad(edgeImage, pix).r < 0.5) return;
 
     // Init Ray
-    float u = (2.0 * (pix.x + 0.5) / WIDTH - 1.0) * cam.aspect * cam.tanHalfFov;
-    float v = (1.0 - 2.0 * (pix.y + 0.5) / HEIGHT) * cam.tanHalfFov;
+    float u = (2.0 * (pix.x + 0.5 + jitter.x) / WIDTH - 1.0) * cam.aspect * cam.tanHalfFov;
+    float v = (1.0 - 2.0 * (pix.y + 0.5 + jitter.y) / HEIGHT) * cam.tanHalfFov;
     vec3 dir = normalize(u * cam.camRight - v * cam.camUp + cam.camForward);
     Ray ray = initRay(cam.camPos, dir);
+    recording = pix.y * WIDTH + pix.x == debug_ray;
+    recordPoint(cam.camPos, 0.0);
 
     vec4 color = vec4(0.0);
     vec3 prevPos = vec3(ray.x, ray.y, ray.z);
     float lambda = 0.0;
+    float swept = 0.0; // CHANNEL_DEFLECTION only
 
     bool hitBlackHole = false;
     bool hitDisk      = false;
     bool hitObject    = false;
+    bool absorbed     = false; // stopped in the thick disk
+    vec3 glow = vec3(0.0);     // what the thick disk sent along the ray
+    float transmittance = 1.0; // and the share of the light behind it that gets through
+    vec3 hitDir       = dir; // direction the ray was traced in when it hit
+    vec3 lastDir      = dir; // direction of the last step, where an escaped ray points
+
+    int steps = max_steps;
 
-    int steps = cam.moving ? 60000 : 60000;
+    if (observer_region == REGION_INTERIOR) {
+        RainRay rray = initRainRay(cam.camPos, dir);
+        // from inside, light with E <= 0 can only have come from the past horizon, which these
+        // coordinates leave out; in a hole that formed from a collapsing star that is the star
+        hitBlackHole = length(cam.camPos) < rs && rray.E <= 0.0;
+        float escape = escapeRadius();
+
+        for (int i = 0; i < steps && !hitBlackHole; ++i) {
+            float r = length(rray.pos);
+            vec3 dx, dp;
+            rainRHS(rray, dx, dp);
+            // the steps shrink with r, the flow and the bending grow towards the singularity
+            float h = min(step_size, 0.02 * r / max(length(dx), 1e-6));
+            rray.pos -= h * dx;
+            rray.p -= h * dp;
+            lambda += h;
+
+            vec3 newPos = rray.pos;
+            float newR = length(newPos);
+            // traced back, light from outside crosses the horizon outwards. One sinking through it,
+            // or whose momentum runs away, is closing in on the past horizon instead: the shadow
+            if ((r >= rs && newR < rs) || dot(rray.p, rray.p) > 1e8 || newR < 1e-3 * rs) { hitBlackHole = true; break; }
+            recordPoint(newPos, lambda);
+            ray.x = newPos.x; ray.y = newPos.y; ray.z = newPos.z;
+            if (crossesEquatorialPlane(prevPos, newPos) && crossDisk(newPos, newPos - prevPos)) { hitDir = newPos - prevPos; hitDisk = true; break; }
+            if (disk_model == DISK_THICK && !sampleThickDisk(prevPos, newPos, glow, transmittance)) { absorbed = true; break; }
+            if (interceptObject(ray)) { hitObject = true; break; }
+            if (render_channel == CHANNEL_DEFLECTION) swept += sweptAngle(prevPos, newPos);
+            lastDir = newPos - prevPos;
+            prevPos = newPos;
+            if (newR > escape && dot(newPos, dx) < 0.0) break;
+        }
+    } else if (integration_mode == MODE_ADAPTIVE) {
+        bool kerr = spin != 0.0;
+        float a = spin * KERR_M;
+        float unit = kerr ? rs : 1.0; // length unit of the state, meters or rs
+        vec3 x, v;
+        if (kerr) {
+            KerrRay kray = initKerrRay(cam.camPos, dir, a);
+            x = vec3(kray.r, kray.theta, kray.phi);
+            v = vec3(kray.pr, kray.ptheta, 0.0);
+            adaptiveE = kray.E; adaptiveL = kray.L; adaptiveA = a;
+        } else {
+            x = vec3(ray.r, ray.theta, ray.phi);
+            v = vec3(ray.dr, ray.dtheta, ray.dphi);
+            adaptiveE = ray.E; adaptiveL = ray.L;
+        }
+        float horizon = kerr ? kerrHorizon(a) * max(1.01, 1.0 + horizon_epsilon) : rs * (1.0 + horizon_epsilon);
+        // an infalling photon below these radii can't turn around before the horizon: the
+        // photon sphere for Schwarzschild, just above the horizon for Kerr; rays aimed
+        // into the hole end here instead of shrinking their steps towards it forever
+        float capture = kerr ? kerrHorizon(a) * 1.05 : min(1.5 * rs, disk_r1);
+        float escape = escapeRadius() / unit;
+        float h = step_size / unit;
+        float hMin = 0.01 * h;
 
-    for (int i = 0; i < steps; ++i) {
-        if (intercept(ray, SagA_rs)) { hitBlackHole = true; break; }
-        rk4Step(ray, D_LAMBDA);
-        lambda += D_LAMBDA;
+        for (int i = 0; i < steps; ++i) {
+            if (x.x <= horizon || (x.x < capture && v.x < 0.0)) { hitBlackHole = true; break; }
+            if (x.x > escape && v.x > 0.0) break;
 
-        vec3 newPos = vec3(ray.x, ray.y, ray.z);
-        if (crossesEquatorialPlane(prevPos, newPos)) { hitDisk = true; break; }
-        if (interceptObject(ray)) { hitObject = true; break; }
-        prevPos = newPos;
-        if (ray.r > ESCAPE_R) break;
+            vec3 nx = x, nv = v;
+            float err = rk45Step(nx, nv, h) / tolerance;
+            if (err > 1.0 && h > hMin) {
+                // reject and retry with a smaller step
+                h = max(h * max(0.9 * pow(err, -0.25), 0.2), hMin);
+                continue;
+            }
+            x = nx; v = nv;
+            lambda += h * unit;
+            // steps are capped relative to r so the disk and objects are still sampled finely
+            h = clamp(h * min(0.9 * pow(max(err, 1e-6), -0.2), 5.0), hMin, max(0.05 * x.x, hMin));
+
+            vec3 newPos;
+            if (kerr) {
+                KerrRay kray;
+                kray.r = x.x; kray.theta = x.y; kray.phi = x.z;
+                newPos = kerrCartesian(kray, a);
+            } else {
+                newPos = x.x * vec3(sin(x.y) * cos(x.z), sin(x.y) * sin(x.z), cos(x.y));
+            }
+            recordPoint(newPos, lambda);
+            // the thick disk is sampled once a step, keep the steps through it short
+            if (disk_model == DISK_THICK && abs(diskHeight(newPos)) < thickness) h = max(min(h, 0.25 * thickness / unit), hMin);
+            vec3 hitPos;
+            if (diskCrossing(prevPos, newPos, hitPos) && crossDisk(hitPos, newPos - prevPos)) {
+                ray.x = hitPos.x; ray.y = hitPos.y; ray.z = hitPos.z;
+                hitDir = newPos - prevPos;
+                hitDisk = true;
+                break;
+            }
+            if (disk_model == DISK_THICK && !sampleThickDisk(prevPos, newPos, glow, transmittance)) { absorbed = true; break; }
+            ray.x = newPos.x; ray.y = newPos.y; ray.z = newPos.z;
+            if (interceptObject(ray)) { hitObject = true; break; }
+            if (render_channel == CHANNEL_DEFLECTION) swept += sweptAngle(prevPos, newPos);
+            lastDir = newPos - prevPos;
+            prevPos = newPos;
+        }
+    } else if (spin == 0.0) {
+        for (int i = 0; i < steps; ++i) {
+            if (intercept(ray, rs * (1.0 + horizon_epsilon))) { hitBlackHole = true; break; }
+            rk4Step(ray, step_size);
+            lambda += step_size;
+
+            vec3 newPos = vec3(ray.x, ray.y, ray.z);
+            recordPoint(newPos, lambda);
+            if (crossesEquatorialPlane(prevPos, newPos) && crossDisk(newPos, newPos - prevPos)) { hitDir = newPos - prevPos; hitDisk = true; break; }
+            if (disk_model == DISK_THICK && !sampleThickDisk(prevPos, newPos, glow, transmittance)) { absorbed = true; break; }
+            if (interceptObject(ray)) { hitObject = true; break; }
+            if (render_channel == CHANNEL_DEFLECTION) swept += sweptAngle(prevPos, newPos);
+            lastDir = newPos - prevPos;
+            prevPos = newPos;
+            if (ray.r > ESCAPE_R) break;
+        }
+    } else {
+        // spin is a/M, with M = 0.5 in rs units
+        float a = spin * KERR_M;
+        float horizon = kerrHorizon(a) * max(1.01, 1.0 + horizon_epsilon);
+        float dL = step_size / rs;
+        KerrRay kray = initKerrRay(cam.camPos, dir, a);
+
+        for (int i = 0; i < steps; ++i) {
+            if (kray.r <= horizon) { hitBlackHole = true; break; }
+            kerrStep(kray, a, dL);
+            lambda += step_size;
+
+            vec3 newPos = kerrCartesian(kray, a);
+            recordPoint(newPos, lambda);
+            ray.x = newPos.x; ray.y = newPos.y; ray.z = newPos.z;
+            if (crossesEquatorialPlane(prevPos, newPos) && crossDisk(newPos, newPos - prevPos)) { hitDir = newPos - prevPos; hitDisk = true; break; }
+            if (disk_model == DISK_THICK && !sampleThickDisk(prevPos, newPos, glow, transmittance)) { absorbed = true; break; }
+            if (interceptObject(ray)) { hitObject = true; break; }
+            if (render_channel == CHANNEL_DEFLECTION) swept += sweptAngle(prevPos, newPos);
+            lastDir = newPos - prevPos;
+            prevPos = newPos;
+            if (kray.r * rs > ESCAPE_R) break;
+        }
     }
 
     if (hitDisk) {
         double r = length(vec3(ray.x, ray.y, ray.z)) / disk_r2;
-        vec3 diskColor = vec3(1.0, r, 0.2);
         //r = 1.0 - abs(r - 0.5) * 2.0;
-        color = vec4(diskColor, r);
+        color = vec4(diskLight, r);
 
-    } else if (hitBlackHole) {
+    } else if (hitBlackHole || absorbed) {
         color = vec4(0.0, 0.0, 0.0, 1.0);
 
     } else if (hitObject) {
@@ -167,11 +855,52 @@ void main() {
         float diff = max(dot(N, V), 0.0);
         float intensity = ambient + (1.0 - ambient) * diff;
         vec3 shaded = objectColor.rgb * intensity;
+        if (redshift_enabled != 0) {
+            shaded = applyFrequencyShift(shaded, gravitationalShift(length(P)));
+        }
         color = vec4(shaded, objectColor.a);
 
+    } else if (background_enabled != 0) {
+        color = vec4(background(normalize(lastDir)), 1.0);
     } else {
-        color = vec4(0.0);
+        // the stars shine on top of the plain backdrop
+        vec3 sky = background_color.rgb;
+        if (starfield_enabled != 0) {
+            sky += starfield(normalize(lastDir));
+        }
+        color = vec4(sky, 1.0);
+    }
+    if (!hitDisk) {
+        // the lower order images the ray passed through on its way
+        color.rgb += diskLight;
+    }
+    if (disk_model == DISK_THICK) {
+        color.rgb = glow + transmittance * color.rgb;
+    }
+    if (render_channel != CHANNEL_COLOR) {
+        vec3 endDir = hitDisk ? hitDir : lastDir;
+        float value;
+        if (render_channel == CHANNEL_TERMINATION) {
+            bool hit = hitDisk || hitObject || absorbed;
+            bool escaped = !hitBlackHole && !hit && length(vec3(ray.x, ray.y, ray.z)) > escapeRadius();
+            value = terminationValue(hitBlackHole, hit, escaped);
+        } else {
+            value = clamp(channelValue(vec3(ray.x, ray.y, ray.z), dir, endDir, swept, lambda, hitBlackHole || absorbed, hitDisk, hitObject), 0.0, 1.0);
+        }
+        // on the texel centers, so 0 and 1 are the map's ends
+        float size = float(textureSize(colormapTex, 0).x);
+        color = vec4(texture(colormapTex, vec2((value * (size - 1.0) + 0.5) / size, 0.5)).rgb, 1.0);
+    }
+
+    if (recording) {
+        debug_count = recorded;
+        return;
     }
 
+    // progressive accumulation: fold this frame into the mean of the earlier ones
+    if (frame_index > 0) {
+        color = mix(imageLoad(accumImage, pix), color, 1.0 / float(frame_index + 1));
+    }
+    imageStore(accumImage, pix, color);
     imageStore(outImage, pix, color);
 }
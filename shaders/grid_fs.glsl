@@ -1,5 +1,8 @@
 #version 330 core
+in float edge;
+uniform vec4 line_color;
 out vec4 FragColor;
 void main() {
-    FragColor = vec4(0.5, 0.5, 0.5, 0.7); // translucent blue lines
+    // translucent lines fading out towards the edge
+    FragColor = vec4(line_color.rgb, line_color.a * (1.0 - smoothstep(0.6, 1.0, edge)));
 }
@@ -1,6 +1,54 @@
-#version 330 core
+#version 430 core
 layout(location = 0) in vec3 aPos;
 uniform mat4 viewProj;
+uniform float extent;     // distance from the center to the grid edge in meters
+uniform float warp_scale; // 1 is the true embedding, larger exaggerates the dips
+uniform bool bend;        // false draws aPos as it is, for lines that aren't the grid
+out float edge;           // 0 at the center, 1 at the edge
+
+layout(std140, binding = 3) uniform Objects {
+    int numObjects;
+    vec4 objPosRadius[16];
+    vec4 objColor[16];
+    float  mass[16];
+};
+
+layout(std140, binding = 4) uniform BlackHole {
+    float spin;
+    float rs;   // Schwarzschild radius 2GM/c^2 in meters
+    int   redshift_enabled;
+    float redshift_exaggeration;
+    int   observer_region;
+    int   _bhPad0, _bhPad1, _bhPad2;
+};
+
+const float G = 6.67430e-11;
+const float C = 299792458.0;
+
+// Height of Flamm's paraboloid, the embedding diagram of the Schwarzschild
+// geometry, at distance r from a mass with Schwarzschild radius r_s. Inside the
+// horizon there is no embedding, the sheet stays flat at the throat.
+float flamm(float r, float r_s) {
+    return 2.0 * sqrt(r_s * max(r - r_s, 0.0));
+}
+
+// Each mass pulls the sheet down relative to the height its paraboloid reaches
+// at the grid edge, so the edge stays level with the plane.
+float dip(vec2 center, float r_s) {
+    float r = length(aPos.xz - center);
+    return flamm(r, r_s) - flamm(extent, r_s);
+}
+
 void main() {
-    gl_Position = viewProj * vec4(aPos, 1.0);
+    if (!bend) {
+        edge = 0.0;
+        gl_Position = viewProj * vec4(aPos, 1.0);
+        return;
+    }
+    float y = dip(vec2(0.0), rs);
+    for (int i = 0; i < numObjects; ++i) {
+        y += dip(objPosRadius[i].xz, 2.0 * G * mass[i] / (C * C));
+    }
+    edge = length(aPos.xz) / extent;
+    gl_Position = viewProj * vec4(aPos.x, warp_scale * y, aPos.z, 1.0);
 }
@@ -1,7 +1,81 @@
 #version 330 core
     in vec2 TexCoord;
     out vec4 FragColor;
-    uniform sampler2D screenTexture;
+    uniform sampler2D screenTexture; // linear HDR radiance from the compute shader
+    uniform sampler2D bloomTexture;
+    uniform float bloomIntensity; // 0 when bloom is off
+    uniform sampler2D flareTexture; // 1x1: luminance and texture coordinates of the image's brightest spot
+    uniform float flareIntensity;   // 0 when the lens flare is off
+    uniform float exposure;       // linear multiplier, 2^EV
+    uniform int supersampling;    // compute texels per output pixel along each axis
+    uniform bool false_color;     // the image holds colormap colors, shown as they are
+
+    // Narkowicz's fit of the ACES filmic curve: highlights roll off smoothly towards 1
+    // instead of clipping, while the darks stay close to linear
+    vec3 acesFilm(vec3 x) {
+        return clamp((x * (2.51 * x + 0.03)) / (x * (2.43 * x + 0.59) + 0.14), 0.0, 1.0);
+    }
+
+    // Box filters the supersampling x supersampling taps one compute texel apart around the
+    // pixel. When the compute texture is exactly that many times the window they land on the
+    // texel centers of the pixel's block; at any other size each tap is the usual linear upscale
+    vec3 screenRadiance() {
+        vec2 texel = 1.0 / vec2(textureSize(screenTexture, 0));
+        float center = 0.5 * float(supersampling - 1);
+        vec3 sum = vec3(0.0);
+        for (int y = 0; y < supersampling; ++y) {
+            for (int x = 0; x < supersampling; ++x) {
+                sum += texture(screenTexture, TexCoord + (vec2(x, y) - center) * texel).rgb;
+            }
+        }
+        return sum / float(supersampling * supersampling);
+    }
+
+    // Ghosts of the brightest spot strung along the line from it through the image center, like
+    // the reflections between the elements of a camera lens. Only HDR luminance above 1, brighter
+    // than white before the exposure, makes them, and they grow with the log of the excess
+    const int GHOSTS = 5;
+    const float ghostOffsets[GHOSTS] = float[](0.4, 0.8, 1.3, 1.7, 2.1); // 0 at the spot, 1 at the center, 2 across it
+    const float ghostRadii[GHOSTS] = float[](0.03, 0.07, 0.02, 0.1, 0.05); // fractions of the image height
+    const vec3 ghostTints[GHOSTS] = vec3[](vec3(1.0, 0.6, 0.3), vec3(0.4, 0.7, 1.0), vec3(1.0, 0.9, 0.6),
+                                           vec3(0.5, 1.0, 0.6), vec3(0.8, 0.5, 1.0));
+
+    vec3 lensFlare() {
+        vec3 brightest = texelFetch(flareTexture, ivec2(0), 0).xyz;
+        float strength = 0.05 * flareIntensity * log2(1.0 + max(brightest.x - 1.0, 0.0));
+        if (strength <= 0.0) {
+            return vec3(0.0);
+        }
+        vec2 size = vec2(textureSize(screenTexture, 0));
+        vec2 aspect = vec2(size.x / size.y, 1.0); // keeps the ghosts round on a wide image
+        vec2 spot = brightest.yz;
+        vec3 sum = vec3(0.0);
+        for (int i = 0; i < GHOSTS; ++i) {
+            vec2 center = spot + (vec2(0.5) - spot) * ghostOffsets[i];
+            float r = length((TexCoord - center) * aspect) / ghostRadii[i];
+            // a disc fading out towards a soft rim
+            sum += ghostTints[i] * (1.0 - smoothstep(0.7, 1.0, r));
+        }
+        return sum * strength;
+    }
+
+    // The sRGB transfer curve, a short linear segment near black and a 2.4 power above it. The
+    // default framebuffer is plain RGBA8, so the encoding is done here rather than by
+    // GL_FRAMEBUFFER_SRGB, which would also re-encode the overlay and grid colors drawn after
+    // the quad, already given in display values. Screenshots read back these same bytes
+    vec3 linearToSrgb(vec3 c) {
+        c = clamp(c, 0.0, 1.0);
+        return mix(c * 12.92, 1.055 * pow(c, vec3(1.0 / 2.4)) - 0.055, step(0.0031308, c));
+    }
+
     void main() {
-        FragColor = texture(screenTexture, TexCoord);
-}
\ No newline at end of file
+        vec3 radiance = screenRadiance();
+        if (false_color) {
+            FragColor = vec4(radiance, 1.0);
+            return;
+        }
+        radiance += texture(bloomTexture, TexCoord).rgb * bloomIntensity;
+        radiance += lensFlare();
+        vec3 mapped = acesFilm(radiance * exposure);
+        FragColor = vec4(linearToSrgb(mapped), 1.0);
+}
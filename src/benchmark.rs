@@ -0,0 +1,191 @@
+use gl::types::{GLint, GLuint, GLuint64};
+
+/// GPU time between `start` and `stop`, measured with a pair of `glQueryCounter` timestamps.
+pub struct GpuTimer {
+    queries: [GLuint; 2],
+}
+
+impl GpuTimer {
+    /// None when the context counts no timestamp bits, which GL allows.
+    pub fn new() -> Option<Self> {
+        let mut bits: GLint = 0;
+        unsafe { gl::GetQueryiv(gl::TIMESTAMP, gl::QUERY_COUNTER_BITS, &mut bits); }
+        if bits == 0 {
+            return None;
+        }
+        let mut queries = [0; 2];
+        unsafe { gl::GenQueries(2, queries.as_mut_ptr()); }
+        Some(GpuTimer { queries })
+    }
+
+    pub fn start(&self) {
+        unsafe { gl::QueryCounter(self.queries[0], gl::TIMESTAMP); }
+    }
+
+    pub fn stop(&self) {
+        unsafe { gl::QueryCounter(self.queries[1], gl::TIMESTAMP); }
+    }
+
+    /// Milliseconds from `start` to `stop`, waiting for the GPU to get there.
+    pub fn elapsed_ms(&self) -> f64 {
+        let (mut begin, mut end): (GLuint64, GLuint64) = (0, 0);
+        unsafe {
+            gl::GetQueryObjectui64v(self.queries[0], gl::QUERY_RESULT, &mut begin);
+            gl::GetQueryObjectui64v(self.queries[1], gl::QUERY_RESULT, &mut end);
+        }
+        end.saturating_sub(begin) as f64 * 1e-6
+    }
+}
+
+impl Drop for GpuTimer {
+    fn drop(&mut self) {
+        unsafe { gl::DeleteQueries(2, self.queries.as_ptr()); }
+    }
+}
+
+/// Frames the profiler keeps in flight. A frame's timestamps are read back when its slot comes
+/// round again, by which time the GPU has long finished it, so the readback never stalls.
+pub const PROFILER_FRAMES: usize = 4;
+
+/// GPU time spent in the parts of one frame, in nanoseconds.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct GpuTimings {
+    pub compute_ns: u64, // the geodesic dispatch
+    pub post_ns: u64, // bloom, tone mapping blit and grid
+    pub total_ns: u64, // the whole frame, the overlay included
+}
+
+/// Times every viewer frame on the GPU with timestamps at its start, after the compute dispatch,
+/// after post-processing and at its end, using a ring of `PROFILER_FRAMES` sets of queries.
+pub struct FrameProfiler {
+    queries: [[GLuint; 4]; PROFILER_FRAMES],
+    pending: [bool; PROFILER_FRAMES], // issued and not read back yet
+    current: usize,
+    latest: Option<GpuTimings>,
+}
+
+impl FrameProfiler {
+    /// None when the context counts no timestamp bits, like `GpuTimer::new`.
+    pub fn new() -> Option<Self> {
+        let mut bits: GLint = 0;
+        unsafe { gl::GetQueryiv(gl::TIMESTAMP, gl::QUERY_COUNTER_BITS, &mut bits); }
+        if bits == 0 {
+            return None;
+        }
+        let mut queries = [[0; 4]; PROFILER_FRAMES];
+        for set in &mut queries {
+            unsafe { gl::GenQueries(4, set.as_mut_ptr()); }
+        }
+        Some(FrameProfiler { queries, pending: [false; PROFILER_FRAMES], current: 0, latest: None })
+    }
+
+    /// The most recent frame whose timestamps are in, a few frames behind the one being drawn.
+    pub fn latest(&self) -> Option<GpuTimings> {
+        self.latest
+    }
+
+    /// Collects the frame that last used this slot if the GPU is done with it, then starts timing.
+    pub fn begin_frame(&mut self) {
+        let set = self.queries[self.current];
+        if self.pending[self.current] {
+            let mut available = 0;
+            unsafe { gl::GetQueryObjectiv(set[3], gl::QUERY_RESULT_AVAILABLE, &mut available); }
+            if available != 0 {
+                let mut stamps: [GLuint64; 4] = [0; 4];
+                for (query, stamp) in set.iter().zip(&mut stamps) {
+                    unsafe { gl::GetQueryObjectui64v(*query, gl::QUERY_RESULT, stamp); }
+                }
+                self.latest = Some(GpuTimings {
+                    compute_ns: stamps[1].saturating_sub(stamps[0]),
+                    post_ns: stamps[2].saturating_sub(stamps[1]),
+                    total_ns: stamps[3].saturating_sub(stamps[0]),
+                });
+            }
+            // otherwise the sample is lost, the queries are simply issued again
+            self.pending[self.current] = false;
+        }
+        self.stamp(0);
+    }
+
+    pub fn compute_done(&self) {
+        self.stamp(1);
+    }
+
+    pub fn post_done(&self) {
+        self.stamp(2);
+    }
+
+    pub fn end_frame(&mut self) {
+        self.stamp(3);
+        self.pending[self.current] = true;
+        self.current = (self.current + 1) % PROFILER_FRAMES;
+    }
+
+    fn stamp(&self, index: usize) {
+        unsafe { gl::QueryCounter(self.queries[self.current][index], gl::TIMESTAMP); }
+    }
+
+    /// Frees the queries, the context they were made in must still be current.
+    pub fn delete(&mut self) {
+        for set in &self.queries {
+            unsafe { gl::DeleteQueries(4, set.as_ptr()); }
+        }
+    }
+}
+
+/// Summary of the frame times of one benchmark run, in milliseconds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FrameStats {
+    pub min: f64,
+    pub median: f64,
+    pub p99: f64,
+    pub max: f64,
+}
+
+impl FrameStats {
+    /// None for an empty list. Percentiles take the nearest rank, so they are always a measured time.
+    pub fn from_samples(samples: &[f64]) -> Option<Self> {
+        if samples.is_empty() {
+            return None;
+        }
+        let mut sorted = samples.to_vec();
+        sorted.sort_by(f64::total_cmp);
+        let rank = |q: f64| sorted[((q * sorted.len() as f64).ceil() as usize).clamp(1, sorted.len()) - 1];
+        Some(FrameStats { min: sorted[0], median: rank(0.5), p99: rank(0.99), max: sorted[sorted.len() - 1] })
+    }
+}
+
+/// One row of the benchmark table: a compute resolution and step count and how long it took.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BenchmarkResult {
+    pub width: u32,
+    pub height: u32,
+    pub steps: i32,
+    pub frames: FrameStats, // wall time from submitting a frame until it finished
+    pub gpu_ms: Option<f64>, // mean GPU time per frame, when the context has timestamps
+}
+
+/// Lays the results out as a plain text table with one line per run.
+pub fn format_table(results: &[BenchmarkResult]) -> String {
+    let mut table = format!("{:>11} {:>7} {:>8} {:>8} {:>8} {:>8} {:>8}\n", "resolution", "steps", "min ms", "median", "p99", "max", "gpu ms");
+    for result in results {
+        let gpu = result.gpu_ms.map_or("-".to_string(), |ms| format!("{:.2}", ms));
+        let stats = result.frames;
+        table += &format!("{:>11} {:>7} {:>8.2} {:>8.2} {:>8.2} {:>8.2} {:>8}\n", format!("{}x{}", result.width, result.height),
+            result.steps, stats.min, stats.median, stats.p99, stats.max, gpu);
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frame_stats_take_nearest_rank_percentiles() {
+        let samples: Vec<f64> = (1..=200).rev().map(f64::from).collect();
+        assert_eq!(FrameStats::from_samples(&samples), Some(FrameStats { min: 1.0, median: 100.0, p99: 198.0, max: 200.0 }));
+        assert_eq!(FrameStats::from_samples(&[4.0]), Some(FrameStats { min: 4.0, median: 4.0, p99: 4.0, max: 4.0 }));
+        assert_eq!(FrameStats::from_samples(&[]), None);
+    }
+}
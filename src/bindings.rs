@@ -0,0 +1,334 @@
+use std::collections::HashMap;
+use glfw::Key;
+
+/// Something a key does in the viewer. Holding Shift or Ctrl on a command's key still picks its
+/// variants, Shift + the grid key cycles the warp for one, except for the few that run only with
+/// Ctrl held, see `needs_control`. The number keys that recall bookmarks, 0 that resets and the
+/// camera's movement keys are not commands and stay fixed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Command {
+    Quit,
+    Exit,
+    SaveScene,
+    ToggleSplit,
+    Reload,
+    Screenshot,
+    Pause,
+    PhotoMode,
+    StepFrame,
+    Reset,
+    ToggleGravity,
+    ToggleGrid,
+    ToggleOverlay,
+    TogglePanel,
+    ToggleTimeline,
+    ToggleObjectPlacement,
+    SpawnParticle,
+    TogglePhotonOrbit,
+    ToggleCrosshair,
+    ToggleObserverRegion,
+    ToggleFullscreen,
+    ToggleBloom,
+    ToggleVsync,
+    ToggleTiled,
+    ToggleDynamicResolution,
+    ToggleIntegrationMode,
+    ToggleDoppler,
+    ToggleRedshift,
+    ToggleStarfield,
+    Recenter,
+    CameraMode,
+    FrameDisk,
+    CycleQuality,
+    CycleGeodesicDebug,
+    ExportGeodesic,
+    CycleOutputAspect,
+    CycleComputeVariant,
+    CycleDiskProfile,
+    CycleDiskModel,
+    CycleImageOrder,
+    CycleChannel,
+    CycleRedshiftExaggeration,
+    IncreaseDiskInnerRadius,
+    DecreaseDiskInnerRadius,
+    IncreaseMass,
+    DecreaseMass,
+    IncreaseSpin,
+    DecreaseSpin,
+    IncreaseResolution,
+    DecreaseResolution,
+    IncreaseFov,
+    DecreaseFov,
+    NextParam,
+    PreviousParam,
+}
+
+/// Every command with its name in the `[keys]` table of a scene file and its default key.
+pub const COMMANDS: [(Command, &str, Key); 54] = [
+    (Command::Quit, "quit", Key::Escape),
+    (Command::Exit, "exit", Key::Q),
+    (Command::SaveScene, "save_scene", Key::S),
+    (Command::ToggleSplit, "toggle_split", Key::D),
+    (Command::Reload, "reload", Key::R),
+    (Command::Screenshot, "screenshot", Key::F12),
+    (Command::Pause, "pause", Key::Space),
+    (Command::PhotoMode, "photo_mode", Key::End),
+    (Command::StepFrame, "step_frame", Key::N),
+    (Command::Reset, "reset", Key::Home),
+    (Command::ToggleGravity, "toggle_gravity", Key::G),
+    (Command::ToggleGrid, "toggle_grid", Key::T),
+    (Command::ToggleOverlay, "toggle_overlay", Key::F1),
+    (Command::TogglePanel, "toggle_panel", Key::Tab),
+    (Command::ToggleTimeline, "toggle_timeline", Key::P),
+    (Command::ToggleObjectPlacement, "toggle_object_placement", Key::O),
+    (Command::SpawnParticle, "spawn_particle", Key::Backspace),
+    (Command::TogglePhotonOrbit, "toggle_photon_orbit", Key::I),
+    (Command::ToggleCrosshair, "toggle_crosshair", Key::Slash),
+    (Command::ToggleObserverRegion, "toggle_observer_region", Key::Backslash),
+    (Command::ToggleFullscreen, "toggle_fullscreen", Key::F11),
+    (Command::ToggleBloom, "toggle_bloom", Key::F4),
+    (Command::ToggleVsync, "toggle_vsync", Key::F5),
+    (Command::ToggleTiled, "toggle_tiled", Key::F6),
+    (Command::ToggleDynamicResolution, "toggle_dynamic_resolution", Key::F10),
+    (Command::ToggleIntegrationMode, "toggle_integration_mode", Key::F3),
+    (Command::ToggleDoppler, "toggle_doppler", Key::B),
+    (Command::ToggleRedshift, "toggle_redshift", Key::H),
+    (Command::ToggleStarfield, "toggle_starfield", Key::V),
+    (Command::Recenter, "recenter", Key::C),
+    (Command::CameraMode, "camera_mode", Key::F),
+    (Command::FrameDisk, "frame_disk", Key::Enter),
+    (Command::CycleQuality, "cycle_quality", Key::F2),
+    (Command::CycleGeodesicDebug, "cycle_geodesic_debug", Key::F7),
+    (Command::ExportGeodesic, "export_geodesic", Key::F8),
+    (Command::CycleOutputAspect, "cycle_output_aspect", Key::F9),
+    (Command::CycleComputeVariant, "cycle_compute_variant", Key::GraveAccent),
+    (Command::CycleDiskProfile, "cycle_disk_profile", Key::U),
+    (Command::CycleDiskModel, "cycle_disk_model", Key::M),
+    (Command::CycleImageOrder, "cycle_image_order", Key::Insert),
+    (Command::CycleChannel, "cycle_channel", Key::Y),
+    (Command::CycleRedshiftExaggeration, "cycle_redshift_exaggeration", Key::J),
+    (Command::IncreaseDiskInnerRadius, "increase_disk_inner_radius", Key::X),
+    (Command::DecreaseDiskInnerRadius, "decrease_disk_inner_radius", Key::Z),
+    (Command::IncreaseMass, "increase_mass", Key::Period),
+    (Command::DecreaseMass, "decrease_mass", Key::Comma),
+    (Command::IncreaseSpin, "increase_spin", Key::L),
+    (Command::DecreaseSpin, "decrease_spin", Key::K),
+    (Command::IncreaseResolution, "increase_resolution", Key::Equal),
+    (Command::DecreaseResolution, "decrease_resolution", Key::Minus),
+    (Command::IncreaseFov, "increase_fov", Key::Semicolon),
+    (Command::DecreaseFov, "decrease_fov", Key::Apostrophe),
+    (Command::NextParam, "next_param", Key::RightBracket),
+    (Command::PreviousParam, "previous_param", Key::LeftBracket),
+];
+
+// Keys the camera reads on its own, see `Camera::process_key`
+const CAMERA_KEYS: [Key; 12] = [Key::W, Key::A, Key::S, Key::D, Key::Q, Key::E,
+    Key::Up, Key::Down, Key::Left, Key::Right, Key::PageUp, Key::PageDown];
+
+// The number keys that recall bookmarks 1 to 9
+const BOOKMARK_KEYS: [Key; 9] = [Key::Num1, Key::Num2, Key::Num3, Key::Num4, Key::Num5, Key::Num6, Key::Num7, Key::Num8, Key::Num9];
+
+// The number key beside the bookmarks that resets the view, whatever key the reset command has
+const RESET_KEY: Key = Key::Num0;
+
+// Names of the keys a scene file can bind, matched without regard to case
+const KEY_NAMES: [(&str, Key); 70] = [
+    ("A", Key::A), ("B", Key::B), ("C", Key::C), ("D", Key::D), ("E", Key::E), ("F", Key::F), ("G", Key::G),
+    ("H", Key::H), ("I", Key::I), ("J", Key::J), ("K", Key::K), ("L", Key::L), ("M", Key::M), ("N", Key::N),
+    ("O", Key::O), ("P", Key::P), ("Q", Key::Q), ("R", Key::R), ("S", Key::S), ("T", Key::T), ("U", Key::U),
+    ("V", Key::V), ("W", Key::W), ("X", Key::X), ("Y", Key::Y), ("Z", Key::Z),
+    ("0", Key::Num0), ("1", Key::Num1), ("2", Key::Num2), ("3", Key::Num3), ("4", Key::Num4),
+    ("5", Key::Num5), ("6", Key::Num6), ("7", Key::Num7), ("8", Key::Num8), ("9", Key::Num9),
+    ("F1", Key::F1), ("F2", Key::F2), ("F3", Key::F3), ("F4", Key::F4), ("F5", Key::F5), ("F6", Key::F6),
+    ("F7", Key::F7), ("F8", Key::F8), ("F9", Key::F9), ("F10", Key::F10), ("F11", Key::F11), ("F12", Key::F12),
+    ("Escape", Key::Escape), ("Tab", Key::Tab), ("Space", Key::Space), ("Enter", Key::Enter),
+    ("Backspace", Key::Backspace), ("Insert", Key::Insert), ("Delete", Key::Delete),
+    ("Home", Key::Home), ("End", Key::End), ("Apostrophe", Key::Apostrophe), ("Comma", Key::Comma),
+    ("Minus", Key::Minus), ("Period", Key::Period), ("Slash", Key::Slash), ("Semicolon", Key::Semicolon),
+    ("Equal", Key::Equal), ("LeftBracket", Key::LeftBracket), ("Backslash", Key::Backslash),
+    ("RightBracket", Key::RightBracket), ("GraveAccent", Key::GraveAccent),
+    ("PageUp", Key::PageUp), ("PageDown", Key::PageDown),
+];
+
+impl Command {
+    /// The command's name in the `[keys]` table, `None` for a name that isn't one.
+    pub fn from_name(name: &str) -> Option<Self> {
+        COMMANDS.iter().find(|(_, n, _)| *n == name).map(|(command, _, _)| *command)
+    }
+
+    pub fn name(self) -> &'static str {
+        COMMANDS.iter().find(|(command, _, _)| *command == self).map(|(_, name, _)| *name).unwrap()
+    }
+
+    fn default_key(self) -> Key {
+        COMMANDS.iter().find(|(command, _, _)| *command == self).map(|(_, _, key)| *key).unwrap()
+    }
+
+    /// Whether holding the key down repeats the command, true for the ones that step a value.
+    pub fn repeats(self) -> bool {
+        matches!(self, Command::StepFrame | Command::IncreaseDiskInnerRadius | Command::DecreaseDiskInnerRadius
+            | Command::IncreaseMass | Command::DecreaseMass | Command::IncreaseSpin | Command::DecreaseSpin
+            | Command::IncreaseResolution | Command::DecreaseResolution | Command::IncreaseFov | Command::DecreaseFov
+            | Command::NextParam | Command::PreviousParam)
+    }
+
+    /// Whether the command runs on its key only with Ctrl held, true for the shortcuts that quit,
+    /// save the scene and split the view. Those keys still move the camera or run another command
+    /// without Ctrl.
+    pub fn needs_control(self) -> bool {
+        matches!(self, Command::Exit | Command::SaveScene | Command::ToggleSplit)
+    }
+}
+
+/// The key scene files write for `key`, `None` for keys they can't bind.
+pub fn key_name(key: Key) -> Option<&'static str> {
+    KEY_NAMES.iter().find(|(_, k)| *k == key).map(|(name, _)| *name)
+}
+
+/// The key called `name`, like "G", "F12" or "LeftBracket", in any case.
+pub fn parse_key(name: &str) -> Option<Key> {
+    KEY_NAMES.iter().find(|(n, _)| n.eq_ignore_ascii_case(name)).map(|(_, key)| *key)
+}
+
+/// Which key runs each command, the defaults of `COMMANDS` unless a scene's `[keys]` table
+/// rebinds them.
+#[derive(Clone, Debug, PartialEq)]
+pub struct KeyBindings {
+    keys: HashMap<Command, Key>,
+}
+
+impl Default for KeyBindings {
+    fn default() -> Self {
+        KeyBindings { keys: COMMANDS.iter().map(|(command, _, key)| (*command, *key)).collect() }
+    }
+}
+
+impl KeyBindings {
+    pub fn bind(&mut self, command: Command, key: Key) {
+        self.keys.insert(command, key);
+    }
+
+    pub fn key(&self, command: Command) -> Key {
+        self.keys[&command]
+    }
+
+    /// The command `key` runs. A key bound twice runs the command listed first in `COMMANDS`.
+    pub fn command(&self, key: Key) -> Option<Command> {
+        COMMANDS.iter().map(|(command, _, _)| *command)
+            .find(|command| !command.needs_control() && self.keys[command] == key)
+    }
+
+    /// The command Ctrl + `key` runs ahead of `command`, one of those that need Ctrl.
+    pub fn control_command(&self, key: Key) -> Option<Command> {
+        COMMANDS.iter().map(|(command, _, _)| *command)
+            .find(|command| command.needs_control() && self.keys[command] == key)
+    }
+
+    /// The commands bound to keys other than their default, in the order of `COMMANDS`.
+    pub fn changed(&self) -> Vec<(Command, Key)> {
+        COMMANDS.iter()
+            .map(|(command, _, _)| (*command, self.key(*command)))
+            .filter(|(command, key)| *key != command.default_key())
+            .collect()
+    }
+
+    /// One message for each key that runs more than one command, with or without Ctrl, or also
+    /// moves the camera, recalls a bookmark or resets, for warning about when the bindings are
+    /// loaded.
+    pub fn conflicts(&self) -> Vec<String> {
+        let mut messages = Vec::new();
+        let mut seen: Vec<(Key, bool)> = Vec::new();
+        for (command, _, _) in &COMMANDS {
+            let key = self.key(*command);
+            let control = command.needs_control();
+            if seen.contains(&(key, control)) {
+                continue;
+            }
+            seen.push((key, control));
+            let name = key_name(key).unwrap_or("?");
+            let commands: Vec<&str> = COMMANDS.iter().map(|(c, n, _)| (*c, *n))
+                .filter(|(c, _)| c.needs_control() == control && self.key(*c) == key).map(|(_, n)| n).collect();
+            if commands.len() > 1 {
+                let chord = if control { format!("Ctrl+{}", name) } else { name.to_string() };
+                messages.push(format!("key {} is bound to {}, only {} will run", chord, commands.join(", "), commands[0]));
+            }
+            if control {
+                // Ctrl + the key no longer picks the other command's Ctrl variant
+                if let Some(other) = self.command(key) {
+                    messages.push(format!("key Ctrl+{} runs {} instead of {} with Ctrl held", name, commands[0], other.name()));
+                }
+                continue;
+            }
+            if CAMERA_KEYS.contains(&key) {
+                messages.push(format!("key {} bound to {} also moves the camera", name, commands[0]));
+            }
+            if BOOKMARK_KEYS.contains(&key) {
+                messages.push(format!("key {} bound to {} no longer recalls its bookmark", name, commands[0]));
+            }
+            if key == RESET_KEY {
+                messages.push(format!("key {} bound to {} no longer resets the view", name, commands[0]));
+            }
+        }
+        messages
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn defaults_match_the_viewer_keys_without_conflicts() {
+        let bindings = KeyBindings::default();
+        assert_eq!(bindings.command(Key::G), Some(Command::ToggleGravity));
+        assert_eq!(bindings.command(Key::Escape), Some(Command::Quit));
+        assert_eq!(bindings.command(Key::W), None, "camera keys are not commands");
+        assert!(bindings.conflicts().is_empty(), "{:?}", bindings.conflicts());
+        assert!(bindings.changed().is_empty());
+        // every command has a name that reads back and a key that has one
+        for (command, name, key) in COMMANDS {
+            assert_eq!(Command::from_name(name), Some(command));
+            assert_eq!(key_name(key).and_then(parse_key), Some(key));
+        }
+    }
+
+    #[test]
+    fn rebinding_reports_duplicates_and_camera_keys() {
+        let mut bindings = KeyBindings::default();
+        bindings.bind(Command::Reload, Key::G);
+        bindings.bind(Command::Pause, Key::W);
+        assert_eq!(bindings.command(Key::G), Some(Command::Reload), "the first command in the table wins");
+        assert_eq!(bindings.command(Key::R), None);
+        assert_eq!(bindings.changed(), vec![(Command::Reload, Key::G), (Command::Pause, Key::W)]);
+        let conflicts = bindings.conflicts();
+        assert_eq!(conflicts.len(), 2, "{:?}", conflicts);
+        assert!(conflicts[0].contains("reload, toggle_gravity"));
+        assert!(conflicts[1].contains("moves the camera"));
+    }
+
+    #[test]
+    fn control_shortcuts_rebind_apart_from_the_plain_keys() {
+        let mut bindings = KeyBindings::default();
+        assert_eq!(bindings.control_command(Key::S), Some(Command::SaveScene));
+        assert_eq!(bindings.command(Key::S), None, "S alone moves the camera");
+        assert_eq!(bindings.control_command(Key::G), None);
+        bindings.bind(Command::SaveScene, Key::T);
+        bindings.bind(Command::ToggleSplit, Key::T);
+        bindings.bind(Command::Reset, Key::Num0);
+        assert_eq!(bindings.control_command(Key::T), Some(Command::SaveScene));
+        assert_eq!(bindings.command(Key::T), Some(Command::ToggleGrid));
+        let conflicts = bindings.conflicts();
+        assert_eq!(conflicts.len(), 3, "{:?}", conflicts);
+        assert!(conflicts[0].contains("Ctrl+T is bound to save_scene, toggle_split"));
+        assert!(conflicts[1].contains("instead of toggle_grid"));
+        assert!(conflicts[2].contains("no longer resets"));
+    }
+
+    #[test]
+    fn key_names_ignore_case() {
+        assert_eq!(parse_key("f12"), Some(Key::F12));
+        assert_eq!(parse_key("leftbracket"), Some(Key::LeftBracket));
+        assert_eq!(parse_key("7"), Some(Key::Num7));
+        assert_eq!(parse_key("Shift"), None);
+    }
+}
@@ -0,0 +1,191 @@
+use glm::vec3;
+use crate::camera::CameraState;
+use crate::engine::EngineError;
+use crate::shader::read;
+
+/// Number of bookmark slots, one per number key 1-9.
+pub const BOOKMARK_SLOTS: usize = 9;
+
+pub type Bookmarks = [Option<CameraState>; BOOKMARK_SLOTS];
+
+/// Reads bookmarks saved by `save`. A missing file is not an error, it just holds no bookmarks.
+pub fn load(path: &str) -> Result<Bookmarks, EngineError> {
+    let src = match read(path) {
+        Ok(src) => src,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => return Ok([None; BOOKMARK_SLOTS]),
+        Err(source) => return Err(EngineError::Io { path: path.to_string(), source }),
+    };
+    parse(&src).map_err(|message| EngineError::Config { path: path.to_string(), message })
+}
+
+/// Writes the bookmarks as a JSON array with one entry per slot, `null` for the empty ones.
+pub fn save(path: &str, bookmarks: &Bookmarks) -> Result<(), EngineError> {
+    std::fs::write(path, to_json(bookmarks)).map_err(|source| EngineError::Io { path: path.to_string(), source })
+}
+
+fn to_json(bookmarks: &Bookmarks) -> String {
+    let entries: Vec<String> = bookmarks.iter().map(|bookmark| match bookmark {
+        // {:?} prints the shortest text that reads back to the same float
+        Some(state) => format!("  {{\"azimuth\": {:?}, \"elevation\": {:?}, \"radius\": {:?}, \"target\": [{:?}, {:?}, {:?}]}}",
+            state.azimuth, state.elevation, state.radius, state.target.x, state.target.y, state.target.z),
+        None => "  null".to_string(),
+    }).collect();
+    format!("[\n{}\n]\n", entries.join(",\n"))
+}
+
+fn parse(src: &str) -> Result<Bookmarks, String> {
+    let mut parser = Parser { src: src.as_bytes(), pos: 0 };
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos != src.len() {
+        return Err(format!("unexpected text after the bookmarks at byte {}", parser.pos));
+    }
+
+    let Json::Array(entries) = value else {
+        return Err("expected an array of bookmarks".to_string());
+    };
+    if entries.len() > BOOKMARK_SLOTS {
+        return Err(format!("holds {} bookmarks, there are only {} slots", entries.len(), BOOKMARK_SLOTS));
+    }
+    let mut bookmarks = [None; BOOKMARK_SLOTS];
+    for (slot, entry) in entries.iter().enumerate() {
+        bookmarks[slot] = match entry {
+            Json::Null => None,
+            Json::Object(fields) => {
+                let field = |name: &str| fields.iter().find(|(key, _)| key == name).map(|(_, value)| value);
+                let number = |name: &str| match field(name) {
+                    Some(Json::Number(n)) => Ok(*n),
+                    _ => Err(format!("bookmark {} needs a number `{}`", slot + 1, name)),
+                };
+                let target = match field("target") {
+                    Some(Json::Array(items)) => match items.as_slice() {
+                        [Json::Number(x), Json::Number(y), Json::Number(z)] => vec3(*x as f32, *y as f32, *z as f32),
+                        _ => return Err(format!("bookmark {}: `target` must hold 3 numbers", slot + 1)),
+                    },
+                    _ => return Err(format!("bookmark {} needs a `target` array", slot + 1)),
+                };
+                Some(CameraState { azimuth: number("azimuth")?, elevation: number("elevation")?, radius: number("radius")?, target })
+            }
+            _ => return Err(format!("bookmark {} must be an object or null", slot + 1)),
+        };
+    }
+    Ok(bookmarks)
+}
+
+// The JSON values the bookmark file uses, strings only ever appear as object keys
+enum Json {
+    Null,
+    Number(f64),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn skip_whitespace(&mut self) {
+        while self.src.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_whitespace();
+        self.src.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8) -> Result<(), String> {
+        if self.peek() != Some(byte) {
+            return Err(format!("expected `{}` at byte {}", byte as char, self.pos));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self) -> Result<Json, String> {
+        match self.peek() {
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value()?);
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        _ => break,
+                    }
+                }
+                self.expect(b']')?;
+                Ok(Json::Array(items))
+            }
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    let key = self.string()?;
+                    self.expect(b':')?;
+                    fields.push((key, self.value()?));
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        _ => break,
+                    }
+                }
+                self.expect(b'}')?;
+                Ok(Json::Object(fields))
+            }
+            Some(b'n') if self.src[self.pos..].starts_with(b"null") => {
+                self.pos += 4;
+                Ok(Json::Null)
+            }
+            _ => {
+                let start = self.pos;
+                while self.src.get(self.pos).is_some_and(|b| b.is_ascii_digit() || b"+-.eE".contains(b)) {
+                    self.pos += 1;
+                }
+                std::str::from_utf8(&self.src[start..self.pos]).ok()
+                    .and_then(|text| text.parse::<f64>().ok())
+                    .filter(|n| n.is_finite())
+                    .map(Json::Number)
+                    .ok_or_else(|| format!("expected a value at byte {}", start))
+            }
+        }
+    }
+
+    fn string(&mut self) -> Result<String, String> {
+        self.expect(b'"')?;
+        let start = self.pos;
+        while self.src.get(self.pos).is_some_and(|&b| b != b'"' && b != b'\\') {
+            self.pos += 1;
+        }
+        let text = String::from_utf8_lossy(&self.src[start..self.pos]).into_owned();
+        self.expect(b'"')?;
+        Ok(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bookmarks_survive_a_round_trip() {
+        let mut bookmarks: Bookmarks = [None; BOOKMARK_SLOTS];
+        bookmarks[0] = Some(CameraState { azimuth: 0.1, elevation: 1.2, radius: 6.34194e10, target: vec3(0.0, 2.5e9, -1.0) });
+        bookmarks[8] = Some(CameraState { azimuth: -3.0, elevation: 0.5, radius: 1e12, target: vec3(0.0, 0.0, 0.0) });
+        assert_eq!(parse(&to_json(&bookmarks)).unwrap(), bookmarks);
+
+        assert_eq!(parse("[null, null]").unwrap(), [None; BOOKMARK_SLOTS]);
+        assert!(parse("[{\"azimuth\": 1, \"target\": [0, 0, 0]}]").unwrap_err().contains("elevation"));
+        assert!(parse("[null] trailing").is_err());
+    }
+}
@@ -0,0 +1,629 @@
+use std::collections::HashSet;
+use std::f64::consts::PI;
+use glfw::{Action, Key};
+use glm::{vec3, Vec3};
+use crate::engine::schwarzschild_radius;
+use crate::timeline::InterpolatedState;
+use crate::units::Length;
+
+/// A viewpoint to come back to: where the camera orbits and from how far.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraState {
+    pub azimuth: f64,
+    pub elevation: f64,
+    pub radius: f64,
+    pub target: Vec3,
+}
+
+/// Keyboard orbit speed in mouse pixels per second, so a held key moves like a steady drag.
+const KEY_ORBIT_RATE: f64 = 100.0;
+/// Keyboard zoom speed in scroll wheel steps per second.
+const KEY_ZOOM_RATE: f64 = 2.0;
+/// How fast scroll zoom catches up, about 95% of a wheel step lands within 3 / rate seconds.
+const ZOOM_EASING_RATE: f64 = 15.0;
+/// Vertical field of view in degrees a new camera starts with.
+pub const VERTICAL_FOV: f32 = 60.0;
+/// Range `Engine::set_fov` keeps the field of view in, degrees.
+pub const MIN_FOV: f32 = 5.0;
+pub const MAX_FOV: f32 = 150.0;
+/// Free flight speed as a fraction of the distance to the black hole per second, so the
+/// approach slows down towards the horizon.
+const FLY_RATE: f64 = 0.5;
+/// How close to the black hole the camera may come, in horizon radii, short of inside mode.
+pub const HORIZON_MARGIN: f64 = 1.1;
+/// Furthest the fly camera looks up or down, short of straight up where its basis breaks down.
+const MAX_PITCH: f64 = PI / 2.0 - 0.01;
+/// Room `frame_disk` leaves round the disk, as a multiple of its outer radius.
+const FRAME_MARGIN: f64 = 1.15;
+
+/// How the camera moves: round a target it always looks at, or freely through the scene.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CameraMode {
+    Orbit,
+    Fly,
+}
+
+/// An eased move from one viewpoint to another, see `Camera::transition_to`.
+struct Transition {
+    from: CameraState,
+    to: CameraState, // azimuth unwrapped to the nearest turn of `from`
+    elapsed: f64,
+    duration: f64,
+}
+
+
+pub struct Camera {
+    pub mode: CameraMode,
+    pub target: Vec3, pub radius: f64, pub min_radius: Length, pub max_radius: Length,
+    pub horizon_radius: Length, // scaled with the mass by `rescale`
+    pub allow_inside_horizon: bool, // lets the camera past HORIZON_MARGIN, the engine sets it in the interior view
+    pub azimuth: f64, pub elevation: f64,
+    pub fly_position: Vec3, pub yaw: f64, pub pitch: f64, // the view in Fly mode, yaw from +x towards +z
+    pub orbit_speed: f64, pub pan_speed: f64, pub zoom_speed: f64,
+    pub sensitivity: f64, // multiplier on mouse drag and scroll input
+    pub fov_degrees: f32, // vertical field of view, the horizontal one follows from the aspect ratio
+    dragging: bool, panning: bool, pub moving: bool, last_x: f64, last_y: f64,
+    zoom_pending: f64, // scroll zoom distance not yet applied, eased in by update
+    held_keys: HashSet<Key>, // navigation keys currently down
+    pub transition_time: f64, // seconds `restore` takes to glide to a viewpoint, 0 jumps
+    transition: Option<Transition>,
+}
+
+impl Camera {
+    pub fn new() -> Self {
+        Camera{
+            mode: CameraMode::Orbit,
+            target: vec3(0.0, 0.0, 0.0), radius: 6.34194e10, min_radius: Length::meters(1e10), max_radius: Length::meters(1e12),
+            horizon_radius: Length::ZERO, allow_inside_horizon: false,
+            azimuth: 0.0, elevation: PI / 2.0,
+            fly_position: vec3(0.0, 0.0, 0.0), yaw: 0.0, pitch: 0.0,
+            orbit_speed: 0.01, pan_speed: 0.002, zoom_speed: 25e9,
+            sensitivity: 1.0,
+            fov_degrees: VERTICAL_FOV,
+            dragging: false, panning: false, moving: false, last_x: 0.0, last_y: 0.0,
+            zoom_pending: 0.0,
+            held_keys: HashSet::new(),
+            transition_time: 0.8,
+            transition: None,
+        }
+    }
+
+    // Calculate camera position in world space
+    pub fn position(&self) -> Vec3 {
+        if self.mode == CameraMode::Fly {
+            return self.fly_position;
+        }
+        let clamped_elevation = glm::clamp(self.elevation, 0.01, PI - 0.01);
+        // Orbit around the target
+        self.target + vec3(
+            (self.radius * f64::sin(clamped_elevation) * f64::cos(self.azimuth)) as f32,
+            (self.radius * f64::cos(clamped_elevation)) as f32,
+            (self.radius * f64::sin(clamped_elevation) * f64::sin(self.azimuth)) as f32)
+    }
+
+    /// Tangent of half the vertical field of view, uploaded to the compute shader as tanHalfFov.
+    pub fn tan_half_fov(&self) -> f32 {
+        (self.fov_degrees / 2.0).to_radians().tan()
+    }
+
+    // Forward, right and up unit vectors of the view, with +y as the world up direction
+    pub fn basis(&self) -> (Vec3, Vec3, Vec3) {
+        let forward = match self.mode {
+            CameraMode::Orbit => glm::normalize(self.target - self.position()),
+            CameraMode::Fly => vec3((self.pitch.cos() * self.yaw.cos()) as f32, self.pitch.sin() as f32,
+                (self.pitch.cos() * self.yaw.sin()) as f32),
+        };
+        let right = glm::normalize(glm::cross(forward, vec3(0.0, 1.0, 0.0)));
+        let up = glm::cross(right, forward);
+        (forward, right, up)
+    }
+
+    /// Direction of the primary ray through a point of the window, given as fractions of its width
+    /// and height from the top left corner. It matches the ray the compute shader starts for the
+    /// pixel there, before gravity bends it.
+    pub fn ray_through(&self, aspect: f32, fx: f64, fy: f64) -> Vec3 {
+        let (forward, right, up) = self.basis();
+        let tan_half_fov = self.tan_half_fov();
+        // the shader's u and v, with its pixel rows counted from the bottom of the window
+        let u = (2.0 * fx as f32 - 1.0) * aspect * tan_half_fov;
+        let v = (2.0 * fy as f32 - 1.0) * tan_half_fov;
+        glm::normalize(right * u - up * v + forward)
+    }
+
+    /// Where a world point appears in the window, as fractions of its width and height from the
+    /// top left corner, the inverse of `ray_through`. None for points behind the camera.
+    pub fn project(&self, point: Vec3, aspect: f32) -> Option<(f64, f64)> {
+        let (forward, right, up) = self.basis();
+        let offset = point - self.position();
+        let depth = glm::dot(offset, forward);
+        if depth <= 0.0 {
+            return None;
+        }
+        let tan_half_fov = self.tan_half_fov();
+        let u = glm::dot(offset, right) / depth / (aspect * tan_half_fov);
+        let v = -glm::dot(offset, up) / depth / tan_half_fov;
+        Some((((u + 1.0) / 2.0) as f64, ((v + 1.0) / 2.0) as f64))
+    }
+
+    /// The closest the orbit radius zooms in to: `min_radius`, and unless `allow_inside_horizon`
+    /// is set no closer than HORIZON_MARGIN horizon radii, so the view can't end up inside the
+    /// black hole. Never beyond `max_radius`.
+    pub fn closest_radius(&self) -> Length {
+        let limit = if self.allow_inside_horizon { self.min_radius } else { self.min_radius.max(self.horizon_radius * HORIZON_MARGIN) };
+        limit.min(self.max_radius)
+    }
+
+    /// An orbit radius in meters held between `closest_radius` and `max_radius`.
+    pub fn clamp_radius(&self, radius: f64) -> f64 {
+        radius.clamp(self.closest_radius().in_meters(), self.max_radius.in_meters())
+    }
+
+    /// How far away what the camera looks at is: the orbit radius, or in Fly mode the distance to
+    /// the black hole.
+    pub fn distance(&self) -> f64 {
+        match self.mode {
+            CameraMode::Orbit => self.radius,
+            CameraMode::Fly => glm::length(self.fly_position) as f64,
+        }
+    }
+
+    /// How fast a clock held still at the camera ticks next to one far away, sqrt(1 - Rs/r) at the
+    /// camera's distance r from a black hole of `mass` kg. 0 at and inside the horizon, where
+    /// nothing can hold still.
+    pub fn time_dilation_factor(&self, mass: f64) -> f64 {
+        let r = glm::length(self.position()) as f64;
+        (1.0 - schwarzschild_radius(mass) / r).max(0.0).sqrt()
+    }
+
+    /// Switches between orbiting and flying from the current viewpoint, so the view doesn't jump.
+    /// Flying starts where the orbit was, looking the same way; orbiting starts round a target
+    /// straight ahead at the distance of the black hole, within the zoom limits.
+    pub fn set_mode(&mut self, mode: CameraMode) {
+        if mode == self.mode {
+            return;
+        }
+        let position = self.position();
+        let (forward, _, _) = self.basis();
+        self.transition = None;
+        self.zoom_pending = 0.0;
+        match mode {
+            CameraMode::Fly => {
+                self.fly_position = position;
+                self.yaw = (forward.z as f64).atan2(forward.x as f64);
+                self.pitch = (forward.y as f64).asin().clamp(-MAX_PITCH, MAX_PITCH);
+            }
+            CameraMode::Orbit => {
+                self.radius = self.clamp_radius(self.distance());
+                self.target = position + forward * self.radius as f32;
+                // the orbit's elevation is measured from +y down to the camera, which sits behind the target
+                self.azimuth = (-forward.z as f64).atan2(-forward.x as f64);
+                self.elevation = (-forward.y as f64).acos().clamp(0.01, PI - 0.01);
+            }
+        }
+        self.mode = mode;
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.set_mode(match self.mode {
+            CameraMode::Orbit => CameraMode::Fly,
+            CameraMode::Fly => CameraMode::Orbit,
+        });
+    }
+
+    // Moves the orbit center back onto the black hole, or turns the fly camera to face it
+    pub fn recenter(&mut self) {
+        if self.mode == CameraMode::Fly {
+            let to_hole = -self.fly_position;
+            if glm::length(to_hole) > 0.0 {
+                let direction = glm::normalize(to_hole);
+                self.yaw = (direction.z as f64).atan2(direction.x as f64);
+                self.pitch = (direction.y as f64).asin().clamp(-MAX_PITCH, MAX_PITCH);
+            }
+            return;
+        }
+        self.target = vec3(0.0, 0.0, 0.0);
+    }
+
+    /// Scales every length of the camera by `factor`: the orbit, its zoom limits and speed and
+    /// the target, so the scene looks the same around a body that many times larger.
+    pub fn rescale(&mut self, factor: f64) {
+        self.radius *= factor;
+        self.min_radius *= factor;
+        self.horizon_radius *= factor;
+        self.max_radius *= factor;
+        self.zoom_speed *= factor;
+        self.zoom_pending *= factor;
+        self.target = self.target * factor as f32;
+        self.fly_position = self.fly_position * factor as f32;
+        if let Some(transition) = &mut self.transition {
+            for state in [&mut transition.from, &mut transition.to] {
+                state.radius *= factor;
+                state.target = state.target * factor as f32;
+            }
+        }
+    }
+
+    /// The current viewpoint as an orbit, the one `set_mode` would switch to when flying.
+    pub fn bookmark(&self) -> CameraState {
+        if self.mode == CameraMode::Fly {
+            let mut orbit = Camera { mode: CameraMode::Fly, transition: None, held_keys: HashSet::new(), ..*self };
+            orbit.set_mode(CameraMode::Orbit);
+            return orbit.bookmark();
+        }
+        CameraState { azimuth: self.azimuth, elevation: self.elevation, radius: self.radius, target: self.target }
+    }
+
+    /// Returns to a bookmarked viewpoint, within the current zoom limits, gliding there over
+    /// `transition_time` seconds. A flying camera goes back to orbiting.
+    pub fn restore(&mut self, s: &CameraState) {
+        self.set_mode(CameraMode::Orbit);
+        if self.transition_time > 0.0 {
+            self.transition_to(*s, self.transition_time);
+            return;
+        }
+        self.transition = None;
+        self.azimuth = s.azimuth;
+        self.elevation = s.elevation;
+        self.radius = self.clamp_radius(s.radius);
+        self.target = s.target;
+        self.zoom_pending = 0.0;
+    }
+
+    /// Zooms to where a disk of `outer_radius` meters round the black hole just fits in the view,
+    /// with some room to spare, and centers the orbit on the hole, gliding there like `restore`.
+    /// `fov` is the narrower of the view's two fields of view in radians; the disk's bounding
+    /// sphere is framed, so it fits from any side and at any tilt.
+    pub fn frame_disk(&mut self, outer_radius: f64, fov: f64) {
+        let radius = FRAME_MARGIN * outer_radius / (fov / 2.0).sin();
+        let state = CameraState { radius, target: vec3(0.0, 0.0, 0.0), ..self.bookmark() };
+        self.restore(&state);
+    }
+
+    /// Starts an eased move to `target` lasting `duration` seconds, advanced by `update`. The
+    /// azimuth turns the short way round and the radius moves on a log scale, like the zoom.
+    /// Any orbit, pan or zoom input cancels the move and leaves the camera where it got to.
+    pub fn transition_to(&mut self, target: CameraState, duration: f64) {
+        self.set_mode(CameraMode::Orbit);
+        let mut to = target;
+        to.radius = self.clamp_radius(target.radius);
+        to.azimuth = self.azimuth + (target.azimuth - self.azimuth + PI).rem_euclid(2.0 * PI) - PI;
+        self.zoom_pending = 0.0;
+        self.transition = Some(Transition { from: self.bookmark(), to, elapsed: 0.0, duration: duration.max(0.0) });
+    }
+
+    /// Jumps to a pose sampled from a `CameraTimeline`, dropping any scroll zoom still easing in.
+    /// The radius stays within the zoom limits and a flying camera goes back to orbiting.
+    pub fn apply_keyframe(&mut self, kf: &InterpolatedState) {
+        self.mode = CameraMode::Orbit;
+        self.azimuth = kf.azimuth;
+        self.elevation = kf.elevation;
+        self.radius = self.clamp_radius(kf.radius);
+        self.target = kf.target;
+        self.zoom_pending = 0.0;
+        self.transition = None;
+    }
+
+    /// Advances the continuous camera motion by `dt` seconds: held navigation keys orbit and zoom
+    /// at a fixed rate per second, and scroll zoom is eased in over a few frames, so the camera
+    /// moves the same way whatever the framerate.
+    pub fn update(&mut self, dt: f64) {
+        let axis = |positive: [Key; 2], negative: [Key; 2]| {
+            let held = |pair: [Key; 2]| pair.iter().any(|key| self.held_keys.contains(key)) as i32;
+            (held(positive) - held(negative)) as f64
+        };
+        // A/D or Left/Right orbit, W/S or Up/Down raise and lower the camera, E/Q or PageUp/PageDown zoom
+        let yaw = axis([Key::D, Key::Right], [Key::A, Key::Left]);
+        let pitch = axis([Key::S, Key::Down], [Key::W, Key::Up]);
+        let zoom = axis([Key::E, Key::PageUp], [Key::Q, Key::PageDown]);
+        let keys_held = yaw != 0.0 || pitch != 0.0 || zoom != 0.0;
+        if keys_held || self.dragging {
+            self.transition = None;
+        }
+        if self.mode == CameraMode::Fly {
+            self.update_flight(dt);
+            return;
+        }
+        if let Some(transition) = &mut self.transition {
+            transition.elapsed += dt;
+            let s = (transition.elapsed / transition.duration).min(1.0);
+            let (from, to) = (transition.from, transition.to);
+            let eased = s * s * (3.0 - 2.0 * s); // smoothstep, starts and stops gently
+            let lerp = |a: f64, b: f64| a + (b - a) * eased;
+            self.azimuth = lerp(from.azimuth, to.azimuth);
+            self.elevation = lerp(from.elevation, to.elevation);
+            self.radius = lerp(from.radius.ln(), to.radius.ln()).exp();
+            self.target = from.target + (to.target - from.target) * eased as f32;
+            if s >= 1.0 {
+                self.radius = to.radius; // exp(ln(r)) can land a hair outside the zoom limits
+                self.transition = None;
+            }
+            self.moving = true;
+            return;
+        }
+
+        self.azimuth += yaw * KEY_ORBIT_RATE * self.orbit_speed * dt;
+        self.elevation = glm::clamp(self.elevation + pitch * KEY_ORBIT_RATE * self.orbit_speed * dt, 0.01, PI - 0.01);
+
+        // exponential easing: the share of the pending zoom applied only depends on elapsed time
+        let eased = self.zoom_pending * (1.0 - f64::exp(-ZOOM_EASING_RATE * dt));
+        self.zoom_pending -= eased;
+        if self.zoom_pending.abs() < 1.0 {
+            self.zoom_pending = 0.0;
+        }
+        let radius = self.radius - zoom * KEY_ZOOM_RATE * self.zoom_speed * dt - eased;
+        self.radius = self.clamp_radius(radius);
+        if self.radius != radius {
+            // stop at the limit instead of pushing against it
+            self.zoom_pending = 0.0;
+        }
+
+        self.moving = self.dragging || self.panning || keys_held || self.zoom_pending != 0.0;
+    }
+
+    // Fly mode's share of `update`: W/S fly along the view, A/D strafe, E/Q rise and sink along
+    // the view's up, the arrow keys look round and scroll zoom eases the camera forward
+    fn update_flight(&mut self, dt: f64) {
+        let held = |key: Key| self.held_keys.contains(&key) as i32 as f32;
+        let thrust = vec3(held(Key::D) - held(Key::A), held(Key::E) - held(Key::Q), held(Key::W) - held(Key::S));
+        let turn = (held(Key::Right) - held(Key::Left)) as f64;
+        let tilt = (held(Key::Up) - held(Key::Down)) as f64;
+
+        self.yaw += turn * KEY_ORBIT_RATE * self.orbit_speed * dt;
+        self.pitch = (self.pitch + tilt * KEY_ORBIT_RATE * self.orbit_speed * dt).clamp(-MAX_PITCH, MAX_PITCH);
+
+        let eased = self.zoom_pending * (1.0 - f64::exp(-ZOOM_EASING_RATE * dt));
+        self.zoom_pending -= eased;
+        if self.zoom_pending.abs() < 1.0 {
+            self.zoom_pending = 0.0;
+        }
+        let (forward, right, up) = self.basis();
+        // never slower than at the closest zoom, or the camera could stall at the black hole
+        let speed = (FLY_RATE * self.distance().max(self.min_radius.in_meters()) * dt) as f32;
+        self.fly_position = self.fly_position + (right * thrust.x + up * thrust.y + forward * thrust.z) * speed
+            + forward * eased as f32;
+        // slide along the sphere at the closest radius instead of crossing it
+        let closest = self.closest_radius().in_meters() as f32;
+        let distance = glm::length(self.fly_position);
+        if !self.allow_inside_horizon && distance < closest && distance > 0.0 {
+            self.fly_position = self.fly_position * (closest / distance);
+            self.zoom_pending = 0.0;
+        }
+
+        let keys_held = thrust != vec3(0.0, 0.0, 0.0) || turn != 0.0 || tilt != 0.0;
+        self.moving = self.dragging || self.panning || keys_held || self.zoom_pending != 0.0;
+    }
+
+    pub fn process_key(&mut self, key: Key, action: Action) {
+        match action {
+            Action::Press => { self.held_keys.insert(key); }
+            Action::Release => { self.held_keys.remove(&key); }
+            Action::Repeat => {}
+        }
+    }
+
+    pub fn process_mouse_move(&mut self, x: f64, y: f64) {
+        let dx = (x - self.last_x) * self.sensitivity;
+        let dy = (y - self.last_y) * self.sensitivity;
+
+        if self.mode == CameraMode::Fly && self.dragging {
+            // Look: the drag turns the view, panning slides the camera across it
+            if self.panning {
+                let (_, right, up) = self.basis();
+                let scale = (self.distance() * self.pan_speed) as f32;
+                self.fly_position = self.fly_position - right * (dx as f32 * scale) + up * (dy as f32 * scale);
+            } else {
+                self.yaw += dx * self.orbit_speed;
+                self.pitch = (self.pitch - dy * self.orbit_speed).clamp(-MAX_PITCH, MAX_PITCH);
+            }
+        } else if self.dragging && self.panning {
+            // Pan: Shift + Left or Middle Mouse, moves the target in the view plane so the
+            // scene follows the cursor; pan_speed is the fraction of the distance per point
+            let (_, right, up) = self.basis();
+            let scale = (self.radius * self.pan_speed) as f32;
+            self.target = self.target - right * (dx as f32 * scale) + up * (dy as f32 * scale);
+        } else if self.dragging && !self.panning {
+            // Orbit: Left mouse only
+            self.azimuth   += dx * self.orbit_speed;
+            self.elevation -= dy * self.orbit_speed;
+            self.elevation = glm::clamp(self.elevation, 0.01, PI - 0.01);
+        }
+
+        self.last_x = x;
+        self.last_y = y;
+    }
+
+    /// `cursor` is where the cursor is, in the same units `process_mouse_move` gets.
+    pub fn process_mouse_button(&mut self, button: glfw::MouseButton, action: glfw::Action, mods: glfw::Modifiers, cursor: (f64, f64)) {
+        if button == glfw::MouseButtonLeft || button == glfw::MouseButtonMiddle {
+            if action == glfw::Action::Press{
+                self.dragging = true;
+                self.panning = button == glfw::MouseButtonMiddle || mods.contains(glfw::Modifiers::Shift);
+                (self.last_x, self.last_y) = cursor;
+            } else if action == Action::Release {
+                self.dragging = false;
+                self.panning = false;
+            }
+        }
+    }
+
+    pub fn process_scroll(&mut self, _xoffset: f64, yoffset: f64) {
+        self.transition = None;
+        self.zoom_pending += yoffset * self.zoom_speed * self.sensitivity;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx_eq(a: Vec3, b: Vec3, tolerance: f32) -> bool {
+        (a.x - b.x).abs() <= tolerance && (a.y - b.y).abs() <= tolerance && (a.z - b.z).abs() <= tolerance
+    }
+
+    fn camera_at(azimuth: f64, elevation: f64) -> Camera {
+        let mut camera = Camera::new();
+        camera.radius = 1e10;
+        camera.azimuth = azimuth;
+        camera.elevation = elevation;
+        camera
+    }
+
+    #[test]
+    fn position_on_equator_follows_azimuth() {
+        // f32 carries about 7 digits, so allow a few parts in 1e7 of the radius
+        let tolerance = 1e10 * 1e-6;
+        assert!(approx_eq(camera_at(0.0, PI / 2.0).position(), vec3(1e10, 0.0, 0.0), tolerance));
+        assert!(approx_eq(camera_at(PI / 2.0, PI / 2.0).position(), vec3(0.0, 0.0, 1e10), tolerance));
+        assert!(approx_eq(camera_at(PI, PI / 2.0).position(), vec3(-1e10, 0.0, 0.0), tolerance));
+    }
+
+    #[test]
+    fn position_orbits_the_target() {
+        let mut camera = camera_at(0.0, PI / 2.0);
+        camera.target = vec3(0.0, 2e10, 0.0);
+        assert!(approx_eq(camera.position(), vec3(1e10, 2e10, 0.0), 1e10 * 1e-6));
+    }
+
+    #[test]
+    fn elevation_is_clamped_away_from_the_poles() {
+        let tolerance = 1e10 * 1e-6;
+        assert!(approx_eq(camera_at(0.3, 0.0).position(), camera_at(0.3, 0.01).position(), tolerance));
+        assert!(approx_eq(camera_at(0.3, PI).position(), camera_at(0.3, PI - 0.01).position(), tolerance));
+
+        // at the clamp the view basis is still well defined
+        for elevation in [0.0, PI] {
+            let (forward, right, up) = camera_at(0.3, elevation).basis();
+            for axis in [forward, right, up] {
+                assert!((glm::length(axis) - 1.0).abs() < 1e-4);
+            }
+        }
+    }
+
+    #[test]
+    fn transition_takes_the_short_way_round_and_input_cancels_it() {
+        let mut camera = camera_at(0.1, PI / 2.0);
+        let target = CameraState { azimuth: 2.0 * PI - 0.1, elevation: 1.0, radius: 1e11, target: vec3(0.0, 0.0, 0.0) };
+        camera.transition_to(target, 1.0);
+        camera.update(0.5);
+        assert!((camera.azimuth - 0.0).abs() < 1e-9, "halfway between 0.1 and -0.1");
+        assert!(camera.moving && camera.transition.is_some());
+        camera.update(0.6);
+        assert!(camera.transition.is_none());
+        assert!((camera.azimuth + 0.1).abs() < 1e-9);
+        assert_eq!((camera.elevation, camera.radius), (1.0, 1e11));
+
+        camera.transition_to(CameraState { azimuth: 1.0, ..target }, 1.0);
+        camera.update(0.25);
+        let azimuth = camera.azimuth;
+        camera.process_scroll(0.0, 1.0);
+        assert!(camera.transition.is_none());
+        camera.zoom_pending = 0.0;
+        camera.update(0.25);
+        assert_eq!(camera.azimuth, azimuth);
+    }
+
+    #[test]
+    fn framing_the_disk_fits_its_edge_in_the_view() {
+        let mut camera = camera_at(0.4, 1.1);
+        camera.target = vec3(3e10, 0.0, 0.0);
+        camera.transition_time = 0.5;
+        let (outer, aspect) = (2e11, 1.5);
+        camera.frame_disk(outer, 2.0 * (camera.tan_half_fov() as f64).atan());
+        for _ in 0..10 {
+            camera.update(0.1);
+        }
+        assert_eq!(camera.target, vec3(0.0, 0.0, 0.0));
+        let mut reach = 0.0f64;
+        for i in 0..64 {
+            let angle = i as f64 / 64.0 * 2.0 * PI;
+            let edge = vec3((outer * angle.cos()) as f32, 0.0, (outer * angle.sin()) as f32);
+            let (fx, fy) = camera.project(edge, aspect).unwrap();
+            assert!((0.0..=1.0).contains(&fx) && (0.0..=1.0).contains(&fy), "edge at {} rad off screen", angle);
+            reach = reach.max((fx - 0.5).abs()).max((fy - 0.5).abs());
+        }
+        // and fills a good part of it rather than a speck in the middle
+        assert!(reach > 0.2, "disk reaches only {} of the way to the edge", 2.0 * reach);
+    }
+
+    #[test]
+    fn switching_modes_keeps_the_viewpoint() {
+        let mut camera = camera_at(0.7, 1.2);
+        camera.target = vec3(1e9, 0.0, -2e9);
+        let (position, (forward, _, _)) = (camera.position(), camera.basis());
+        camera.set_mode(CameraMode::Fly);
+        assert!(approx_eq(camera.position(), position, 1.0));
+        assert!(approx_eq(camera.basis().0, forward, 1e-5));
+
+        camera.fly_position = vec3(0.0, 5e9, 2e10);
+        camera.yaw = -2.0;
+        camera.pitch = -0.3;
+        let (position, (forward, _, _)) = (camera.position(), camera.basis());
+        let bookmark = camera.bookmark();
+        camera.set_mode(CameraMode::Orbit);
+        assert_eq!(camera.bookmark(), bookmark);
+        assert!(approx_eq(camera.position(), position, 2e10 * 1e-5));
+        assert!(approx_eq(camera.basis().0, forward, 1e-5));
+        assert!((camera.radius - glm::length(position) as f64).abs() < 1e4);
+    }
+
+    #[test]
+    fn zoom_stops_short_of_the_horizon_unless_allowed() {
+        let mut camera = camera_at(0.0, PI / 2.0);
+        camera.min_radius = Length::meters(1e8);
+        camera.horizon_radius = Length::meters(1e10);
+        camera.process_scroll(0.0, 100.0);
+        for _ in 0..100 {
+            camera.update(0.1);
+        }
+        assert_eq!(camera.radius, 1.1e10);
+
+        // the limit follows the mass
+        camera.rescale(2.0);
+        assert_eq!(camera.closest_radius(), Length::meters(2.2e10));
+
+        camera.allow_inside_horizon = true;
+        camera.process_scroll(0.0, 100.0);
+        for _ in 0..100 {
+            camera.update(0.1);
+        }
+        assert_eq!(camera.radius, 2e8);
+    }
+
+    #[test]
+    fn ray_through_the_window_center_looks_forward() {
+        let camera = camera_at(0.7, 1.2);
+        let (forward, _, up) = camera.basis();
+        assert!(approx_eq(camera.ray_through(1.5, 0.5, 0.5), forward, 1e-6));
+        // the top edge of the window is half the field of view above the center
+        let top = camera.ray_through(1.5, 0.5, 0.0);
+        assert!((glm::dot(top, forward).acos().to_degrees() - VERTICAL_FOV / 2.0).abs() < 1e-3);
+        assert!(glm::dot(top, up) > 0.0);
+        // a wider lens spreads the same window over more of the sky, the pixels staying square
+        let mut wide = camera_at(0.7, 1.2);
+        wide.fov_degrees = 100.0;
+        assert!((glm::dot(wide.ray_through(1.5, 0.5, 0.0), forward).acos().to_degrees() - 50.0).abs() < 1e-3);
+        let right_edge = wide.ray_through(1.5, 1.0, 0.5);
+        let half_width = glm::dot(right_edge, forward).acos();
+        assert!((half_width.tan() - 1.5 * wide.tan_half_fov()).abs() < 1e-4);
+
+        let point = camera.position() + camera.ray_through(1.5, 0.2, 0.9) * 3e10;
+        let (fx, fy) = camera.project(point, 1.5).unwrap();
+        assert!((fx - 0.2).abs() < 1e-5 && (fy - 0.9).abs() < 1e-5);
+        assert!(camera.project(camera.position() - forward * 1e10, 1.5).is_none(), "behind the camera");
+    }
+
+    #[test]
+    fn clocks_slow_towards_the_horizon_and_stop_inside() {
+        // a mass whose horizon is 1e10 m
+        let mass = 1e10 * crate::engine::C * crate::engine::C / (2.0 * crate::engine::G);
+        let factor = |radius: f64| {
+            let mut camera = camera_at(0.0, PI / 2.0);
+            camera.radius = radius;
+            camera.time_dilation_factor(mass)
+        };
+        assert!((factor(4e10) - 0.75f64.sqrt()).abs() < 1e-6);
+        assert!(factor(1e13) > 0.999);
+        assert!(factor(1.01e10) < factor(1.1e10) && factor(1.1e10) < factor(2e10));
+        assert_eq!(factor(0.5e10), 0.0);
+    }
+}
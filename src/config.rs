@@ -0,0 +1,1063 @@
+use std::f64::consts::PI;
+use glm::{vec3, Vec3};
+use crate::bindings::{key_name, parse_key, Command, KeyBindings};
+use crate::camera::{Camera, HORIZON_MARGIN, MAX_FOV, MIN_FOV};
+use crate::engine::{C, DiskModel, DiskParams, DiskProfile, EngineError, GravObject, IntegrationMode, QualityPreset, G,
+    MAX_AA_SAMPLES, MAX_EXPOSURE, MAX_HORIZON_EPSILON, MAX_INTEGRATION_STEPS, MAX_MASS, MAX_OBJECTS, MAX_SPIN, MIN_EXPOSURE,
+    SAGA_RS, SOLAR_MASS};
+use crate::shader::{read, ComputeVariant};
+use crate::units::Length;
+
+/// A value in a scene file, limited to the TOML types scenes use.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ConfigValue {
+    Bool(bool),
+    Number(f64),
+    Str(String),
+    Numbers(Vec<f64>), // an array of numbers, like a vector `[x, y, z]`
+}
+
+impl ConfigValue {
+    fn parse(text: &str) -> Option<Self> {
+        match text {
+            "true" => return Some(ConfigValue::Bool(true)),
+            "false" => return Some(ConfigValue::Bool(false)),
+            _ => {}
+        }
+        if let Some(inner) = text.strip_prefix('"').and_then(|t| t.strip_suffix('"')) {
+            return (!inner.contains('"')).then(|| ConfigValue::Str(inner.to_string()));
+        }
+        if let Some(inner) = text.strip_prefix('[').and_then(|t| t.strip_suffix(']')) {
+            return inner.split(',').map(|item| match ConfigValue::parse(item.trim()) {
+                Some(ConfigValue::Number(n)) => Some(n),
+                _ => None,
+            }).collect::<Option<Vec<f64>>>().map(ConfigValue::Numbers);
+        }
+        text.replace('_', "").parse::<f64>().ok().filter(|n| n.is_finite()).map(ConfigValue::Number)
+    }
+
+    pub fn as_number(&self) -> Option<f64> {
+        match self {
+            ConfigValue::Number(n) => Some(*n),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            ConfigValue::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            ConfigValue::Str(text) => Some(text),
+            _ => None,
+        }
+    }
+
+    // A whole number that fits in a u32
+    /// Integers up to 2^53, the largest a TOML float holds exactly.
+    pub fn as_u64(&self) -> Option<u64> {
+        self.as_number()
+            .filter(|n| n.fract() == 0.0 && *n >= 0.0 && *n <= (1u64 << 53) as f64)
+            .map(|n| n as u64)
+    }
+
+    pub fn as_u32(&self) -> Option<u32> {
+        self.as_number()
+            .filter(|n| n.fract() == 0.0 && *n >= 0.0 && *n <= u32::MAX as f64)
+            .map(|n| n as u32)
+    }
+
+    pub fn as_vec3(&self) -> Option<[f64; 3]> {
+        match self {
+            ConfigValue::Numbers(n) => n.as_slice().try_into().ok(),
+            _ => None,
+        }
+    }
+
+    // A pixel dimension: a positive whole number that fits in a u32
+    fn as_dimension(&self) -> Option<u32> {
+        self.as_u32().filter(|n| *n >= 1)
+    }
+}
+
+/// Parses the subset of TOML that scene files use: `[table]` headers, `[[array]]` headers,
+/// `key = value` pairs holding numbers, booleans, plain quoted strings or arrays of numbers, and
+/// `#` comments. Keys are returned as `table.key`, or `array.index.key` for the tables of an
+/// array, together with the line they appeared on.
+pub fn parse_toml(src: &str) -> Result<Vec<(String, ConfigValue, usize)>, String> {
+    let mut table = String::new();
+    let mut arrays: Vec<(String, usize)> = Vec::new(); // tables seen so far in each array
+    let mut entries: Vec<(String, ConfigValue, usize)> = Vec::new();
+
+    for (index, raw) in src.lines().enumerate() {
+        let line = index + 1;
+        // a '#' starts a comment unless it sits inside a string
+        let mut in_string = false;
+        let end = raw.char_indices()
+            .find(|&(_, ch)| {
+                if ch == '"' { in_string = !in_string; }
+                ch == '#' && !in_string
+            })
+            .map_or(raw.len(), |(i, _)| i);
+        let text = raw[..end].trim();
+        if text.is_empty() {
+            continue;
+        }
+
+        if let Some(header) = text.strip_prefix("[[") {
+            let name = header.strip_suffix("]]").map(str::trim)
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| format!("line {}: malformed array header `{}`", line, text))?;
+            let index = match arrays.iter_mut().find(|(array, _)| array == name) {
+                Some((_, count)) => { *count += 1; *count - 1 }
+                None => { arrays.push((name.to_string(), 1)); 0 }
+            };
+            table = format!("{}.{}", name, index);
+            continue;
+        }
+        if let Some(header) = text.strip_prefix('[') {
+            let name = header.strip_suffix(']').map(str::trim)
+                .filter(|name| !name.is_empty())
+                .ok_or_else(|| format!("line {}: malformed table header `{}`", line, text))?;
+            table = name.to_string();
+            continue;
+        }
+
+        let (key, value) = text.split_once('=')
+            .ok_or_else(|| format!("line {}: expected `key = value`, found `{}`", line, text))?;
+        let key = key.trim();
+        if key.is_empty() || !key.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '_' || ch == '-') {
+            return Err(format!("line {}: invalid key `{}`", line, key));
+        }
+        let value = ConfigValue::parse(value.trim())
+            .ok_or_else(|| format!("line {}: invalid value for `{}`: `{}`", line, key, value.trim()))?;
+
+        let key = if table.is_empty() { key.to_string() } else { format!("{}.{}", table, key) };
+        if let Some((_, _, first)) = entries.iter().find(|(existing, _, _)| *existing == key) {
+            return Err(format!("line {}: `{}` is already set on line {}", line, key, first));
+        }
+        entries.push((key, value, line));
+    }
+    Ok(entries)
+}
+
+/// Initial simulation parameters, loaded from a TOML scene file so scenes can be shared.
+/// Lengths are in meters, angles in radians and the mass in kilograms.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Config {
+    pub window_width: u32,
+    pub window_height: u32,
+    pub compute_width: u32,
+    pub compute_height: u32,
+    pub camera_azimuth: f64,
+    pub camera_elevation: f64,
+    pub camera_radius: f64,
+    pub camera_target: Vec3, // the point the camera orbits
+    pub camera_min_radius: Length,
+    pub camera_max_radius: Length,
+    pub camera_sensitivity: f64,
+    pub camera_transition_time: f64, // seconds a recalled bookmark takes to glide in
+    pub camera_allow_inside_horizon: bool, // opens the interior view, see `ObserverRegion`
+    pub camera_fov_degrees: f32, // vertical field of view, see `Engine::set_fov`
+    pub disk_inner_radius: Length,
+    pub disk_outer_radius: Length,
+    pub disk_thickness: f64, // height of the thick disk
+    pub disk_model: DiskModel,
+    pub disk_inclination: f64, // radians, see `Engine::set_disk_orientation`
+    pub disk_azimuth: f64,
+    pub disk_density_scale: f32, // see `Engine::set_disk_volume`
+    pub disk_emission_scale: f32,
+    pub disk_texture: Option<String>, // emission image over the disk, see `Engine::load_disk_texture`
+    pub disk_profile: DiskProfile,
+    pub disk_max_temperature: f32, // kelvin at full red in the temperature profile
+    pub disk_peak_temperature: Option<f32>, // kelvin at the inner edge of a blackbody disk, see `Engine::set_disk_temperature`
+    pub black_hole_mass: f64,
+    pub black_hole_spin: f64, // a/M, 0 to MAX_SPIN
+    pub quality: QualityPreset, // see `Engine::set_integration_quality`
+    pub integration_steps: Option<i32>, // replaces the preset's step count
+    pub integration_step_size: Option<f32>, // meters, replaces the preset's step
+    pub integration_mode: IntegrationMode,
+    pub integration_tolerance: f32, // see `Engine::set_integration_tolerance`
+    pub horizon_epsilon: Option<f32>, // rs above the horizon that capture rays, see `Engine::set_horizon_epsilon`
+    pub exposure: f32, // EV, see `Engine::set_exposure`
+    pub bloom: bool,
+    pub bloom_threshold: f32, // see `Engine::set_bloom`
+    pub bloom_intensity: f32,
+    pub lens_flare: bool, // off unless asked for, the ghosts are a camera artifact
+    pub lens_flare_intensity: f32, // see `Engine::set_lens_flare`
+    pub edge_threshold: f32, // see `Engine::set_adaptive_aa`
+    pub edge_samples: u32, // extra frames on the edges, 0 for none
+    pub gravity: bool,
+    pub seed: u64, // see `Engine::set_seed`
+    pub fixed_timestep: Option<f64>, // seconds the simulation moves on per frame, see `Engine::set_fixed_timestep`
+    pub starfield: bool,
+    pub star_density: f32,
+    pub star_brightness: f32,
+    pub star_seed: u32,
+    pub background: Option<String>, // equirectangular image shown instead of the starfield
+    pub background_color: Vec3, // linear, behind the starfield when there is no image
+    pub objects: Vec<GravObject>, // the [[object]] tables, none unless the scene places some
+    pub compute_variants: Vec<ComputeVariant>, // more compute shaders to cycle through, see `Engine::load_compute_variant`
+    pub key_bindings: KeyBindings, // the [keys] table, see `bindings::COMMANDS`
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        let camera = Camera::new();
+        let disk = DiskParams::default();
+        Config {
+            window_width: 800,
+            window_height: 600,
+            compute_width: 200,
+            compute_height: 150,
+            camera_azimuth: camera.azimuth,
+            camera_elevation: camera.elevation,
+            camera_radius: camera.radius,
+            camera_target: camera.target,
+            camera_min_radius: camera.min_radius,
+            camera_max_radius: camera.max_radius,
+            camera_sensitivity: camera.sensitivity,
+            camera_transition_time: camera.transition_time,
+            camera_allow_inside_horizon: camera.allow_inside_horizon,
+            camera_fov_degrees: camera.fov_degrees,
+            disk_inner_radius: disk.inner_radius,
+            disk_outer_radius: disk.outer_radius,
+            disk_thickness: disk.thickness as f64,
+            disk_model: DiskModel::Thin,
+            disk_inclination: 0.0,
+            disk_azimuth: 0.0,
+            disk_density_scale: 1.0,
+            disk_emission_scale: 1.0,
+            disk_texture: None,
+            disk_profile: DiskProfile::Gradient,
+            disk_max_temperature: 20000.0,
+            disk_peak_temperature: None,
+            black_hole_mass: SAGA_RS * C * C / (2.0 * G),
+            black_hole_spin: 0.0,
+            quality: QualityPreset::High,
+            integration_steps: None,
+            integration_step_size: None,
+            integration_mode: IntegrationMode::Fixed,
+            integration_tolerance: 1e-5,
+            horizon_epsilon: None,
+            exposure: 0.0,
+            bloom: false,
+            bloom_threshold: 0.7,
+            bloom_intensity: 1.0,
+            lens_flare: false,
+            lens_flare_intensity: 1.0,
+            edge_threshold: 0.1,
+            edge_samples: 0,
+            gravity: false,
+            seed: 0,
+            fixed_timestep: None,
+            starfield: true,
+            star_density: 0.05,
+            star_brightness: 1.0,
+            star_seed: 0,
+            background: None,
+            background_color: vec3(0.0, 0.0, 0.0),
+            objects: Vec::new(),
+            compute_variants: Vec::new(),
+            key_bindings: KeyBindings::default(),
+        }
+    }
+}
+
+impl Config {
+    /// Reads and validates a scene file, keys that are left out keep their default value.
+    pub fn load(path: &str) -> Result<Self, EngineError> {
+        let src = read(path).map_err(|source| EngineError::Io { path: path.to_string(), source })?;
+        Config::parse(&src).map_err(|message| EngineError::Config { path: path.to_string(), message })
+    }
+
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut config = Config::default();
+        let mut variants: Vec<[Option<String>; 2]> = Vec::new(); // name, path of each [[compute_variant]]
+        // each [[object]], white and of one solar mass unless set, with whether its pos and radius are
+        let mut objects: Vec<(GravObject, [bool; 2])> = Vec::new();
+        let blank = GravObject { pos: vec3(0.0, 0.0, 0.0), radius: 0.0, color: vec3(1.0, 1.0, 1.0), mass: SOLAR_MASS };
+        for (key, value, line) in parse_toml(src)? {
+            let number = || value.as_number().ok_or_else(|| format!("line {}: `{}` must be a number", line, key));
+            let dimension = || value.as_dimension().ok_or_else(|| format!("line {}: `{}` must be a positive integer", line, key));
+            match key.as_str() {
+                "window.width" => config.window_width = dimension()?,
+                "window.height" => config.window_height = dimension()?,
+                "compute.width" => config.compute_width = dimension()?,
+                "compute.height" => config.compute_height = dimension()?,
+                "camera.azimuth" => config.camera_azimuth = number()?,
+                "camera.elevation" => config.camera_elevation = number()?,
+                "camera.radius" => config.camera_radius = number()?,
+                "camera.target" => {
+                    let [x, y, z] = value.as_vec3().ok_or_else(|| format!("line {}: `{}` must be an array of 3 numbers", line, key))?;
+                    config.camera_target = vec3(x as f32, y as f32, z as f32)
+                }
+                "camera.min_radius" => config.camera_min_radius = Length::meters(number()?),
+                "camera.max_radius" => config.camera_max_radius = Length::meters(number()?),
+                "camera.sensitivity" => config.camera_sensitivity = number()?,
+                "camera.transition_time" => config.camera_transition_time = number()?,
+                "camera.fov_degrees" => config.camera_fov_degrees = number()? as f32,
+                "camera.allow_inside_horizon" => {
+                    config.camera_allow_inside_horizon = value.as_bool().ok_or_else(|| format!("line {}: `{}` must be true or false", line, key))?
+                }
+                "disk.inner_radius" => config.disk_inner_radius = Length::meters(number()?),
+                "disk.outer_radius" => config.disk_outer_radius = Length::meters(number()?),
+                "disk.thickness" => config.disk_thickness = number()?,
+                "disk.inclination" => config.disk_inclination = number()?,
+                "disk.azimuth" => config.disk_azimuth = number()?,
+                "disk.density_scale" => config.disk_density_scale = number()? as f32,
+                "disk.emission_scale" => config.disk_emission_scale = number()? as f32,
+                "disk.model" => {
+                    config.disk_model = match value.as_str() {
+                        Some("thin") => DiskModel::Thin,
+                        Some("thick") => DiskModel::Thick,
+                        Some("none") => DiskModel::None,
+                        _ => return Err(format!("line {}: `{}` must be \"thin\", \"thick\" or \"none\"", line, key)),
+                    }
+                }
+                "disk.texture" => {
+                    let path = value.as_str().ok_or_else(|| format!("line {}: `{}` must be a string", line, key))?;
+                    config.disk_texture = Some(path.to_string())
+                }
+                "disk.profile" => {
+                    config.disk_profile = match value.as_str() {
+                        Some("gradient") => DiskProfile::Gradient,
+                        Some("color") => DiskProfile::Color,
+                        Some("temperature") => DiskProfile::Temperature,
+                        _ => return Err(format!("line {}: `{}` must be \"gradient\", \"color\" or \"temperature\"", line, key)),
+                    }
+                }
+                "disk.max_temperature" => config.disk_max_temperature = number()? as f32,
+                "disk.peak_temperature" => config.disk_peak_temperature = Some(number()? as f32),
+                "black_hole.mass" => config.black_hole_mass = number()?,
+                "black_hole.spin" => config.black_hole_spin = number()?,
+                "integrator.quality" => {
+                    config.quality = match value.as_str() {
+                        Some("low") => QualityPreset::Low,
+                        Some("medium") => QualityPreset::Medium,
+                        Some("high") => QualityPreset::High,
+                        _ => return Err(format!("line {}: `{}` must be \"low\", \"medium\" or \"high\"", line, key)),
+                    }
+                }
+                "integrator.steps" => {
+                    let steps = value.as_u32().filter(|steps| (1..=MAX_INTEGRATION_STEPS as u32).contains(steps))
+                        .ok_or_else(|| format!("line {}: `{}` must be a whole number from 1 to {}", line, key, MAX_INTEGRATION_STEPS))?;
+                    config.integration_steps = Some(steps as i32)
+                }
+                "integrator.step_size" => config.integration_step_size = Some(number()? as f32),
+                "integrator.mode" => {
+                    config.integration_mode = match value.as_str() {
+                        Some("fixed") => IntegrationMode::Fixed,
+                        Some("adaptive") => IntegrationMode::Adaptive,
+                        _ => return Err(format!("line {}: `{}` must be \"fixed\" or \"adaptive\"", line, key)),
+                    }
+                }
+                "integrator.tolerance" => config.integration_tolerance = number()? as f32,
+                "integrator.horizon_epsilon" => config.horizon_epsilon = Some(number()? as f32),
+                "image.exposure" => config.exposure = number()? as f32,
+                "image.bloom" => {
+                    config.bloom = value.as_bool().ok_or_else(|| format!("line {}: `{}` must be true or false", line, key))?
+                }
+                "image.bloom_threshold" => config.bloom_threshold = number()? as f32,
+                "image.bloom_intensity" => config.bloom_intensity = number()? as f32,
+                "image.lens_flare" => {
+                    config.lens_flare = value.as_bool().ok_or_else(|| format!("line {}: `{}` must be true or false", line, key))?
+                }
+                "image.lens_flare_intensity" => config.lens_flare_intensity = number()? as f32,
+                "image.edge_threshold" => config.edge_threshold = number()? as f32,
+                "image.edge_samples" => {
+                    config.edge_samples = value.as_u32().filter(|n| *n <= MAX_AA_SAMPLES)
+                        .ok_or_else(|| format!("line {}: `{}` must be a whole number from 0 to {}", line, key, MAX_AA_SAMPLES))?
+                }
+                "simulation.gravity" => {
+                    config.gravity = value.as_bool().ok_or_else(|| format!("line {}: `{}` must be true or false", line, key))?
+                }
+                "simulation.fixed_timestep" => config.fixed_timestep = Some(number()?),
+                "simulation.seed" => {
+                    config.seed = value.as_u64().ok_or_else(|| format!("line {}: `{}` must be an integer from 0 to 2^53", line, key))?
+                }
+                "starfield.enabled" => {
+                    config.starfield = value.as_bool().ok_or_else(|| format!("line {}: `{}` must be true or false", line, key))?
+                }
+                "starfield.density" => config.star_density = number()? as f32,
+                "starfield.brightness" => config.star_brightness = number()? as f32,
+                "starfield.seed" => {
+                    config.star_seed = value.as_u32().ok_or_else(|| format!("line {}: `{}` must be a non-negative integer", line, key))?
+                }
+                "background.path" => {
+                    let path = value.as_str().ok_or_else(|| format!("line {}: `{}` must be a string", line, key))?;
+                    config.background = Some(path.to_string())
+                }
+                "background.color" => {
+                    let [r, g, b] = value.as_vec3().ok_or_else(|| format!("line {}: `{}` must be an array of 3 numbers", line, key))?;
+                    config.background_color = vec3(r as f32, g as f32, b as f32)
+                }
+                _ if key.starts_with("keys.") => {
+                    let command = Command::from_name(&key["keys.".len()..])
+                        .ok_or_else(|| format!("line {}: unknown key `{}`", line, key))?;
+                    let bound = value.as_str().and_then(parse_key)
+                        .ok_or_else(|| format!("line {}: `{}` must name a key, like \"G\" or \"F12\"", line, key))?;
+                    config.key_bindings.bind(command, bound)
+                }
+                _ if key.starts_with("compute_variant.") => {
+                    // only `[[compute_variant]]` tables are numbered
+                    let (index, field) = key["compute_variant.".len()..].split_once('.')
+                        .and_then(|(index, field)| Some((index.parse::<usize>().ok()?, field)))
+                        .ok_or_else(|| format!("line {}: unknown key `{}`", line, key))?;
+                    let slot = match field {
+                        "name" => 0,
+                        "path" => 1,
+                        _ => return Err(format!("line {}: unknown key `{}`", line, key)),
+                    };
+                    let text = value.as_str().ok_or_else(|| format!("line {}: `{}` must be a string", line, key))?;
+                    if variants.len() <= index {
+                        variants.resize(index + 1, [None, None]);
+                    }
+                    variants[index][slot] = Some(text.to_string());
+                }
+                _ if key.starts_with("object.") => {
+                    // only `[[object]]` tables are numbered
+                    let (index, field) = key["object.".len()..].split_once('.')
+                        .and_then(|(index, field)| Some((index.parse::<usize>().ok()?, field)))
+                        .ok_or_else(|| format!("line {}: unknown key `{}`", line, key))?;
+                    let vector = || value.as_vec3().map(|[x, y, z]| vec3(x as f32, y as f32, z as f32))
+                        .ok_or_else(|| format!("line {}: `{}` must be an array of 3 numbers", line, key));
+                    if objects.len() <= index {
+                        objects.resize(index + 1, (blank, [false; 2]));
+                    }
+                    let (object, set) = &mut objects[index];
+                    match field {
+                        "pos" => { object.pos = vector()?; set[0] = true }
+                        "radius" => { object.radius = number()? as f32; set[1] = true }
+                        "color" => object.color = vector()?,
+                        "mass" => object.mass = number()?,
+                        _ => return Err(format!("line {}: unknown key `{}`", line, key)),
+                    }
+                }
+                _ => return Err(format!("line {}: unknown key `{}`", line, key)),
+            }
+        }
+        config.compute_variants = variants.into_iter().enumerate().map(|(index, fields)| match fields {
+            [Some(name), Some(path)] => Ok(ComputeVariant { name, path }),
+            _ => Err(format!("compute_variant {} needs a name and a path", index + 1)),
+        }).collect::<Result<_, _>>()?;
+        config.objects = objects.into_iter().enumerate().map(|(index, (object, set))| match set {
+            [true, true] => Ok(object),
+            _ => Err(format!("object {} needs a pos and a radius", index + 1)),
+        }).collect::<Result<_, _>>()?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        if self.black_hole_mass <= 0.0 {
+            return Err(format!("black_hole.mass must be positive, got {}", self.black_hole_mass));
+        }
+        if !(self.camera_min_radius > Length::ZERO && self.camera_min_radius < self.camera_max_radius) {
+            return Err(format!("camera.min_radius ({}) must be positive and below camera.max_radius ({})",
+                self.camera_min_radius.in_meters(), self.camera_max_radius.in_meters()));
+        }
+        let camera_radius = Length::meters(self.camera_radius);
+        if camera_radius < self.camera_min_radius || camera_radius > self.camera_max_radius {
+            return Err(format!("camera.radius ({}) must lie between camera.min_radius and camera.max_radius", self.camera_radius));
+        }
+        if !(0.0..=MAX_SPIN).contains(&self.black_hole_spin) {
+            return Err(format!("black_hole.spin must lie between 0 and {}, got {}", MAX_SPIN, self.black_hole_spin));
+        }
+        if self.integration_step_size.is_some_and(|step| step.is_nan() || step <= 0.0) {
+            return Err(format!("integrator.step_size must be positive, got {}", self.integration_step_size.unwrap()));
+        }
+        if !(1e-7..=1e-1).contains(&self.integration_tolerance) {
+            return Err(format!("integrator.tolerance must lie between 1e-7 and 1e-1, got {}", self.integration_tolerance));
+        }
+        if self.horizon_epsilon.is_some_and(|epsilon| !(0.0..=MAX_HORIZON_EPSILON).contains(&epsilon)) {
+            return Err(format!("integrator.horizon_epsilon must lie between 0 and {}, got {:?}", MAX_HORIZON_EPSILON, self.horizon_epsilon.unwrap()));
+        }
+        if !(MIN_EXPOSURE..=MAX_EXPOSURE).contains(&self.exposure) {
+            return Err(format!("image.exposure must lie between {} and {} EV, got {}", MIN_EXPOSURE, MAX_EXPOSURE, self.exposure));
+        }
+        if self.bloom_threshold < 0.0 || self.bloom_intensity < 0.0 {
+            return Err(format!("image.bloom_threshold ({}) and image.bloom_intensity ({}) can't be negative",
+                self.bloom_threshold, self.bloom_intensity));
+        }
+        if self.lens_flare_intensity < 0.0 {
+            return Err(format!("image.lens_flare_intensity can't be negative, got {}", self.lens_flare_intensity));
+        }
+        if !(0.0..=1.0).contains(&self.edge_threshold) {
+            return Err(format!("image.edge_threshold must lie between 0 and 1, got {}", self.edge_threshold));
+        }
+        if self.fixed_timestep.is_some_and(|dt| !(dt > 0.0 && dt <= 1.0)) {
+            return Err(format!("simulation.fixed_timestep must lie above 0 and up to 1 second, got {:?}", self.fixed_timestep.unwrap()));
+        }
+        if self.camera_sensitivity <= 0.0 {
+            return Err(format!("camera.sensitivity must be positive, got {}", self.camera_sensitivity));
+        }
+        if self.camera_transition_time < 0.0 {
+            return Err(format!("camera.transition_time must not be negative, got {}", self.camera_transition_time));
+        }
+        if !(MIN_FOV..=MAX_FOV).contains(&self.camera_fov_degrees) {
+            return Err(format!("camera.fov_degrees must lie between {} and {}, got {}", MIN_FOV, MAX_FOV, self.camera_fov_degrees));
+        }
+        if self.camera_elevation <= 0.0 || self.camera_elevation >= PI {
+            return Err(format!("camera.elevation ({}) must lie strictly between 0 and pi", self.camera_elevation));
+        }
+        if !(0.0..=1.0).contains(&self.star_density) {
+            return Err(format!("starfield.density must lie between 0 and 1, got {}", self.star_density));
+        }
+        if self.star_brightness < 0.0 {
+            return Err(format!("starfield.brightness can't be negative, got {}", self.star_brightness));
+        }
+        if self.background_color.x < 0.0 || self.background_color.y < 0.0 || self.background_color.z < 0.0 {
+            return Err(format!("background.color can't have negative channels, got {:?}", self.background_color));
+        }
+        if self.disk_profile != DiskProfile::Gradient && self.disk_texture.is_none() {
+            return Err(format!("disk.profile {:?} reads the disk texture, set disk.texture too", self.disk_profile));
+        }
+        if self.disk_peak_temperature.is_some() && self.disk_texture.is_some() {
+            return Err("disk.peak_temperature and disk.texture both color the disk, set only one".to_string());
+        }
+        if self.disk_peak_temperature.is_some_and(|kelvin| kelvin < 1000.0) {
+            return Err(format!("disk.peak_temperature must be at least 1000 K, got {}", self.disk_peak_temperature.unwrap()));
+        }
+        if !(1000.0..=40000.0).contains(&self.disk_max_temperature) {
+            return Err(format!("disk.max_temperature must lie between 1000 and 40000 K, got {}", self.disk_max_temperature));
+        }
+        if self.disk_thickness.is_nan() || self.disk_thickness <= 0.0 {
+            return Err(format!("disk.thickness must be positive, got {}", self.disk_thickness));
+        }
+        if !(0.0..=PI).contains(&self.disk_inclination) {
+            return Err(format!("disk.inclination must lie between 0 and pi radians, got {}", self.disk_inclination));
+        }
+        if self.disk_density_scale < 0.0 || self.disk_emission_scale < 0.0 {
+            return Err(format!("disk.density_scale ({}) and disk.emission_scale ({}) can't be negative",
+                self.disk_density_scale, self.disk_emission_scale));
+        }
+        let rs = Length::rs(self.black_hole_mass);
+        if !(self.disk_inner_radius >= rs && self.disk_inner_radius < self.disk_outer_radius) {
+            return Err(format!("disk.inner_radius ({}) must be outside the horizon ({:.4e}) and below disk.outer_radius ({})",
+                self.disk_inner_radius.in_meters(), rs.in_meters(), self.disk_outer_radius.in_meters()));
+        }
+        if let Some(variant) = self.compute_variants.iter().find(|variant| variant.name.trim().is_empty()
+            || self.compute_variants.iter().filter(|other| other.name == variant.name).count() > 1) {
+            return Err(format!("compute_variant names must be unique and not empty, got `{}`", variant.name));
+        }
+        if self.objects.len() > MAX_OBJECTS {
+            return Err(format!("the shader renders at most {} objects, the scene has {}", MAX_OBJECTS, self.objects.len()));
+        }
+        if let Some(index) = self.objects.iter().position(|object| object.radius.is_nan() || object.radius <= 0.0
+            || !(0.0..=MAX_MASS).contains(&object.mass) || [object.color.x, object.color.y, object.color.z].iter().any(|c| !(0.0..=1.0).contains(c))) {
+            return Err(format!("object {} needs a positive radius, a mass from 0 to {:e} kg and color channels from 0 to 1", index + 1, MAX_MASS));
+        }
+        if !self.camera_allow_inside_horizon && camera_radius < rs * HORIZON_MARGIN {
+            return Err(format!("camera.radius ({}) is within {} Rs ({:.4e}) of the black hole, set camera.allow_inside_horizon to start there",
+                self.camera_radius, HORIZON_MARGIN, (rs * HORIZON_MARGIN).in_meters()));
+        }
+        Ok(())
+    }
+
+    /// Writes the scene back out in the schema `parse` reads, every key spelled out so that
+    /// parsing the text gives this config again. Floats print through {:?}, the shortest text
+    /// that reads back to the same value.
+    pub fn to_toml(&self) -> String {
+        let vector = |v: Vec3| format!("[{:?}, {:?}, {:?}]", v.x, v.y, v.z);
+        let mut toml = String::new();
+        let mut line = |text: String| {
+            toml.push_str(&text);
+            toml.push('\n');
+        };
+        line(format!("[window]\nwidth = {}\nheight = {}\n", self.window_width, self.window_height));
+        line(format!("[compute]\nwidth = {}\nheight = {}\n", self.compute_width, self.compute_height));
+        line(format!("[camera]\nazimuth = {:?}\nelevation = {:?}\nradius = {:?}\ntarget = {}\nmin_radius = {:?}\nmax_radius = {:?}",
+            self.camera_azimuth, self.camera_elevation, self.camera_radius, vector(self.camera_target),
+            self.camera_min_radius.in_meters(), self.camera_max_radius.in_meters()));
+        line(format!("sensitivity = {:?}\ntransition_time = {:?}\nallow_inside_horizon = {}\nfov_degrees = {:?}\n",
+            self.camera_sensitivity, self.camera_transition_time, self.camera_allow_inside_horizon, self.camera_fov_degrees));
+        line(format!("[disk]\ninner_radius = {:?}\nouter_radius = {:?}\nmodel = \"{}\"\nthickness = {:?}\ninclination = {:?}\nazimuth = {:?}",
+            self.disk_inner_radius.in_meters(), self.disk_outer_radius.in_meters(), format!("{:?}", self.disk_model).to_lowercase(),
+            self.disk_thickness, self.disk_inclination, self.disk_azimuth));
+        line(format!("density_scale = {:?}\nemission_scale = {:?}\nprofile = \"{}\"\nmax_temperature = {:?}",
+            self.disk_density_scale, self.disk_emission_scale, format!("{:?}", self.disk_profile).to_lowercase(), self.disk_max_temperature));
+        if let Some(path) = &self.disk_texture {
+            line(format!("texture = \"{}\"", path));
+        }
+        if let Some(kelvin) = self.disk_peak_temperature {
+            line(format!("peak_temperature = {:?}", kelvin));
+        }
+        line(format!("\n[black_hole]\nmass = {:?}\nspin = {:?}\n", self.black_hole_mass, self.black_hole_spin));
+        line(format!("[integrator]\nquality = \"{}\"\nmode = \"{}\"\ntolerance = {:?}",
+            format!("{:?}", self.quality).to_lowercase(), format!("{:?}", self.integration_mode).to_lowercase(), self.integration_tolerance));
+        if let Some(steps) = self.integration_steps {
+            line(format!("steps = {}", steps));
+        }
+        if let Some(step) = self.integration_step_size {
+            line(format!("step_size = {:?}", step));
+        }
+        if let Some(epsilon) = self.horizon_epsilon {
+            line(format!("horizon_epsilon = {:?}", epsilon));
+        }
+        line(format!("\n[image]\nexposure = {:?}\nbloom = {}\nbloom_threshold = {:?}\nbloom_intensity = {:?}\nlens_flare = {}\nlens_flare_intensity = {:?}\nedge_threshold = {:?}\nedge_samples = {}\n",
+            self.exposure, self.bloom, self.bloom_threshold, self.bloom_intensity, self.lens_flare, self.lens_flare_intensity,
+            self.edge_threshold, self.edge_samples));
+        line(format!("[simulation]\ngravity = {}\nseed = {}", self.gravity, self.seed));
+        if let Some(dt) = self.fixed_timestep {
+            line(format!("fixed_timestep = {:?}", dt));
+        }
+        line(String::new());
+        line(format!("[starfield]\nenabled = {}\ndensity = {:?}\nbrightness = {:?}\nseed = {}\n",
+            self.starfield, self.star_density, self.star_brightness, self.star_seed));
+        line(format!("[background]\ncolor = {}", vector(self.background_color)));
+        if let Some(path) = &self.background {
+            line(format!("path = \"{}\"", path));
+        }
+        let rebound = self.key_bindings.changed();
+        if !rebound.is_empty() {
+            line("\n[keys]".to_string());
+        }
+        for (command, key) in rebound {
+            line(format!("{} = \"{}\"", command.name(), key_name(key).unwrap()));
+        }
+        for object in &self.objects {
+            line(format!("\n[[object]]\npos = {}\nradius = {:?}\ncolor = {}\nmass = {:?}",
+                vector(object.pos), object.radius, vector(object.color), object.mass));
+        }
+        for variant in &self.compute_variants {
+            line(format!("\n[[compute_variant]]\nname = \"{}\"\npath = \"{}\"", variant.name, variant.path));
+        }
+        toml
+    }
+}
+
+/// Size and sample count of an offline render, see `Engine::render_to_file`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RenderOptions {
+    pub width: u32,
+    pub height: u32,
+    pub samples: u32, // jittered frames accumulated per pixel
+    pub supersampling: u32, // compute texels per output pixel along each axis, see `Engine::set_supersampling`
+}
+
+impl Default for RenderOptions {
+    fn default() -> Self {
+        RenderOptions { width: 1920, height: 1080, samples: 64, supersampling: 1 }
+    }
+}
+
+/// A turntable recording, see `Engine::record_orbit`. Parsed from the `--orbit` spec, a comma
+/// separated list like `frames=240,revs=1,out=./frames` where every key is optional.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrbitOptions {
+    pub frames: u32,
+    pub revolutions: f64,
+    pub out_dir: String,
+    pub elevation: Option<f64>, // held for the whole sweep, the scene's when unset
+    pub radius: Option<f64>,
+    pub video: Option<String>, // also encode the frames into this file with ffmpeg
+    pub fps: u32,
+}
+
+impl Default for OrbitOptions {
+    fn default() -> Self {
+        OrbitOptions { frames: 240, revolutions: 1.0, out_dir: "./frames".to_string(), elevation: None, radius: None, video: None, fps: 30 }
+    }
+}
+
+impl OrbitOptions {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut orbit = OrbitOptions::default();
+        for entry in spec.split(',').filter(|entry| !entry.is_empty()) {
+            let (key, value) = entry.split_once('=').ok_or_else(|| format!("--orbit: expected `key=value`, found `{}`", entry))?;
+            let number = || value.parse::<f64>().ok().filter(|n| n.is_finite())
+                .ok_or_else(|| format!("--orbit: `{}` must be a number, got `{}`", key, value));
+            let count = || value.parse::<u32>().ok().filter(|n| *n > 0)
+                .ok_or_else(|| format!("--orbit: `{}` must be a positive integer, got `{}`", key, value));
+            match key {
+                "frames" => orbit.frames = count()?,
+                "revs" => orbit.revolutions = number()?,
+                "out" => orbit.out_dir = value.to_string(),
+                "elevation" => {
+                    let elevation = number()?;
+                    if elevation <= 0.0 || elevation >= std::f64::consts::PI {
+                        return Err(format!("--orbit: elevation ({}) must lie strictly between 0 and pi", elevation));
+                    }
+                    orbit.elevation = Some(elevation);
+                }
+                "radius" => {
+                    let radius = number()?;
+                    if radius <= 0.0 {
+                        return Err(format!("--orbit: radius must be positive, got {}", radius));
+                    }
+                    orbit.radius = Some(radius);
+                }
+                "video" => orbit.video = Some(value.to_string()),
+                "fps" => orbit.fps = count()?,
+                _ => return Err(format!("--orbit: unknown key `{}`", key)),
+            }
+        }
+        Ok(orbit)
+    }
+}
+
+/// A performance run, see `Engine::benchmark`. Parsed from the `--benchmark` spec, a comma
+/// separated list like `frames=200,sizes=800x600/1920x1080,steps=15000/60000` where every key is
+/// optional; `sizes` and `steps` take `/` separated lists and every size is timed with every step
+/// count. Left out, they default to the scene's compute resolution and quality.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BenchmarkOptions {
+    pub frames: u32, // timed frames per combination
+    pub warmup: u32, // untimed frames before those
+    pub sizes: Vec<(u32, u32)>,
+    pub steps: Vec<i32>,
+}
+
+impl Default for BenchmarkOptions {
+    fn default() -> Self {
+        BenchmarkOptions { frames: 100, warmup: 10, sizes: Vec::new(), steps: Vec::new() }
+    }
+}
+
+impl BenchmarkOptions {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let mut benchmark = BenchmarkOptions::default();
+        for entry in spec.split(',').filter(|entry| !entry.is_empty()) {
+            let (key, value) = entry.split_once('=').ok_or_else(|| format!("--benchmark: expected `key=value`, found `{}`", entry))?;
+            let count = |value: &str| value.parse::<u32>().ok().filter(|n| *n > 0)
+                .ok_or_else(|| format!("--benchmark: `{}` must be a positive integer, got `{}`", key, value));
+            match key {
+                "frames" => benchmark.frames = count(value)?,
+                "warmup" => {
+                    benchmark.warmup = value.parse().map_err(|_| format!("--benchmark: `warmup` must be an integer, got `{}`", value))?
+                }
+                "sizes" => {
+                    benchmark.sizes = value.split('/').map(|size| {
+                        size.split_once('x').and_then(|(w, h)| Some((w.parse::<u32>().ok()?, h.parse::<u32>().ok()?)))
+                            .filter(|&(w, h)| w > 0 && h > 0)
+                            .ok_or_else(|| format!("--benchmark: sizes must look like 800x600/1920x1080, got `{}`", size))
+                    }).collect::<Result<_, _>>()?
+                }
+                "steps" => {
+                    benchmark.steps = value.split('/').map(|steps| {
+                        let steps = count(steps)?;
+                        if steps > MAX_INTEGRATION_STEPS as u32 {
+                            return Err(format!("--benchmark: at most {} steps per ray, got {}", MAX_INTEGRATION_STEPS, steps));
+                        }
+                        Ok(steps as i32)
+                    }).collect::<Result<_, _>>()?
+                }
+                _ => return Err(format!("--benchmark: unknown key `{}`", key)),
+            }
+        }
+        Ok(benchmark)
+    }
+}
+
+/// Startup options taken from the command line. Anything left unset comes from the scene file,
+/// or from the built-in defaults when there is none.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct EngineOptions {
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    pub compute_width: Option<u32>,
+    pub compute_height: Option<u32>,
+    pub scene: Option<String>,
+    pub background: Option<String>,
+    pub background_color: Option<Vec3>, // replaces the scene's background.color
+    pub gravity: bool,
+    pub render: Option<String>, // render a single image to this path instead of opening the viewer
+    pub render_options: RenderOptions, // also sizes the frames of an orbit
+    pub orbit: Option<OrbitOptions>, // record a turntable instead of opening the viewer
+    pub timeline: Option<String>, // camera timeline played from the start
+    pub record: Option<String>, // write every frame of the timeline to this directory instead of opening the viewer
+    pub benchmark: Option<BenchmarkOptions>, // time frames and print a table instead of opening the viewer
+    pub no_vsync: bool, // present frames as fast as they render
+    pub seed: Option<u64>, // replaces the scene's simulation.seed
+    pub aspect: Option<f32>, // width over height the picture is locked to, see `Engine::set_output_aspect`
+    pub monitor: Option<usize>, // the window opens centered on, and goes fullscreen on, this monitor, 0 the primary
+    pub list_monitors: bool, // print the connected monitors and exit
+}
+
+impl EngineOptions {
+    pub const USAGE: &'static str = "usage: black_hole_rs [--width N] [--height N] [--compute-width N] [--compute-height N] [--scene PATH] [--background PATH] [--background-color R,G,B] [--gravity] [--render OUT.png | --orbit frames=N,revs=R,out=DIR[,elevation=E,radius=R,video=OUT.mp4,fps=N]] [--timeline PATH [--record DIR]] [--render-size WxH] [--samples N] [--supersample N] [--benchmark frames=N,warmup=N,sizes=WxH/WxH,steps=N/N] [--no-vsync] [--seed N] [--aspect R | W:H] [--monitor N] [--list-monitors]";
+
+    /// Parses the arguments after the program name, accepting both `--flag value` and `--flag=value`.
+    pub fn from_args<I: IntoIterator<Item = String>>(args: I) -> Result<Self, String> {
+        let mut options = EngineOptions::default();
+        let mut args = args.into_iter();
+        let mut render_flag = None;
+        while let Some(arg) = args.next() {
+            let (flag, inline) = match arg.split_once('=') {
+                Some((flag, value)) if flag.starts_with("--") => (flag.to_string(), Some(value.to_string())),
+                _ => (arg.clone(), None),
+            };
+            if flag == "--gravity" || flag == "--no-vsync" || flag == "--list-monitors" {
+                if inline.is_some() {
+                    return Err(format!("{} doesn't take a value", flag));
+                }
+                match flag.as_str() {
+                    "--gravity" => options.gravity = true,
+                    "--list-monitors" => options.list_monitors = true,
+                    _ => options.no_vsync = true,
+                }
+                continue;
+            }
+
+            let mut value = || inline.clone().or_else(|| args.next()).ok_or_else(|| format!("{} needs a value", flag));
+            let mut dimension = || {
+                let value = value()?;
+                value.parse::<u32>().ok().filter(|n| *n > 0)
+                    .ok_or_else(|| format!("{} must be a positive integer, got `{}`", flag, value))
+            };
+            match flag.as_str() {
+                "--width" => options.width = Some(dimension()?),
+                "--height" => options.height = Some(dimension()?),
+                "--compute-width" => options.compute_width = Some(dimension()?),
+                "--compute-height" => options.compute_height = Some(dimension()?),
+                "--scene" => options.scene = Some(value()?),
+                "--background" => options.background = Some(value()?),
+                "--background-color" => {
+                    let value = value()?;
+                    let channels = value.split(',').map(|channel| channel.trim().parse::<f32>().ok().filter(|n| n.is_finite() && *n >= 0.0))
+                        .collect::<Option<Vec<f32>>>();
+                    let Some(&[r, g, b]) = channels.as_deref() else {
+                        return Err(format!("--background-color must be three non-negative numbers like 0.02,0.02,0.05, got `{}`", value));
+                    };
+                    options.background_color = Some(vec3(r, g, b));
+                }
+                "--render" => options.render = Some(value()?),
+                "--orbit" => options.orbit = Some(OrbitOptions::parse(&value()?)?),
+                "--timeline" => options.timeline = Some(value()?),
+                "--record" => options.record = Some(value()?),
+                "--benchmark" => options.benchmark = Some(BenchmarkOptions::parse(&value()?)?),
+                "--seed" => {
+                    let value = value()?;
+                    options.seed = Some(value.parse::<u64>().map_err(|_| format!("--seed must be a non-negative integer, got `{}`", value))?);
+                }
+                "--monitor" => {
+                    let value = value()?;
+                    options.monitor = Some(value.parse::<usize>().map_err(|_| format!("--monitor must be a monitor index from --list-monitors, got `{}`", value))?);
+                }
+                "--aspect" => {
+                    // 2.39, or 16:9 as the two sides
+                    let value = value()?;
+                    let ratio = match value.split_once(':') {
+                        Some((w, h)) => w.parse::<f32>().ok().zip(h.parse::<f32>().ok()).map(|(w, h)| w / h),
+                        None => value.parse::<f32>().ok(),
+                    };
+                    let Some(ratio) = ratio.filter(|ratio| ratio.is_finite() && *ratio > 0.0) else {
+                        return Err(format!("--aspect must be a positive ratio like 2.39 or 16:9, got `{}`", value));
+                    };
+                    options.aspect = Some(ratio);
+                }
+                "--render-size" => {
+                    let value = value()?;
+                    let size = value.split_once('x').and_then(|(w, h)| Some((w.parse::<u32>().ok()?, h.parse::<u32>().ok()?)));
+                    let Some((width, height)) = size.filter(|&(w, h)| w > 0 && h > 0) else {
+                        return Err(format!("--render-size must look like 3840x2160, got `{}`", value));
+                    };
+                    options.render_options.width = width;
+                    options.render_options.height = height;
+                    render_flag = Some(flag.clone());
+                }
+                "--samples" => {
+                    options.render_options.samples = dimension()?;
+                    render_flag = Some(flag.clone());
+                }
+                "--supersample" => {
+                    options.render_options.supersampling = dimension()?;
+                    render_flag = Some(flag.clone());
+                }
+                _ => return Err(format!("unexpected argument `{}`", arg)),
+            }
+        }
+        let offline = [options.render.is_some(), options.orbit.is_some(), options.record.is_some()];
+        if offline.iter().filter(|&&set| set).count() > 1 {
+            return Err("only one of --render, --orbit and --record can be given".to_string());
+        }
+        if options.benchmark.is_some() && offline.contains(&true) {
+            return Err("--benchmark can't be combined with --render, --orbit or --record".to_string());
+        }
+        if options.record.is_some() && options.timeline.is_none() {
+            return Err("--record needs a --timeline to play".to_string());
+        }
+        if let Some(flag) = render_flag.filter(|_| !offline.contains(&true)) {
+            return Err(format!("{} only applies together with --render, --orbit or --record", flag));
+        }
+        Ok(options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    pub fn config_parses_tables_and_keeps_defaults() {
+        let config = Config::parse("
+            # a closer look
+            [camera]
+            radius = 5e10 # meters
+            [compute]
+            width = 400
+            height = 300
+            [simulation]
+            gravity = true
+        ").unwrap();
+
+        assert_eq!(config.camera_radius, 5e10);
+        assert_eq!((config.compute_width, config.compute_height), (400, 300));
+        assert!(config.gravity);
+        assert_eq!(Config::parse("[simulation]\nseed = 42").unwrap().seed, 42);
+        assert!(Config::parse("[simulation]\nseed = 1.5").is_err());
+        assert_eq!(config.window_width, Config::default().window_width);
+
+        let config = Config::parse("[[compute_variant]]\nname = \"rk45\"\npath = \"./shaders/geodesic_rk45_cs.glsl\"").unwrap();
+        assert_eq!(config.compute_variants, [ComputeVariant { name: "rk45".to_string(), path: "./shaders/geodesic_rk45_cs.glsl".to_string() }]);
+        assert_eq!(ComputeVariant::from_path("./shaders/geodesic_rk45_cs.glsl").name, "geodesic_rk45");
+
+        assert!(Config::default().objects.is_empty(), "objects come from the scene");
+        let config = Config::parse("[[object]]\npos = [4e11, 0, 0]\nradius = 4e10\ncolor = [1, 1, 0]\n[[object]]\npos = [0, 0, 4e11]\nradius = 4e10").unwrap();
+        assert_eq!(config.objects, [
+            GravObject { pos: vec3(4e11, 0.0, 0.0), radius: 4e10, color: vec3(1.0, 1.0, 0.0), mass: SOLAR_MASS },
+            GravObject { pos: vec3(0.0, 0.0, 4e11), radius: 4e10, color: vec3(1.0, 1.0, 1.0), mass: SOLAR_MASS },
+        ]);
+    }
+
+    #[test]
+    pub fn config_round_trips_through_toml() {
+        assert_eq!(Config::parse(&Config::default().to_toml()), Ok(Config::default()));
+
+        let mut config = Config {
+            camera_azimuth: 0.1 + 0.2, // digits that don't print exactly
+            camera_radius: 1.0 / 3.0 * 1e11,
+            camera_target: vec3(1e9, -2.5e8, 1.0 / 3.0),
+            camera_fov_degrees: 45.5,
+            disk_model: DiskModel::Thick,
+            disk_texture: Some("disk_ramp.png".to_string()),
+            disk_profile: DiskProfile::Temperature,
+            black_hole_mass: 1.2345678901234e37,
+            black_hole_spin: 0.7,
+            quality: QualityPreset::Medium,
+            integration_steps: Some(12345),
+            integration_step_size: Some(3.3e7),
+            integration_mode: IntegrationMode::Adaptive,
+            horizon_epsilon: Some(0.01),
+            exposure: -1.5,
+            bloom: true,
+            lens_flare: true,
+            lens_flare_intensity: 0.5,
+            edge_threshold: 0.25,
+            edge_samples: 32,
+            seed: 42,
+            fixed_timestep: Some(1.0 / 30.0),
+            background: Some("milky_way.hdr".to_string()),
+            background_color: vec3(0.01, 0.02, 0.03),
+            objects: vec![
+                GravObject { pos: vec3(4e11, 0.0, 1.0 / 3.0), radius: 4e10, color: vec3(1.0, 0.5, 0.0), mass: SOLAR_MASS },
+                GravObject { pos: vec3(-2.5e11, 1e9, 0.0), radius: 1.0 / 7.0 * 1e10, color: vec3(0.6, 0.8, 1.0), mass: 0.0 },
+            ],
+            compute_variants: vec![ComputeVariant { name: "rk45".to_string(), path: "./shaders/geodesic_rk45_cs.glsl".to_string() }],
+            ..Config::default()
+        };
+        config.key_bindings.bind(Command::Screenshot, glfw::Key::Delete);
+        assert_eq!(Config::parse(&config.to_toml()), Ok(config));
+    }
+
+    #[test]
+    pub fn options_parse_flags() {
+        let args = ["--compute-width", "400", "--compute-height=300", "--scene", "a.toml", "--gravity"];
+        let options = EngineOptions::from_args(args.iter().map(|arg| arg.to_string())).unwrap();
+        assert_eq!(options, EngineOptions {
+            compute_width: Some(400),
+            compute_height: Some(300),
+            scene: Some("a.toml".to_string()),
+            gravity: true,
+            ..EngineOptions::default()
+        });
+
+        let args = ["--render", "out.png", "--render-size=3840x2160", "--samples", "256", "--supersample=2"];
+        let options = EngineOptions::from_args(args.iter().map(|arg| arg.to_string())).unwrap();
+        assert_eq!(options.render.as_deref(), Some("out.png"));
+        assert_eq!(options.render_options, RenderOptions { width: 3840, height: 2160, samples: 256, supersampling: 2 });
+        assert!(EngineOptions::from_args(["--samples=16".to_string()]).is_err());
+        assert!(EngineOptions::from_args(["--render=a.png".to_string(), "--render-size=4k".to_string()]).is_err());
+
+        let orbit = OrbitOptions::parse("frames=120,revs=0.5,out=shots,radius=5e10").unwrap();
+        assert_eq!(orbit, OrbitOptions { frames: 120, revolutions: 0.5, out_dir: "shots".to_string(), radius: Some(5e10), ..OrbitOptions::default() });
+        assert!(OrbitOptions::parse("frames=0").is_err());
+        assert!(OrbitOptions::parse("elevation=4").is_err());
+        assert!(EngineOptions::from_args(["--orbit=frames=2".to_string(), "--render=a.png".to_string()]).is_err());
+        assert!(EngineOptions::from_args(["--record=frames".to_string()]).is_err());
+
+        let benchmark = BenchmarkOptions::parse("frames=50,sizes=800x600/1920x1080,steps=15000/60000").unwrap();
+        assert_eq!(benchmark, BenchmarkOptions { frames: 50, sizes: vec![(800, 600), (1920, 1080)], steps: vec![15000, 60000], ..BenchmarkOptions::default() });
+        assert!(BenchmarkOptions::parse("sizes=800/600").is_err());
+        assert!(BenchmarkOptions::parse("steps=1000000").is_err());
+        assert!(EngineOptions::from_args(["--benchmark=".to_string(), "--render=a.png".to_string()]).is_err());
+
+        assert_eq!(EngineOptions::from_args(["--seed=18446744073709551615".to_string()]).unwrap().seed, Some(u64::MAX));
+        assert!(EngineOptions::from_args(["--seed=-1".to_string()]).is_err());
+
+        assert_eq!(EngineOptions::from_args(["--aspect=2.39".to_string()]).unwrap().aspect, Some(2.39));
+        assert_eq!(EngineOptions::from_args(["--aspect=4:3".to_string()]).unwrap().aspect, Some(4.0 / 3.0));
+        assert!(EngineOptions::from_args(["--aspect=16:0".to_string()]).is_err());
+
+        let options = EngineOptions::from_args(["--monitor".to_string(), "1".to_string(), "--list-monitors".to_string()]).unwrap();
+        assert_eq!((options.monitor, options.list_monitors), (Some(1), true));
+        assert!(EngineOptions::from_args(["--monitor=primary".to_string()]).is_err());
+
+        let options = EngineOptions::from_args(["--background=milky_way.hdr".to_string()]).unwrap();
+        assert_eq!(options.background.as_deref(), Some("milky_way.hdr"));
+        let options = EngineOptions::from_args(["--background-color".to_string(), "0.1,0.2,0.3".to_string()]).unwrap();
+        assert_eq!(options.background_color, Some(vec3(0.1, 0.2, 0.3)));
+        assert!(EngineOptions::from_args(["--background-color=1,1".to_string()]).is_err());
+        assert!(EngineOptions::from_args(["--background-color=0,-1,0".to_string()]).is_err());
+
+        assert!(EngineOptions::from_args(["--width".to_string(), "0".to_string()]).is_err());
+        assert!(EngineOptions::from_args(["--height".to_string()]).is_err());
+        assert!(EngineOptions::from_args(["scene.toml".to_string()]).is_err());
+    }
+
+    #[test]
+    pub fn config_rejects_bad_values() {
+        assert!(Config::parse("[camera]\nmin_radius = 2e12").unwrap_err().contains("min_radius"));
+        assert!(Config::parse("[compute]\nwidth = -4").unwrap_err().starts_with("line 2"));
+        assert!(Config::parse("[disk]\ninner = 1").unwrap_err().contains("unknown key"));
+        assert!(Config::parse("[window\nwidth = 10").is_err());
+        assert!(Config::parse("[camera]\nradius = [1, 2]").unwrap_err().contains("number"));
+        assert!(Config::parse("[background]\npath = 3").unwrap_err().contains("string"));
+        assert_eq!(Config::parse("[background]\ncolor = [0.5, 0, 1]").unwrap().background_color, vec3(0.5, 0.0, 1.0));
+        assert!(Config::parse("[background]\ncolor = [0, -0.1, 0]").unwrap_err().contains("negative"));
+        assert!(Config::parse("[disk]\nprofile = \"temperature\"").unwrap_err().contains("disk.texture"));
+        assert!(Config::parse("[disk]\nprofile = \"plasma\"").unwrap_err().contains("gradient"));
+        assert_eq!(Config::parse("[disk]\nmodel = \"thick\"").unwrap().disk_model, DiskModel::Thick);
+        assert!(Config::parse("[disk]\nthickness = 0").unwrap_err().contains("thickness"));
+        assert!(Config::parse("[disk]\ninclination = 4").unwrap_err().contains("inclination"));
+        assert!(Config::parse("[camera]\nradius = 1.2e10").unwrap_err().contains("allow_inside_horizon"));
+        assert!(Config::parse("[camera]\nradius = 1.2e10\nallow_inside_horizon = true").is_ok());
+        assert!(Config::parse("[camera]\nfov_degrees = 179").unwrap_err().contains("fov_degrees"));
+        assert!(Config::parse("[black_hole]\nspin = 1").unwrap_err().contains("spin"));
+        assert!(Config::parse("[integrator]\nsteps = 0").unwrap_err().contains("steps"));
+        assert!(Config::parse("[integrator]\nmode = \"euler\"").unwrap_err().contains("adaptive"));
+        assert!(Config::parse("[integrator]\nhorizon_epsilon = 0.5").unwrap_err().contains("horizon_epsilon"));
+        assert!(Config::parse("[image]\nexposure = 20").unwrap_err().contains("exposure"));
+        assert!(Config::parse("[image]\nedge_threshold = 2").unwrap_err().contains("edge_threshold"));
+        assert!(Config::parse("[image]\nedge_samples = 1000").unwrap_err().contains("edge_samples"));
+        assert!(Config::parse("[simulation]\nfixed_timestep = 0").unwrap_err().contains("fixed_timestep"));
+        assert!(Config::parse("[image]\nlens_flare_intensity = -1").unwrap_err().contains("lens_flare_intensity"));
+        assert_eq!(Config::parse("[keys]\nreload = \"f5\"").unwrap().key_bindings.command(glfw::Key::F5), Some(Command::Reload));
+        assert!(Config::parse("[keys]\nteleport = \"T\"").unwrap_err().contains("unknown key"));
+        assert!(Config::parse("[keys]\nreload = \"Hyper\"").unwrap_err().contains("must name a key"));
+        assert!(Config::parse("[compute_variant]\nname = \"x\"").unwrap_err().contains("unknown key"));
+        assert!(Config::parse("[compute_variant.x]\nname = \"x\"").unwrap_err().contains("unknown key"));
+        assert!(Config::parse("[[compute_variant]]\nname = \"a\"").unwrap_err().contains("path"));
+        assert!(Config::parse("[[compute_variant]]\nname = \"a\"\npath = \"a.glsl\"\n[[compute_variant]]\nname = \"a\"\npath = \"b.glsl\"")
+            .unwrap_err().contains("unique"));
+        assert!(Config::parse("[[object]]\npos = [1, 2, 3]").unwrap_err().contains("radius"));
+        assert!(Config::parse("[[object]]\npos = [1, 2, 3]\nradius = 1\nmass = -1").unwrap_err().contains("mass"));
+        assert!(Config::parse("[[object]]\npos = [1, 2, 3]\nradius = 1\nspin = 1").unwrap_err().contains("unknown key"));
+        assert!(Config::parse(&"[[object]]\npos = [1, 2, 3]\nradius = 1\n".repeat(MAX_OBJECTS + 1)).unwrap_err().contains("at most"));
+    }
+}
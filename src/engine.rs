@@ -0,0 +1,4605 @@
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::ffi::CString;
+use std::{mem, ptr};
+use std::os::raw::c_void;
+use gl::types::{GLfloat, GLint, GLsizei, GLsizeiptr};
+use glfw::{Action, Context, Key};
+use glm::{vec3, Vec3};
+use log::{debug, error, info, warn};
+use crate::benchmark::{self, BenchmarkResult, FrameProfiler, FrameStats, GpuTimer, GpuTimings};
+use crate::bindings::Command;
+use crate::bookmarks::{self, Bookmarks};
+use crate::camera::{Camera, CameraMode, CameraState, HORIZON_MARGIN, MAX_FOV, MIN_FOV};
+use crate::config::{BenchmarkOptions, Config, EngineOptions, RenderOptions};
+use crate::geodesic::{self, Fate};
+use crate::gl_debug::{self, gl_check};
+use crate::overlay::TextOverlay;
+use crate::panel::{Panel, Param, ParamCursor, SLIDERS};
+use crate::particle::{Apsides, TestParticle};
+use crate::shader::{self, ComputeVariant, ShaderPaths};
+use crate::timeline::CameraTimeline;
+use crate::ubo::{self, BlackHoleUbo, CameraUbo, DiskUbo, IntegratorUbo, ObjectsUbo, SkyUbo};
+use crate::units::Length;
+
+/// Speed of light in m/s.
+pub const C: f64 = 299792458.0;
+/// Newton's gravitational constant in m³/(kg s²).
+pub const G: f64 = 6.67430e-11;
+/// Work group size of the geodesic compute shader (`local_size_x`/`local_size_y` in geodesic_cs.glsl).
+/// Dispatches are counted in whole groups, so the group count is the compute resolution divided by
+/// this and rounded up; the shader discards the invocations that land past the image edge.
+pub const LOCAL_SIZE: u32 = 16;
+pub const MIN_RESOLUTION_SCALE: f32 = 0.05;
+pub const MAX_RESOLUTION_SCALE: f32 = 2.0;
+pub const MAX_SUPERSAMPLING: u32 = 4; // 16 compute texels per pixel
+pub const MAX_OBJECTS: usize = 16; // array size of the Objects UBO in the compute shader
+pub const SAGA_RS: f64 = 1.269e10; // Schwarzschild radius of Sagittarius A*, the default black hole
+/// Upper limit of the dimensionless spin a/M, the Thorne limit for a hole spun up by an accretion disk.
+pub const MAX_SPIN: f64 = 0.998;
+/// Upper bound on geodesic integration steps per ray, beyond which a frame would stall the GPU for seconds.
+pub const MAX_INTEGRATION_STEPS: i32 = 200_000;
+/// Horizontal plus vertical blur rounds of the bloom, each one widens the glow by 8 texels.
+pub const BLOOM_BLUR_PASSES: usize = 4;
+/// Regions along each axis the lens flare splits the image into to find its brightest spot.
+pub const LENS_FLARE_REGIONS: GLint = 16;
+/// Upper limit of the jittered samples accumulated per pixel.
+pub const MAX_AA_SAMPLES: u32 = 256;
+/// Largest margin above the horizon, in rs, at which rays end as captured: well inside the
+/// photon sphere at 1.5 rs. See `Engine::set_horizon_epsilon`.
+pub const MAX_HORIZON_EPSILON: f32 = 0.4;
+/// Highest order of the disk images rays look for, see `Engine::set_max_image_order`.
+pub const MAX_IMAGE_ORDER: u32 = 4;
+/// Points kept in a test particle's trail, the oldest fading out first.
+pub const TRAIL_POINTS: usize = 1200;
+/// How fast test particles move: radians per second on screen a circular orbit at the radius
+/// they were dropped at would turn, so any orbit takes a few seconds, near or far.
+pub const PARTICLE_TIME_SCALE: f64 = 2.0;
+/// Where camera bookmarks are kept between runs, relative to the working directory like the shaders.
+pub const BOOKMARKS_PATH: &str = "./bookmarks.json";
+pub const GEODESIC_CSV_PATH: &str = "./geodesic.csv"; // where F8 exports the ray under the cursor
+pub const SCENE_EXPORT_PATH: &str = "./scene.toml"; // where Ctrl+S saves the current scene
+pub const MIN_EXPOSURE: f32 = -8.0; // EV
+pub const MAX_EXPOSURE: f32 = 8.0;
+pub const SOLAR_MASS: f64 = 1.98892e30; // kg
+/// Range of the black hole mass, from a stellar remnant up to the heaviest known quasars.
+pub const MIN_MASS: f64 = SOLAR_MASS;
+pub const MAX_MASS: f64 = 1e11 * SOLAR_MASS;
+
+// Two counter-clockwise triangles covering the screen, which back face culling keeps
+const QUAD_VERTICES: [GLfloat; 24] = [
+    // positions   // texCoords
+    -1.0,  1.0,  0.0, 1.0,  // top left
+    -1.0, -1.0,  0.0, 0.0,  // bottom left
+    1.0, -1.0,  1.0, 0.0,  // bottom right
+
+    -1.0,  1.0,  0.0, 1.0,  // top left
+    1.0, -1.0,  1.0, 0.0,  // bottom right
+    1.0,  1.0,  1.0, 1.0   // top right
+];
+
+/// How the compute shader advances each geodesic, mirrored as `integration_mode` in the Integrator UBO.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IntegrationMode {
+    /// Constant steps of `step_size`, `max_steps` per ray.
+    Fixed = 0,
+    /// Cash-Karp RK45 with the step sized to keep the error under `tolerance`; starts at `step_size`
+    /// and spends its `max_steps` attempts where the ray bends the most.
+    Adaptive = 1,
+}
+
+/// Where the disk's emission comes from, mirrored as `disk_profile` in the Disk UBO.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiskProfile {
+    /// The built-in gradient from orange at the inner edge towards yellow at the outer one.
+    Gradient = 0,
+    /// The disk texture holds the emitted colors.
+    Color = 1,
+    /// The red channel of the disk texture holds the temperature as a fraction of the maximum,
+    /// shown in blackbody colors.
+    Temperature = 2,
+}
+
+/// How the disk is drawn, mirrored as `disk_model` in the Disk UBO.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DiskModel {
+    /// An infinitely thin sheet in the equatorial plane, shaded where a ray crosses it.
+    Thin = 0,
+    /// A slab `thickness` tall around the plane. Rays sample it once per integration step on
+    /// their way through, picking up the gas's glow and losing the light behind it to absorption.
+    Thick = 1,
+    /// No disk at all, leaving just the lensed sky and objects.
+    None = 2,
+}
+
+/// Where the camera may be and how it sees, uploaded as the compute shader's `observer_region`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ObserverRegion {
+    /// A static observer outside the horizon, rays traced in Schwarzschild or Boyer-Lindquist
+    /// coordinates. The camera stays HORIZON_MARGIN horizon radii out.
+    Exterior = 0,
+    /// A freely falling observer, dropped from rest far away, who can cross the horizon: rays
+    /// are traced in Painleve-Gullstrand coordinates, which stay regular there, so the view
+    /// changes smoothly on the way in. Non-rotating holes only. The Doppler and gravitational
+    /// shifts are still those a static observer would see.
+    Interior = 1,
+}
+
+/// What the image shows, uploaded as the compute shader's `render_channel`. Every channel but
+/// `Color` maps a quantity of each pixel's ray onto [0, 1] and colors it through the turbo
+/// colormap, shown without exposure, bloom or tone mapping.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RenderChannel {
+    /// The rendered scene.
+    Color = 0,
+    /// How far gravity bent the ray, from straight up to a full turn. The rays that skim the
+    /// photon sphere wind around it, so the bending grows without bound towards the shadow's edge.
+    DeflectionAngle = 1,
+    /// The frequency ratio of the light the ray ends on, emitted over received: 0 for the
+    /// shadow, 1 in the middle of the map for no shift and 2 at the top.
+    Redshift = 2,
+    /// The affine parameter, roughly meters of path, the ray covers until it hits something or
+    /// escapes, up to twice the escape radius.
+    TravelTime = 3,
+    /// How the ray ended, one color each: captured at the horizon, escaped, stopped on the disk
+    /// or an object, or still undecided when its steps ran out.
+    Termination = 4,
+}
+
+/// Integration step presets, cycled with F2. Each preset traces rays out to the same path length
+/// (steps * step = 6e11 m), finer presets just take more, shorter steps. Compute cost grows
+/// linearly with the step count, while too coarse a step makes the photon ring shimmer.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum QualityPreset {
+    Low,
+    Medium,
+    High,
+}
+
+impl QualityPreset {
+    /// Step count and step size in meters for a black hole as heavy as Sagittarius A*, the engine
+    /// scales the step with the Schwarzschild radius of the actual mass.
+    pub fn params(self) -> (i32, f32) {
+        match self {
+            QualityPreset::Low => (15_000, 4e7),
+            QualityPreset::Medium => (30_000, 2e7),
+            QualityPreset::High => (60_000, 1e7),
+        }
+    }
+
+    pub fn next(self) -> Self {
+        match self {
+            QualityPreset::Low => QualityPreset::Medium,
+            QualityPreset::Medium => QualityPreset::High,
+            QualityPreset::High => QualityPreset::Low,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum EngineError {
+    /// A shader source file couldn't be opened or read.
+    Io { path: String, source: std::io::Error },
+    /// A shader stage failed to compile, carries the driver's info log.
+    ShaderCompile(String),
+    /// A program failed to link, carries the driver's info log.
+    ShaderLink(String),
+    /// An image couldn't be encoded or written to disk.
+    Image { path: String, source: image::ImageError },
+    /// A scene file is malformed or holds out-of-range values.
+    Config { path: String, message: String },
+    /// The GPU or driver lacks something the renderer can't do without, like compute shaders.
+    Unsupported(String),
+}
+
+impl std::fmt::Display for EngineError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EngineError::Io { path, source } => write!(f, "couldn't open {}: {}", path, source),
+            EngineError::ShaderCompile(log) => write!(f, "shader compilation failed:\n{}", log),
+            EngineError::ShaderLink(log) => write!(f, "shader link failed:\n{}", log),
+            EngineError::Image { path, source } => write!(f, "couldn't write {}: {}", path, source),
+            EngineError::Config { path, message } => write!(f, "invalid scene file {}: {}", path, message),
+            EngineError::Unsupported(message) => write!(f, "{}", message),
+        }
+    }
+}
+
+impl std::error::Error for EngineError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            EngineError::Io { source, .. } => Some(source),
+            EngineError::Image { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+// Element `index` of the Halton low-discrepancy sequence in `base`, in [0, 1)
+fn halton(mut index: u32, base: u32) -> f32 {
+    let mut fraction = 1.0;
+    let mut result = 0.0;
+    while index > 0 {
+        fraction /= base as f32;
+        result += fraction * (index % base) as f32;
+        index /= base;
+    }
+    result
+}
+
+/// Number of `tile` x `tile` squares covering a `width` x `height` image.
+pub fn tile_count(tile: u32, width: u32, height: u32) -> u32 {
+    width.div_ceil(tile) * height.div_ceil(tile)
+}
+
+/// Pixels of tile `index` as [x0, y0, x1, y1), counted row by row from the top left corner of a
+/// `width` x `height` image whose rows start at the bottom, like a GL texture's. The last
+/// column and the top row are cut to fit.
+pub fn tile_rect(index: u32, tile: u32, width: u32, height: u32) -> [i32; 4] {
+    let columns = width.div_ceil(tile);
+    let (column, row) = (index % columns, index / columns);
+    let top = height.saturating_sub(row * tile);
+    let x0 = column * tile;
+    [x0 as i32, top.saturating_sub(tile) as i32, (x0 + tile).min(width) as i32, top as i32]
+}
+
+/// `rect`, as [x0, y0, x1, y1) like `tile_rect`'s, cut at column `divider` into the part on its
+/// left and the part on its right. One of them is empty, x0 == x1, when the rect lies all on
+/// one side.
+pub fn split_rect(rect: [i32; 4], divider: i32) -> [[i32; 4]; 2] {
+    let divider = divider.clamp(rect[0], rect[2]);
+    [[rect[0], rect[1], divider, rect[3]], [divider, rect[1], rect[2], rect[3]]]
+}
+
+/// The centered part of a `width` x `height` window with the aspect `ratio`, width over height,
+/// as x and y from the top left, width and height. Black bars fill the rest, above and below
+/// when the window is wider than the ratio and at the sides when it is narrower. Without a
+/// ratio it is the whole window.
+pub fn letterbox(width: u32, height: u32, ratio: Option<f32>) -> [u32; 4] {
+    let Some(ratio) = ratio else {
+        return [0, 0, width, height];
+    };
+    if width as f32 > height as f32 * ratio {
+        let w = ((height as f32 * ratio).round() as u32).clamp(1, width);
+        [(width - w) / 2, 0, w, height]
+    } else {
+        let h = ((width as f32 / ratio).round() as u32).clamp(1, height);
+        [0, (height - h) / 2, width, h]
+    }
+}
+
+// The 32 bits of a seed the shaders take, both halves count
+fn fold_seed(seed: u64) -> u32 {
+    (seed ^ (seed >> 32)) as u32
+}
+
+// Writes bottom-up RGBA rows read back from GL to an image file, the format follows the extension
+fn save_pixels(path: &str, width: u32, height: u32, pixels: Vec<u8>) -> Result<(), EngineError> {
+    pixels_to_image(width, height, pixels).save(path).map_err(|source| EngineError::Image { path: path.to_string(), source })
+}
+
+// The image `save_pixels` writes
+fn pixels_to_image(width: u32, height: u32, pixels: Vec<u8>) -> image::RgbaImage {
+    let mut image = image::RgbaImage::from_raw(width, height, pixels).unwrap();
+    // GL's origin is the bottom-left corner, image rows start at the top
+    image::imageops::flip_vertical_in_place(&mut image);
+    // the compute alpha channel isn't opacity, save what the quad actually shows
+    for pixel in image.pixels_mut() {
+        pixel[3] = 255;
+    }
+    image
+}
+
+/// Encodes the numbered frames `record_orbit` wrote to `frames_dir` into a video at `path` with
+/// ffmpeg, which has to be on the PATH.
+pub fn encode_video(frames_dir: &str, path: &str, fps: u32) -> Result<(), EngineError> {
+    let io_error = |source| EngineError::Io { path: path.to_string(), source };
+    let status = std::process::Command::new("ffmpeg")
+        .args(["-y", "-loglevel", "error", "-framerate", &fps.to_string(), "-i", &format!("{}/frame_%05d.png", frames_dir)])
+        // yuv420p needs even dimensions and is what most players expect
+        .args(["-vf", "pad=ceil(iw/2)*2:ceil(ih/2)*2", "-pix_fmt", "yuv420p", path])
+        .status()
+        .map_err(io_error)?;
+    if !status.success() {
+        return Err(io_error(std::io::Error::other(format!("ffmpeg exited with {}", status))));
+    }
+    info!("Encoded {}", path);
+    Ok(())
+}
+
+// GLFW hands back no list at all while no monitor is connected, which the glfw crate's
+// with_connected_monitors doesn't survive, so look for the primary one first
+fn monitors_connected(glfw: &mut glfw::Glfw) -> bool {
+    glfw.with_primary_monitor(|_, monitor| monitor.is_some())
+}
+
+// The monitor at `index` in GLFW's list, which puts the primary one first, or the primary one
+// with a warning when fewer are connected
+fn requested_monitor<'a>(monitors: &'a [&mut glfw::Monitor], index: usize) -> Option<&'a glfw::Monitor> {
+    if index >= monitors.len() {
+        warn!("There is no monitor {} with {} connected, using the primary one", index, monitors.len());
+    }
+    monitors.get(index).or(monitors.first()).map(|monitor| &**monitor)
+}
+
+/// One line per connected monitor, numbered the way `--monitor` takes them: its name, desktop
+/// video mode, position on the desktop and content scale, the primary one first.
+pub fn list_monitors() -> Result<String, EngineError> {
+    let mut glfw = glfw::init(glfw::log_errors)
+        .map_err(|error| EngineError::Unsupported(format!("GLFW failed to start: {}", error)))?;
+    if !monitors_connected(&mut glfw) {
+        return Ok("no monitors connected\n".to_string());
+    }
+    Ok(glfw.with_connected_monitors(|_, monitors| {
+        let mut list = String::new();
+        for (index, monitor) in monitors.iter().enumerate() {
+            let name = monitor.get_name().unwrap_or_else(|| "unnamed".to_string());
+            let mode = monitor.get_video_mode().map_or("no video mode".to_string(), |mode| format!("{}x{} @ {} Hz", mode.width, mode.height, mode.refresh_rate));
+            let ((x, y), (scale, _)) = (monitor.get_pos(), monitor.get_content_scale());
+            list += &format!("{}: {}, {} at ({}, {}), scale {:.2}{}\n", index, name, mode, x, y, scale, if index == 0 { " (primary)" } else { "" });
+        }
+        list
+    }))
+}
+
+// Framebuffer pixels per point for a window content scale, the larger of the two axes. Some
+// platforms report 0 before the window is on a screen, which counts as 1
+fn ui_scale((x, y): (f32, f32)) -> f32 {
+    let scale = x.max(y);
+    if scale.is_finite() && scale > 0.0 { scale } else { 1.0 }
+}
+
+// Decodes an sRGB channel value in [0, 1] to linear intensity
+fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 { v / 12.92 } else { ((v + 0.055) / 1.055).powf(2.4) }
+}
+
+// Encodes linear intensity as an sRGB channel value, clipped to [0, 1]. The inverse of
+// `srgb_to_linear` and the curve main_fs.glsl's linearToSrgb applies on screen
+fn linear_to_srgb(v: f32) -> f32 {
+    let v = v.clamp(0.0, 1.0);
+    if v <= 0.0031308 { v * 12.92 } else { 1.055 * v.powf(1.0 / 2.4) - 0.055 }
+}
+
+/// Where a straight ray from `origin` along `dir` crosses the disk plane y = 0, if it does.
+pub fn disk_plane_hit(origin: Vec3, dir: Vec3) -> Option<Vec3> {
+    let t = -origin.y / dir.y;
+    (dir.y != 0.0 && t > 0.0).then(|| origin + dir * t)
+}
+
+// Where a selection lands once the object at `removed` leaves the list: cleared if it was the
+// one removed, one index down if it came after it
+fn selection_after_removal(selected: Option<usize>, removed: usize) -> Option<usize> {
+    match selected {
+        Some(index) if index == removed => None,
+        Some(index) if index > removed => Some(index - 1),
+        other => other,
+    }
+}
+
+/// Radius of the innermost stable circular orbit in the equatorial plane, in Schwarzschild radii,
+/// for prograde orbits around a hole of spin a/M (Bardeen, Press & Teukolsky 1972). 3 Rs at spin 0.
+pub fn isco_radius(spin: f64) -> f64 {
+    let z1 = 1.0 + (1.0 - spin * spin).cbrt() * ((1.0 + spin).cbrt() + (1.0 - spin).cbrt());
+    let z2 = (3.0 * spin * spin + z1 * z1).sqrt();
+    // in units of M, which is half a Schwarzschild radius
+    (3.0 + z2 - ((3.0 - z1) * (3.0 + z1 + 2.0 * z2)).sqrt()) / 2.0
+}
+
+/// Radius of the prograde circular photon orbit in the equatorial plane, in Schwarzschild radii,
+/// for a hole of spin a/M. 1.5 Rs at spin 0, the photon sphere.
+pub fn photon_orbit_radius(spin: f64) -> f64 {
+    1.0 + (2.0 / 3.0 * (-spin).acos()).cos()
+}
+
+/// Linear color of a blackbody at `kelvin` with its brightest channel at 1, following the
+/// Planckian locus from red through white at about 6600 K to blue-white. Tanner Helland's fit to
+/// the CIE tables, valid from 1000 K to 40000 K and held at those ends; the compute shader's
+/// `blackbody` uses the same fit.
+pub fn blackbody_rgb(kelvin: f32) -> Vec3 {
+    let t = kelvin.clamp(1000.0, 40000.0) / 100.0;
+    let r = if t <= 66.0 { 255.0 } else { 329.69873 * (t - 60.0).powf(-0.13320476) };
+    let g = if t <= 66.0 { 99.4708 * t.ln() - 161.11957 } else { 288.12217 * (t - 60.0).powf(-0.075514846) };
+    let b = if t >= 66.0 { 255.0 } else if t <= 19.0 { 0.0 } else { 138.51773 * (t - 10.0).ln() - 305.0448 };
+    let linear = |v: f32| srgb_to_linear((v / 255.0).clamp(0.0, 1.0));
+    vec3(linear(r), linear(g), linear(b))
+}
+
+/// Google's turbo colormap at `x` in [0, 1], from dark blue through green to dark red, as
+/// display (sRGB encoded) values. Mikhailov's polynomial fit.
+pub fn turbo_rgb(x: f32) -> Vec3 {
+    let x = x.clamp(0.0, 1.0);
+    let r = 0.13572138 + x * (4.6153926 + x * (-42.660323 + x * (132.13108 + x * (-152.9424 + x * 59.28638))));
+    let g = 0.09140261 + x * (2.1941884 + x * (4.8429666 + x * (-14.185033 + x * (4.2772986 + x * 2.829566))));
+    let b = 0.1066733 + x * (12.641946 + x * (-60.582048 + x * (110.36277 + x * (-89.90311 + x * 27.34825))));
+    vec3(r.clamp(0.0, 1.0), g.clamp(0.0, 1.0), b.clamp(0.0, 1.0))
+}
+
+/// Temperature of a thin disk at radius `r` falling off as r^(-3/4) from `peak` at its inner
+/// edge `inner`, the large radius limit of a Shakura-Sunyaev disk.
+pub fn disk_temperature(r: f64, inner: f64, peak: f32) -> f32 {
+    peak * (r / inner).powf(-0.75) as f32
+}
+
+/// Vertices (x, y, z) and line indices of a flat square grid in the plane y = 0, centered on the
+/// origin with `divisions` cells along each side of length 2 * `extent`.
+pub fn grid_mesh(divisions: u32, extent: f32) -> (Vec<GLfloat>, Vec<u32>) {
+    let n = divisions + 1; // vertices along a side
+    let mut vertices = Vec::with_capacity((n * n * 3) as usize);
+    for row in 0..n {
+        for column in 0..n {
+            let x = -extent + 2.0 * extent * column as f32 / divisions as f32;
+            let z = -extent + 2.0 * extent * row as f32 / divisions as f32;
+            vertices.extend_from_slice(&[x, 0.0, z]);
+        }
+    }
+    // each vertex links to its neighbour along +x and along +z
+    let mut indices = Vec::with_capacity((divisions * n * 4) as usize);
+    for row in 0..n {
+        for column in 0..n {
+            let index = row * n + column;
+            if column < divisions {
+                indices.extend_from_slice(&[index, index + 1]);
+            }
+            if row < divisions {
+                indices.extend_from_slice(&[index, index + n]);
+            }
+        }
+    }
+    (vertices, indices)
+}
+
+/// Triangle indices into `grid_mesh`'s vertices that fill every cell, each triangle listed in
+/// both windings so back face culling keeps the sheet seen from above and from below.
+pub fn grid_sheet(divisions: u32) -> Vec<u32> {
+    let n = divisions + 1;
+    let mut indices = Vec::with_capacity((divisions * divisions * 12) as usize);
+    for row in 0..divisions {
+        for column in 0..divisions {
+            let (a, b) = (row * n + column, row * n + column + 1);
+            let (a_below, b_below) = (a + n, b + n);
+            indices.extend_from_slice(&[a, a_below, b, b, a_below, b_below]);
+            indices.extend_from_slice(&[a, b, a_below, b, b_below, a_below]);
+        }
+    }
+    indices
+}
+
+/// Holds the viewer at a frame rate by trading compute resolution for speed, see
+/// `Engine::set_dynamic_resolution`. Tracing costs about the same per pixel, so the frame time
+/// goes with the square of the resolution scale. The scale drops straight to where the target
+/// is predicted to be met once frames run more than 10% under it, and rises one 0.05 step at a
+/// time, only while the step up is predicted to leave 5% to spare. The band between keeps it
+/// from hunting, and the measurement after a change, which spans the switch, is passed over.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResolutionController {
+    pub target_fps: f64,
+    settling: bool,
+}
+
+impl ResolutionController {
+    const STEP: f32 = 0.05;
+
+    pub fn new(target_fps: f64) -> Self {
+        ResolutionController { target_fps, settling: false }
+    }
+
+    /// The scale to switch to after frames at `scale` took `frame_ms` each on average, None to
+    /// stay. Kept between MIN_RESOLUTION_SCALE and 1, the window's own resolution.
+    pub fn update(&mut self, scale: f32, frame_ms: f64) -> Option<f32> {
+        if mem::take(&mut self.settling) || frame_ms.is_nan() || frame_ms <= 0.0 {
+            return None;
+        }
+        let (fps, step) = (1000.0 / frame_ms, ResolutionController::STEP);
+        let next = if fps < 0.9 * self.target_fps {
+            let predicted = scale * (fps / self.target_fps).sqrt() as f32;
+            ((predicted / step).floor() * step).min(scale - step)
+        } else {
+            let up = scale + step;
+            if fps * (scale as f64 / up as f64).powi(2) < 1.05 * self.target_fps {
+                return None;
+            }
+            up
+        };
+        let next = next.clamp(MIN_RESOLUTION_SCALE, 1.0);
+        if (next - scale).abs() < 1e-4 {
+            return None;
+        }
+        self.settling = true;
+        Some(next)
+    }
+}
+
+// The longest 1, 2 or 5 times a power of ten that is at most `max`, for labeling scale bars
+fn nice_length(max: f64) -> f64 {
+    let decade = 10f64.powf(max.log10().floor());
+    [5.0, 2.0, 1.0].into_iter().map(|m| m * decade).find(|&length| length <= max).unwrap_or(decade)
+}
+
+/// Normal of a disk inclined by `inclination` radians from the +y axis, leaning towards `azimuth`
+/// radians around it from +x to +z.
+pub fn disk_axis(inclination: f64, azimuth: f64) -> Vec3 {
+    let (sin, cos) = inclination.sin_cos();
+    vec3((sin * azimuth.cos()) as f32, cos as f32, (sin * azimuth.sin()) as f32)
+}
+
+/// Schwarzschild radius 2GM/c^2 of a mass in kilograms.
+pub fn schwarzschild_radius(mass: f64) -> f64 {
+    2.0 * G * mass / (C * C)
+}
+
+/// Accretion disk parameters mirrored into the `Disk` UBO (binding = 2), the thickness in meters.
+#[derive(Clone, Copy)]
+pub struct DiskParams {
+    pub inner_radius: Length,
+    pub outer_radius: Length,
+    pub thickness: f32,
+    pub color: Vec3,
+    pub axis: Vec3, // orbital angular momentum direction of the gas, the normal of the disk plane
+    pub angular_velocity: f32, // in units of the Keplerian angular velocity, negative is retrograde
+}
+
+impl Default for DiskParams {
+    fn default() -> Self {
+        DiskParams {
+            inner_radius: Length::meters(SAGA_RS) * 2.2,
+            outer_radius: Length::meters(SAGA_RS) * 5.2,
+            thickness: 1e9,
+            color: vec3(1.0, 1.0, 1.0),
+            axis: vec3(0.0, 1.0, 0.0),
+            angular_velocity: 1.0,
+        }
+    }
+}
+
+/// The settings one half of the A/B split renders with, see `Engine::set_split`. Everything
+/// else, the camera, mass and disk included, is shared by both halves.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SplitParams {
+    pub spin: f64,
+    pub doppler: bool,
+    pub redshift: bool,
+    pub max_steps: i32,
+    pub integration_mode: IntegrationMode,
+}
+
+/// A massive body rendered by the compute shader, mirrored into the `Objects` UBO (binding = 3).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GravObject {
+    pub pos: Vec3,
+    pub radius: f32,
+    pub color: Vec3,
+    pub mass: f64,
+}
+
+/// What a screenshot reads back: the presented window framebuffer, the part of it the scene
+/// fills inside the letterbox bars, or the raw compute output. The window holds sRGB encoded
+/// bytes, saved as they are; the compute output is linear radiance, saved clipped to [0, 1] and
+/// sRGB encoded without exposure, bloom or tone mapping.
+#[derive(Clone, Copy)]
+pub enum CaptureSource {
+    Window,
+    Scene,
+    Compute,
+}
+
+// The viewer's window with the GLFW instance that made it and the events it queues, see
+// `Engine::new_windowed`
+struct AppWindow {
+    glfw: glfw::Glfw,
+    window: Box<glfw::PWindow>,
+    events: glfw::GlfwReceiver<(f64, glfw::WindowEvent)>,
+}
+
+pub struct Engine {
+    shader_paths: ShaderPaths,
+    #[cfg(feature = "hot-reload")]
+    shader_stamps: Vec<Option<std::time::SystemTime>>,
+    #[cfg(feature = "hot-reload")]
+    last_reload_check: f64,
+    grid_shader_program: gl::types::GLuint,
+    // -- Window & events -- //
+    app_window: Option<AppWindow>, // None when embedded, see `new_with_context`
+    clock: std::time::Instant, // when the engine was built, the zero of `now`
+    camera: Camera,
+    // -- Quad & Texture render -- //
+    quad_vao: gl::types::GLuint,
+    quad_vbo: gl::types::GLuint,
+    texture: gl::types::GLuint,
+    accum_texture: gl::types::GLuint, // running mean of the frames since the view last changed
+    frame_index: u32, // frames accumulated into accum_texture, 0 restarts from the current frame
+    aa_samples: u32, // frames accumulated before the image counts as converged
+    edge_texture: gl::types::GLuint, // 1 on the pixels the adaptive AA keeps sampling
+    edge_threshold: f32, // step in compressed luminance that marks an edge, see `set_adaptive_aa`
+    edge_samples: u32, // extra frames traced on the edges alone after aa_samples, 0 turns it off
+    last_camera_data: CameraUbo,
+    shader_program: gl::types::GLuint,
+    compute_program: gl::types::GLuint,
+    compute_variants: Vec<ComputeVariant>, // geodesic_cs.glsl first, then the scene's
+    compute_variant: usize, // the one compute_program was built from
+    // -- Bloom -- //
+    bloom_program: gl::types::GLuint,
+    bloom_fbos: [gl::types::GLuint; 2], // ping-pong targets of the blur
+    bloom_textures: [gl::types::GLuint; 2], // color attachments of bloom_fbos, at the compute resolution
+    bloom_enabled: bool,
+    bloom_threshold: f32, // luminance where the glow starts
+    bloom_intensity: f32,
+    flare_program: gl::types::GLuint,
+    flare_fbos: [gl::types::GLuint; 2], // the brightest spot of each region, then of the whole image
+    flare_textures: [gl::types::GLuint; 2], // color attachments of flare_fbos, LENS_FLARE_REGIONS square and 1x1
+    lens_flare_enabled: bool,
+    lens_flare_intensity: f32,
+    exposure: f32, // EV applied before tone mapping, 0 leaves the radiance as is
+    // -- UBOs -- //
+    camera_ubo: gl::types::GLuint,
+    disk_ubo: gl::types::GLuint,
+    disk: DiskParams,
+    black_hole_ubo: gl::types::GLuint,
+    doppler_enabled: bool,
+    integrator_ubo: gl::types::GLuint,
+    sky_ubo: gl::types::GLuint,
+    debug_ray_ssbo: gl::types::GLuint, // the DebugRay block, export_geodesic reads it back
+    starfield_enabled: bool,
+    star_density: f32,
+    star_brightness: f32,
+    star_seed: u32,
+    seed: u64, // mixed into the starfield arrangement and the jitter sequence, see `set_seed`
+    fixed_timestep: Option<f64>, // seconds the simulation moves on per frame, None follows the wall clock
+    background: gl::types::GLuint, // equirectangular sky texture, 0 until one is loaded
+    background_path: Option<String>, // where it came from, for exporting the scene
+    background_color: Vec3, // linear radiance behind escaped rays while no texture is loaded
+    disk_texture: gl::types::GLuint, // emission lookup over the disk, 0 until one is loaded
+    disk_texture_path: Option<String>, // where it came from, for reloading
+    colormap_texture: gl::types::GLuint, // turbo_rgb along a row, for the false color channels
+    render_channel: RenderChannel,
+    observer_region: ObserverRegion,
+    disk_profile: DiskProfile,
+    disk_model: DiskModel,
+    horizon_epsilon: Option<f32>, // margin above the horizon that captures rays, None follows the step size
+    max_image_order: u32, // crossings of the thin disk a ray sees through before it stops, see `set_max_image_order`
+    disk_inclination: f64, // radians between the disk's axis and the black hole's, see `set_disk_orientation`
+    disk_azimuth: f64, // radians, the direction the axis leans towards
+    tilting_disk: bool, // Ctrl + left drag tilts the disk instead of orbiting the camera
+    tilt_cursor: (f64, f64), // where the last tilting drag event left the cursor, in points
+    disk_density_scale: f32, // thick disk density and with it its opacity, see `set_disk_volume`
+    disk_emission_scale: f32, // thick disk glow per unit of optical depth
+    disk_max_temperature: f32, // kelvin
+    disk_peak_temperature: Option<f32>, // kelvin at the inner edge when the texture is a generated temperature ramp
+    disk_ramp_ratio: f64, // outer over inner radius the ramp was generated for
+    quality: QualityPreset, // last preset picked, custom settings keep it
+    max_steps: i32,
+    step_size: f32, // meters
+    integration_mode: IntegrationMode,
+    tolerance: f32, // adaptive mode, relative error per step
+    spin: f64, // dimensionless a/M of the Kerr black hole, 0 is Schwarzschild
+    mass: f64, // black hole mass in kg
+    redshift_enabled: bool,
+    redshift_exaggeration: f32,
+    objects_ubo: gl::types::GLuint,
+    objects: Vec<GravObject>,
+    placing_objects: bool, // left click adds an object in the disk plane, right click removes one
+    selected_object: Option<usize>, // index into `objects`, kept across uploads
+    // -- grid mess vars -- //
+    grid_vao: gl::types::GLuint,
+    grid_vbo: gl::types::GLuint,
+    grid_ebo: gl::types::GLuint,
+    grid_index_count: gl::types::GLsizei,// originally int
+    grid_sheet_count: GLsizei, // triangle indices of the filled sheet, after the line indices
+    grid_divisions: u32,
+    grid_extent: Length, // half the side of the grid
+    grid_warp: f32, // exaggeration of the embedding diagram dips
+    show_grid: bool,
+    grid_wireframe: bool, // only the lines, false fills the cells under them
+    geodesic_stride: u32, // compute pixels between the rays drawn as paths, 0 draws none
+    geodesic_paths_stale: bool, // traced on the next frame, from the camera there
+    path_vao: gl::types::GLuint,
+    path_vbo: gl::types::GLuint,
+    path_counts: [GLsizei; 2], // line vertices of the captured rays, then of the escaped ones
+    particle: Option<TestParticle>, // None once it falls in
+    particle_trail: VecDeque<Vec3>, // where the particle was, one point a frame, newest last
+    particle_clock: f64, // seconds of the particle's proper time per second on screen
+    particle_apsides: Apsides,
+    coordinate_time: f64, // seconds a clock far from the black hole has counted
+    camera_proper_time: f64, // and one held still at the camera, which runs slower the closer it is
+    trail_vao: gl::types::GLuint,
+    trail_vbo: gl::types::GLuint,
+
+    win_width: u32,  // Framebuffer width, in pixels, which high DPI screens have more of than window coordinates
+    win_height: u32, // Framebuffer height
+    ui_scale: f32, // the window's content scale, framebuffer pixels per point of the overlay and panel
+    compute_width: i32,   // Compute resolution width
+    compute_height: i32, // Compute resolution height
+    resolution_scale: f32, // Compute resolution as a fraction of the window size
+    supersampling: u32, // and that many times more along each axis, box filtered back down by the quad shader
+    tiled: bool, // trace one tile per frame instead of the whole image
+    tile_size: u32, // pixels along a tile's side, a multiple of LOCAL_SIZE
+    tile_index: u32, // the next tile to trace, from the top left
+    output_aspect: Option<f32>, // width over height the picture is locked to, letterboxed in the window
+    vsync: bool,
+    windowed_placement: Option<((i32, i32), (i32, i32))>, // position and size to restore when leaving fullscreen
+    monitor: Option<usize>, // the monitor fullscreen goes to, by default the one under the window
+    bookmarks: Bookmarks, // number keys 1-9
+    bookmarks_changed: bool, // saved on exit when set
+    // -- camera timeline -- //
+    timeline: Option<CameraTimeline>,
+    timeline_time: f64, // seconds, in the timeline's own clock
+    timeline_playing: bool,
+    paused: bool, // the compute pass and the timeline hold, the last image stays on screen
+    step_pending: bool, // render one frame while paused
+    pending_screenshot: Option<CaptureSource>, // taken after the next frame is drawn
+    photo_mode: bool, // hides the grid, the ray paths, the overlay and the panel
+    photo_pending: bool, // hides them for the frame `pending_screenshot` captures
+    // -- simulation state -- //
+    last_frame_time: f64,
+    gravity_enabled: bool,
+    last_fps_print: f64,
+    frames_this_second: u64,
+    traced_this_second: u64, // the frames among them that dispatched the compute shader
+    target_fbo: gl::types::GLuint, // where the image is drawn, 0 for the window, see `render_into`
+    dynamic_resolution: Option<ResolutionController>,
+    // -- overlay -- //
+    overlay: TextOverlay,
+    defaults: Config, // the scene as loaded, what `reset` goes back to, and the key bindings
+    panel: Panel,
+    param_cursor: ParamCursor, // the parameter [ and ] step
+    feedback: Option<(String, f64)>, // the last change made from the keyboard and when, shown for a moment
+    profiler: Option<FrameProfiler>, // None when the context has no timestamp queries
+    show_overlay: bool,
+    show_photon_orbit: bool, // reference circles drawn around the black hole, toggled apart
+    show_isco: bool,
+    show_crosshair: bool, // marks the middle of the view
+    show_center_marker: bool, // marks where the black hole's center projects to
+    split: Option<[SplitParams; 2]>, // the left and right half of the A/B comparison
+    split_position: f32, // the divider, as a fraction of the scene's width from the left
+    dragging_split: bool,
+    fps: f64,
+    frame_ms: f64,
+}
+
+impl Engine {
+    /// Opens the viewer's window with GLFW, makes its GL context current and builds the engine in
+    /// it, see `new_with_context`. The scene file named in `options`, if any, sizes the window,
+    /// with the command line values taking precedence over it.
+    pub fn new_windowed(options: &EngineOptions) -> Result<Self, EngineError> {
+        let config = Self::scene_config(options)?;
+        let (win_width, win_height) = (config.window_width, config.window_height);
+
+        // a failed window is handled below, GLFW's errors only need logging
+        let mut glfw = glfw::init(glfw::log_errors)
+            .map_err(|error| EngineError::Unsupported(format!("GLFW failed to start: {}", error)))?;
+        glfw.window_hint(glfw::WindowHint::ContextVersionMajor(4));
+        glfw.window_hint(glfw::WindowHint::ContextVersionMinor(3));
+        glfw.window_hint(glfw::WindowHint::OpenGlProfile(glfw::OpenGlProfileHint::Core));
+        // a debug context reports far more through the debug callback, at some cost in speed
+        glfw.window_hint(glfw::WindowHint::OpenGlDebugContext(cfg!(feature = "gl-debug")));
+        // an offline render only needs the context, keep its window off screen
+        let offline = options.render.is_some() || options.orbit.is_some() || options.record.is_some() || options.benchmark.is_some();
+        glfw.window_hint(glfw::WindowHint::Visible(!offline));
+        // the window size counts points where the platform scales for DPI, grow it on platforms
+        // that count pixels so it doesn't come up tiny on a high DPI screen
+        glfw.window_hint(glfw::WindowHint::ScaleToMonitor(true));
+
+        let Some((mut window, events)) = glfw.create_window(win_width, win_height, "Black Hole", glfw::WindowMode::Windowed) else {
+            // most likely the driver can't make a 4.3 context, find out what it can make instead
+            let message = match gl_debug::probe_default_version(&mut glfw) {
+                Some(version) => format!("couldn't create an OpenGL 4.3 core context: your GPU/driver reports GL {} which lacks compute shaders", version),
+                None => "couldn't create an OpenGL context, check that your GPU/driver supports OpenGL 4.3".to_string(),
+            };
+            return Err(EngineError::Unsupported(message));
+        };
+
+        if let (Some(index), false) = (options.monitor, offline || !monitors_connected(&mut glfw)) {
+            glfw.with_connected_monitors(|_, monitors| {
+                if let Some(monitor) = requested_monitor(monitors, index) {
+                    let (x, y, w, h) = monitor.get_workarea();
+                    let (width, height) = window.get_size();
+                    window.set_pos(x + (w - width).max(0) / 2, y + (h - height).max(0) / 2);
+                }
+            });
+        }
+
+        window.make_current();
+        window.set_key_polling(true);
+        window.set_cursor_pos_polling(true);
+        window.set_mouse_button_polling(true);
+        window.set_scroll_polling(true);
+        window.set_framebuffer_size_polling(true);
+        window.set_content_scale_polling(true);
+        window.set_close_polling(true);
+        // everything is drawn in framebuffer pixels, which outnumber the window size on a high
+        // DPI screen; the window size only places the cursor
+        let (win_width, win_height) = match window.get_framebuffer_size() {
+            (w, h) if w > 0 && h > 0 => (w as u32, h as u32),
+            _ => (win_width, win_height),
+        };
+        let ui_scale = ui_scale(window.get_content_scale());
+
+        // the window's context is current, which is all GLFW's lookup needs
+        let loader = |s: &str| glfw.get_proc_address_raw(s).map_or(ptr::null(), |f| f as *const c_void);
+        let mut engine = Self::build(config, options, loader, (win_width, win_height))?;
+        engine.ui_scale = ui_scale;
+        engine.app_window = Some(AppWindow { glfw, window: Box::new(window), events });
+        // the benchmark measures throughput, waiting for the display would cap it
+        engine.set_vsync(!options.no_vsync && options.benchmark.is_none());
+        Ok(engine)
+    }
+
+    /// Builds the engine in a GL 4.3 context the caller has made current, to embed the renderer
+    /// in an app that makes its own window, with SDL or winit say. `loader` looks GL functions
+    /// up by name, null for the ones the driver lacks, and `size` is the framebuffer the image
+    /// is drawn to, in pixels. The scene comes from `options` as for the viewer. Draw frames
+    /// with `render_into`; `run`, window events, fullscreen and vsync need `new_windowed`.
+    #[allow(dead_code)] // for embedding, the viewer opens its own window
+    pub fn new_with_context(options: &EngineOptions, loader: impl Fn(&str) -> *const c_void, size: (u32, u32)) -> Result<Self, EngineError> {
+        Self::build(Self::scene_config(options)?, options, loader, size)
+    }
+
+    // The scene file named in `options`, if any, with the command line values taking precedence over it
+    fn scene_config(options: &EngineOptions) -> Result<Config, EngineError> {
+        let mut config = match &options.scene {
+            Some(path) => Config::load(path)?,
+            None => Config::default(),
+        };
+        config.window_width = options.width.unwrap_or(config.window_width);
+        config.window_height = options.height.unwrap_or(config.window_height);
+        config.compute_width = options.compute_width.unwrap_or(config.compute_width);
+        config.compute_height = options.compute_height.unwrap_or(config.compute_height);
+        config.gravity |= options.gravity;
+        config.seed = options.seed.unwrap_or(config.seed);
+        if options.background.is_some() {
+            config.background = options.background.clone();
+        }
+        config.background_color = options.background_color.unwrap_or(config.background_color);
+        let misaligned = |n: Option<u32>| n.is_some_and(|n| n % LOCAL_SIZE != 0);
+        if misaligned(options.compute_width) || misaligned(options.compute_height) {
+            warn!("Compute resolution {}x{} is not a multiple of the {}x{} work group, the edge groups run partly idle",
+                config.compute_width, config.compute_height, LOCAL_SIZE, LOCAL_SIZE);
+        }
+        for conflict in config.key_bindings.conflicts() {
+            warn!("Key bindings: {}", conflict);
+        }
+        Ok(config)
+    }
+
+    // Loads GL through `loader` and builds the GL state for a framebuffer of `size` pixels
+    fn build(config: Config, options: &EngineOptions, loader: impl Fn(&str) -> *const c_void, size: (u32, u32)) -> Result<Self, EngineError> {
+        let (win_width, win_height) = size;
+
+        let compute_width = config.compute_width as i32;   // Compute resolution width
+        let compute_height = config.compute_height as i32;
+
+        let mut camera = Camera::new();
+        camera.azimuth = config.camera_azimuth;
+        camera.elevation = config.camera_elevation;
+        camera.radius = config.camera_radius;
+        camera.target = config.camera_target;
+        camera.min_radius = config.camera_min_radius;
+        camera.max_radius = config.camera_max_radius;
+        camera.horizon_radius = Length::rs(config.black_hole_mass);
+        camera.allow_inside_horizon = config.camera_allow_inside_horizon;
+        camera.sensitivity = config.camera_sensitivity;
+        camera.transition_time = config.camera_transition_time;
+        camera.fov_degrees = config.camera_fov_degrees;
+        if let Some(orbit) = &options.orbit {
+            camera.elevation = orbit.elevation.unwrap_or(camera.elevation);
+            camera.radius = camera.clamp_radius(orbit.radius.unwrap_or(camera.radius));
+        }
+
+        let timeline = options.timeline.as_deref().map(CameraTimeline::load).transpose()?;
+        // losing the bookmarks is no reason not to start
+        let bookmarks = bookmarks::load(BOOKMARKS_PATH).unwrap_or_else(|error| {
+            warn!("{}, starting without bookmarks", error);
+            [None; bookmarks::BOOKMARK_SLOTS]
+        });
+
+        let disk = DiskParams {
+            inner_radius: config.disk_inner_radius,
+            outer_radius: config.disk_outer_radius,
+            thickness: config.disk_thickness as f32,
+            ..DiskParams::default()
+        };
+
+        // symbols the driver lacks load as null, so they can be named below instead of panicking here
+        gl::load_with(loader);
+        let version = gl_debug::gl_string(gl::VERSION);
+        info!("GL {} on {} by {}, GLSL {}", version, gl_debug::gl_string(gl::RENDERER),
+            gl_debug::gl_string(gl::VENDOR), gl_debug::gl_string(gl::SHADING_LANGUAGE_VERSION));
+        let missing = gl_debug::missing_functions();
+        if !missing.is_empty() {
+            return Err(EngineError::Unsupported(format!("your driver (GL {}) is missing {}, which the renderer needs; try updating it",
+                if version.is_empty() { "unknown" } else { &version }, missing.join(", "))));
+        }
+        if !gl_debug::supports_compute_shaders() {
+            return Err(EngineError::Unsupported(format!("your GPU/driver reports GL {} which lacks compute shaders (GL 4.3 or GL_ARB_compute_shader)", version)));
+        }
+        if !gl_debug::enable_debug_output() {
+            debug!("No GL debug output on this context, only gl-debug builds check for GL errors");
+        }
+
+        let shader_paths = ShaderPaths::default();
+        let shader_program = shader::create_shader_program(&shader_paths.main_vs, &shader_paths.main_fs)?;
+        let compute_program = shader::create_compute_program(&shader_paths.compute)?;
+        let mut compute_variants = vec![ComputeVariant::from_path(&shader_paths.compute)];
+        compute_variants.extend(config.compute_variants.iter().cloned());
+        let grid_shader_program = shader::create_shader_program(&shader_paths.grid_vs, &shader_paths.grid_fs)?;
+        let bloom_program = shader::create_shader_program(&shader_paths.main_vs, &shader_paths.bloom_fs)?;
+        let flare_program = shader::create_shader_program(&shader_paths.main_vs, &shader_paths.flare_fs)?;
+        let overlay = TextOverlay::new(shader::create_shader_program(&shader_paths.text_vs, &shader_paths.text_fs)?);
+
+        // the bindings match the blocks' `binding =` in the shaders
+        let camera_ubo = ubo::create::<CameraUbo>(1);
+        let disk_ubo = ubo::create::<DiskUbo>(2);
+        let objects_ubo = ubo::create::<ObjectsUbo>(3);
+        let black_hole_ubo = ubo::create::<BlackHoleUbo>(4);
+        let integrator_ubo = ubo::create::<IntegratorUbo>(5);
+        let sky_ubo = ubo::create::<SkyUbo>(6);
+        debug!("Uniform buffers: Camera {} B, Disk {} B, BlackHole {} B, Integrator {} B, Sky {} B, Objects {} B",
+            mem::size_of::<CameraUbo>(), mem::size_of::<DiskUbo>(), mem::size_of::<BlackHoleUbo>(),
+            mem::size_of::<IntegratorUbo>(), mem::size_of::<SkyUbo>(), mem::size_of::<ObjectsUbo>());
+        gl_check("allocating the uniform buffers");
+
+        // room for a point per step of the longest ray after the 16 byte count
+        let mut debug_ray_ssbo = 0;
+        unsafe {
+            gl::GenBuffers(1, &mut debug_ray_ssbo);
+            gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, debug_ray_ssbo);
+            let size = 16 * (MAX_INTEGRATION_STEPS as usize + 2);
+            gl::BufferData(gl::SHADER_STORAGE_BUFFER, size as GLsizeiptr, ptr::null(), gl::DYNAMIC_READ);
+            gl::BindBufferBase(gl::SHADER_STORAGE_BUFFER, 0, debug_ray_ssbo);
+        }
+        gl_check("allocating the debug ray buffer");
+
+        let result = Self::quad_vao(compute_width, compute_height);
+        let quad_vao = result[0];
+        let quad_vbo = result[1];
+        let texture = result[2];
+        let (bloom_fbos, bloom_textures) = Self::bloom_targets();
+        let (flare_fbos, flare_textures) = Self::flare_targets();
+        let (mut accum_texture, mut edge_texture) = (0, 0);
+        unsafe {
+            for texture in [&mut accum_texture, &mut edge_texture] {
+                gl::GenTextures(1, texture);
+                gl::BindTexture(gl::TEXTURE_2D, *texture);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+            }
+        }
+
+        let mut engine = Engine{
+            #[cfg(feature = "hot-reload")]
+            shader_stamps: shader_paths.stamps(),
+            #[cfg(feature = "hot-reload")]
+            last_reload_check: 0.0,
+            shader_paths,
+            app_window: None,
+            clock: std::time::Instant::now(),
+            camera,
+            quad_vao,
+            quad_vbo,
+            texture,
+            accum_texture,
+            frame_index: 0,
+            aa_samples: 1,
+            edge_texture,
+            edge_threshold: 0.1,
+            edge_samples: 0,
+            last_camera_data: CameraUbo::default(),
+            shader_program,
+            compute_program,
+            compute_variants,
+            compute_variant: 0,
+            grid_shader_program,
+            bloom_program,
+            bloom_fbos,
+            bloom_textures,
+            bloom_enabled: config.bloom,
+            bloom_threshold: 0.0,
+            bloom_intensity: 0.0,
+            flare_program,
+            flare_fbos,
+            flare_textures,
+            lens_flare_enabled: config.lens_flare,
+            lens_flare_intensity: 0.0,
+            exposure: 0.0,
+            // -- UBOs -- //
+            camera_ubo,
+            disk_ubo,
+            disk,
+            black_hole_ubo,
+            doppler_enabled: false,
+            integrator_ubo,
+            sky_ubo,
+            debug_ray_ssbo,
+            starfield_enabled: config.starfield,
+            star_density: 0.0,
+            star_brightness: config.star_brightness,
+            star_seed: 0,
+            seed: 0,
+            fixed_timestep: None,
+            background: 0,
+            background_path: None,
+            background_color: vec3(0.0, 0.0, 0.0),
+            disk_texture: 0,
+            disk_texture_path: None,
+            colormap_texture: Self::colormap_texture(),
+            render_channel: RenderChannel::Color,
+            observer_region: ObserverRegion::Exterior,
+            disk_profile: config.disk_profile,
+            disk_model: config.disk_model,
+            max_image_order: 1,
+            horizon_epsilon: None,
+            disk_inclination: 0.0,
+            disk_azimuth: 0.0,
+            tilting_disk: false,
+            tilt_cursor: (0.0, 0.0),
+            disk_density_scale: 1.0,
+            disk_emission_scale: 1.0,
+            disk_max_temperature: config.disk_max_temperature,
+            disk_peak_temperature: None,
+            disk_ramp_ratio: 0.0,
+            quality: QualityPreset::High,
+            max_steps: 0,
+            step_size: 0.0,
+            integration_mode: IntegrationMode::Fixed,
+            tolerance: 0.0,
+            spin: 0.0,
+            mass: config.black_hole_mass,
+            redshift_enabled: false,
+            redshift_exaggeration: 1.0,
+            objects_ubo,
+            objects: Vec::new(),
+            placing_objects: false,
+            selected_object: None,
+            // -- grid mess vars -- //
+            grid_vao: 0,
+            grid_vbo: 0,
+            grid_ebo: 0,
+            grid_index_count: 0,
+            grid_sheet_count: 0,
+            grid_divisions: 0,
+            grid_extent: Length::ZERO,
+            grid_warp: 1.0,
+            show_grid: false,
+            grid_wireframe: true,
+            geodesic_stride: 0,
+            geodesic_paths_stale: false,
+            path_vao: 0,
+            path_vbo: 0,
+            path_counts: [0; 2],
+            particle: None,
+            particle_trail: VecDeque::new(),
+            particle_clock: 0.0,
+            particle_apsides: Apsides::default(),
+            coordinate_time: 0.0,
+            camera_proper_time: 0.0,
+            trail_vao: 0,
+            trail_vbo: 0,
+
+            win_width,  // Framebuffer width
+            win_height, // Framebuffer height
+            ui_scale: 1.0,
+            compute_width,   // Compute resolution width
+            compute_height,  // Compute resolution height
+            resolution_scale: compute_width as f32 / win_width as f32,
+            supersampling: 1,
+            tiled: false,
+            tile_size: 256,
+            tile_index: 0,
+            output_aspect: None,
+            vsync: true,
+            windowed_placement: None,
+            monitor: options.monitor,
+            bookmarks,
+            bookmarks_changed: false,
+            timeline_time: timeline.as_ref().map_or(0.0, CameraTimeline::start),
+            paused: false,
+            step_pending: false,
+            timeline_playing: timeline.is_some(),
+            timeline,
+            pending_screenshot: None,
+            photo_mode: false,
+            photo_pending: false,
+            last_frame_time: 0.0,
+            gravity_enabled: config.gravity,
+            last_fps_print: 0.0,
+            frames_this_second: 0,
+            traced_this_second: 0,
+            target_fbo: 0,
+            dynamic_resolution: None,
+            overlay,
+            defaults: config.clone(),
+            panel: Panel::default(),
+            param_cursor: ParamCursor::default(),
+            feedback: None,
+            profiler: FrameProfiler::new(),
+            show_overlay: true,
+            show_photon_orbit: false,
+            show_isco: false,
+            show_crosshair: false,
+            show_center_marker: false,
+            split: None,
+            split_position: 0.5,
+            dragging_split: false,
+            fps: 0.0,
+            frame_ms: 0.0,
+        };
+        let disk = engine.disk;
+        engine.update_disk(&disk);
+        engine.allocate_texture();
+        // Every triangle is wound counter-clockwise on screen: the quad, the overlay's and both
+        // sides of the grid sheet, so back faces can go
+        unsafe {
+            gl::FrontFace(gl::CCW);
+            gl::CullFace(gl::BACK);
+            gl::Enable(gl::CULL_FACE);
+        }
+        engine.apply_default_parameters();
+        engine.build_grid(80, Length::meters(SAGA_RS) * 20.0);
+        engine.set_starfield(config.star_density, config.star_seed);
+        engine.set_background(config.background_color);
+        engine.set_seed(config.seed);
+        engine.set_fixed_timestep(config.fixed_timestep);
+        if options.aspect.is_some() {
+            engine.set_output_aspect(options.aspect);
+        }
+        if let Some(path) = &config.background {
+            engine.load_background(path)?;
+        }
+        if let Some(path) = &config.disk_texture {
+            engine.load_disk_texture(path)?;
+        }
+        if let Some(peak) = config.disk_peak_temperature {
+            engine.set_disk_temperature(peak);
+        }
+        engine.set_disk_volume(config.disk_density_scale, config.disk_emission_scale);
+        engine.set_disk_orientation(config.disk_inclination, config.disk_azimuth);
+        for object in &config.objects {
+            engine.add_object(*object);
+        }
+
+        Ok(engine)
+    }
+    // The simulation and image settings a fresh engine starts with: the scene's spin, quality
+    // and exposure, the rest the same for every scene
+    fn apply_default_parameters(&mut self) {
+        let defaults = self.defaults.clone();
+        self.set_spin(defaults.black_hole_spin);
+        self.quality = defaults.quality;
+        self.apply_quality();
+        if defaults.integration_steps.is_some() || defaults.integration_step_size.is_some() {
+            self.set_integration_quality(defaults.integration_steps.unwrap_or(self.max_steps),
+                defaults.integration_step_size.unwrap_or(self.step_size));
+        }
+        self.set_integration_mode(defaults.integration_mode);
+        self.set_integration_tolerance(defaults.integration_tolerance);
+        self.set_horizon_epsilon(defaults.horizon_epsilon);
+        self.set_aa_samples(16);
+        self.set_adaptive_aa(defaults.edge_threshold, defaults.edge_samples);
+        self.set_bloom(defaults.bloom_threshold, defaults.bloom_intensity);
+        self.set_lens_flare(defaults.lens_flare_intensity);
+        self.set_exposure(defaults.exposure);
+        self.set_redshift_exaggeration(1.0);
+        self.set_grid_warp(1.0);
+        self.set_observer_region(self.starting_observer_region());
+    }
+
+    /// Goes back to the scene as it was loaded: its mass, spin, disk, camera, quality and
+    /// exposure. The camera eases back like a recalled bookmark.
+    pub fn reset(&mut self) {
+        let defaults = self.defaults.clone();
+        self.set_mass(defaults.black_hole_mass);
+        self.update_disk(&DiskParams {
+            inner_radius: defaults.disk_inner_radius,
+            outer_radius: defaults.disk_outer_radius,
+            thickness: defaults.disk_thickness as f32,
+            ..DiskParams::default()
+        });
+        self.set_disk_orientation(defaults.disk_inclination, defaults.disk_azimuth);
+        self.apply_default_parameters();
+        self.camera.min_radius = defaults.camera_min_radius;
+        self.camera.max_radius = defaults.camera_max_radius;
+        self.camera.fov_degrees = defaults.camera_fov_degrees;
+        self.camera.restore(&CameraState {
+            azimuth: defaults.camera_azimuth,
+            elevation: defaults.camera_elevation,
+            radius: defaults.camera_radius,
+            target: defaults.camera_target,
+        });
+        self.coordinate_time = 0.0;
+        self.camera_proper_time = 0.0;
+        info!("View and parameters reset");
+    }
+
+    // Moves what runs on its own, apart from the camera, on by `dt` seconds
+    fn step_simulation(&mut self, dt: f64) {
+        self.advance_particle(dt);
+        self.advance_clocks(dt);
+    }
+
+    // Runs the clock far away for `dt` and the camera's for as much of it as passes where the
+    // camera is; inside the horizon the camera's clock is stopped
+    fn advance_clocks(&mut self, dt: f64) {
+        self.coordinate_time += dt;
+        self.camera_proper_time += dt * self.camera.time_dilation_factor(self.mass);
+    }
+
+    fn quad_vao(compute_width: i32, compute_height: i32) -> Vec<gl::types::GLuint> {
+        let mut vao: gl::types::GLuint = 0;
+        let mut vbo: gl::types::GLuint = 0;
+        let mut texture: gl::types::GLuint = 0;
+
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+
+            gl::BindVertexArray(vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            gl::BufferData(gl::ARRAY_BUFFER,  mem::size_of_val(&QUAD_VERTICES) as GLsizeiptr, QUAD_VERTICES.as_ptr() as *const c_void, gl::STATIC_DRAW);
+
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, (4 * mem::size_of::<GLfloat>()) as GLsizei, ptr::null());
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(1, 2, gl::FLOAT,gl::FALSE, (4 * mem::size_of::<GLfloat>()) as GLsizei, (2 * mem::size_of::<GLfloat>()) as *const c_void);
+            gl::EnableVertexAttribArray(1);
+
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as gl::types::GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as gl::types::GLint);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::TexImage2D(gl::TEXTURE_2D, 0,             // mip
+                         gl::RGBA16F as gl::types::GLint,    // internal format, float so highlights aren't clamped
+                         compute_width, compute_height, 0, gl::RGBA, gl::FLOAT, ptr::null());
+        }
+
+        vec!(vao, vbo, texture)
+    }
+
+    // Framebuffers the bloom renders into, their storage is sized by allocate_texture
+    fn bloom_targets() -> ([gl::types::GLuint; 2], [gl::types::GLuint; 2]) {
+        let mut fbos = [0; 2];
+        let mut textures = [0; 2];
+        unsafe {
+            gl::GenFramebuffers(2, fbos.as_mut_ptr());
+            gl::GenTextures(2, textures.as_mut_ptr());
+            for (&fbo, &texture) in fbos.iter().zip(&textures) {
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+                // the blur taps past the edge would otherwise wrap around to the far side
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+                gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, texture, 0);
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        (fbos, textures)
+    }
+
+    // Framebuffers the lens flare finds the brightest spot with, full floats to hold its position
+    fn flare_targets() -> ([gl::types::GLuint; 2], [gl::types::GLuint; 2]) {
+        let mut fbos = [0; 2];
+        let mut textures = [0; 2];
+        unsafe {
+            gl::GenFramebuffers(2, fbos.as_mut_ptr());
+            gl::GenTextures(2, textures.as_mut_ptr());
+            for ((&fbo, &texture), size) in fbos.iter().zip(&textures).zip([LENS_FLARE_REGIONS, 1]) {
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+                gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA32F as GLint, size, size, 0, gl::RGBA, gl::FLOAT, ptr::null());
+                gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+                gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, texture, 0);
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, 0);
+        }
+        gl_check("allocating the lens flare targets");
+        (fbos, textures)
+    }
+
+    fn update_disk(&mut self, params: &DiskParams) {
+        self.disk = *params;
+        self.reset_accumulation();
+        // the ramp spans the disk, new edges move every temperature but the peak
+        if self.disk_peak_temperature.is_some() && params.outer_radius / params.inner_radius != self.disk_ramp_ratio {
+            self.build_temperature_ramp();
+        }
+        ubo::upload(self.disk_ubo, &self.disk_block());
+    }
+
+    // The Disk block for the current disk and its settings
+    fn disk_block(&self) -> DiskUbo {
+        let params = &self.disk;
+        let axis = glm::normalize(params.axis);
+        // without a texture there is nothing for the other profiles to read
+        let profile = if self.disk_texture == 0 { DiskProfile::Gradient } else { self.disk_profile };
+        DiskUbo {
+            disk_r1: params.inner_radius.in_meters() as f32,
+            disk_r2: params.outer_radius.in_meters() as f32,
+            disk_num: 2.0,
+            thickness: params.thickness,
+            disk_color: [params.color.x, params.color.y, params.color.z, 0.0],
+            disk_axis: [axis.x, axis.y, axis.z, params.angular_velocity],
+            doppler_enabled: self.doppler_enabled as i32,
+            disk_profile: profile as i32,
+            max_temperature: self.disk_max_temperature,
+            disk_model: self.disk_model as i32,
+            density_scale: self.disk_density_scale,
+            emission_scale: self.disk_emission_scale,
+            ..DiskUbo::default()
+        }
+    }
+
+    /// Sets the disk's inner and outer edge. The inner edge stays outside the horizon and at least
+    /// a tenth of a Schwarzschild radius inside the outer one.
+    pub fn set_disk_radii(&mut self, inner: Length, outer: Length) {
+        let rs = Length::rs(self.mass);
+        let mut disk = self.disk;
+        disk.outer_radius = outer.max(rs * 1.1);
+        disk.inner_radius = inner.clamp(rs, disk.outer_radius - rs * 0.1);
+        self.update_disk(&disk);
+    }
+
+    /// Sets the black hole spin a/M, clamped to [0, MAX_SPIN]. At 0 the compute shader falls back
+    /// to its Schwarzschild integrator, anything above is traced through the Kerr metric.
+    pub fn set_spin(&mut self, a: f64) {
+        self.spin = a.clamp(0.0, MAX_SPIN);
+        if self.spin != 0.0 && self.observer_region == ObserverRegion::Interior {
+            warn!("The interior view has no rotating metric, back to the exterior one");
+            self.set_observer_region(ObserverRegion::Exterior);
+        }
+        self.update_black_hole();
+    }
+
+    /// Switches between the exterior view and the one that can follow the camera through the
+    /// horizon, see `ObserverRegion`. The interior is only open when the scene sets
+    /// camera.allow_inside_horizon and the hole doesn't spin, otherwise this warns and stays put.
+    /// Leaving it takes the camera back out to HORIZON_MARGIN horizon radii if it was closer.
+    pub fn set_observer_region(&mut self, region: ObserverRegion) {
+        if region == ObserverRegion::Interior && !self.defaults.camera_allow_inside_horizon {
+            warn!("The interior view needs camera.allow_inside_horizon in the scene");
+            return;
+        }
+        if region == ObserverRegion::Interior && self.spin != 0.0 {
+            warn!("The interior view has no rotating metric, set the spin to 0 first");
+            return;
+        }
+        self.observer_region = region;
+        // the camera's own clamp moves it back out on its next update
+        self.camera.allow_inside_horizon = region == ObserverRegion::Interior;
+        self.update_black_hole();
+    }
+
+    fn toggle_observer_region(&mut self) {
+        self.set_observer_region(match self.observer_region {
+            ObserverRegion::Exterior => ObserverRegion::Interior,
+            ObserverRegion::Interior => ObserverRegion::Exterior,
+        });
+        info!("Observer region {:?}", self.observer_region);
+    }
+
+    // A scene that starts the camera within HORIZON_MARGIN horizon radii, which it may only do
+    // with camera.allow_inside_horizon, starts in the interior
+    fn starting_observer_region(&self) -> ObserverRegion {
+        let inside = self.defaults.camera_radius < HORIZON_MARGIN * schwarzschild_radius(self.defaults.black_hole_mass);
+        if self.defaults.camera_allow_inside_horizon && inside && self.spin == 0.0 {
+            ObserverRegion::Interior
+        } else {
+            ObserverRegion::Exterior
+        }
+    }
+
+    fn schwarzschild_radius(&self) -> f64 {
+        schwarzschild_radius(self.mass)
+    }
+
+    /// A length in meters in units of the Schwarzschild radius of the current mass.
+    pub fn to_rs(&self, meters: f64) -> f64 {
+        meters / self.schwarzschild_radius()
+    }
+
+    /// Sets the black hole mass in kilograms, clamped to [MIN_MASS, MAX_MASS]. The Schwarzschild
+    /// radius follows the mass, and with it the photon sphere at 1.5 Rs and the ISCO at 3 Rs, so
+    /// the disk, the camera with its zoom limits and the integration steps are all scaled by the
+    /// same factor: the view stays framed and shows the same hole at a different size.
+    pub fn set_mass(&mut self, kg: f64) {
+        let kg = kg.clamp(MIN_MASS, MAX_MASS);
+        let factor = kg / self.mass;
+        self.mass = kg;
+        self.camera.rescale(factor);
+        let mut disk = self.disk;
+        disk.inner_radius *= factor;
+        disk.outer_radius *= factor;
+        disk.thickness *= factor as f32;
+        self.update_disk(&disk);
+        self.update_black_hole();
+        self.apply_quality();
+        self.build_grid(self.grid_divisions, self.grid_extent * factor);
+    }
+
+    // Multiplies the mass by 10^steps
+    fn step_mass(&mut self, steps: i32) {
+        self.set_mass(self.mass * 10f64.powi(steps));
+        self.notify(format!("Black hole mass {:.3e} kg ({:.3e} solar masses), Rs {:.3e} m",
+            self.mass, self.mass / SOLAR_MASS, self.schwarzschild_radius()));
+    }
+
+    /// Turns the gravitational redshift of light climbing out of the potential well on or off.
+    pub fn set_gravitational_redshift(&mut self, enabled: bool) {
+        self.redshift_enabled = enabled;
+        self.update_black_hole();
+    }
+
+    /// Raises the gravitational frequency shift to this power, clamped to [1, 16], so the
+    /// effect reads clearly away from the horizon. 1 is physical.
+    pub fn set_redshift_exaggeration(&mut self, factor: f32) {
+        self.redshift_exaggeration = factor.clamp(1.0, 16.0);
+        self.update_black_hole();
+    }
+
+    // Doubles the exaggeration up to 16, then starts over at the physical value
+    fn cycle_redshift_exaggeration(&mut self) {
+        let next = if self.redshift_exaggeration >= 16.0 { 1.0 } else { self.redshift_exaggeration * 2.0 };
+        self.set_redshift_exaggeration(next);
+        info!("Gravitational redshift exaggeration x{}", self.redshift_exaggeration);
+    }
+
+    fn update_black_hole(&mut self) {
+        self.reset_accumulation();
+        ubo::upload(self.black_hole_ubo, &self.black_hole_block());
+    }
+
+    fn black_hole_block(&self) -> BlackHoleUbo {
+        BlackHoleUbo {
+            spin: self.spin as GLfloat,
+            rs: self.schwarzschild_radius() as GLfloat,
+            redshift_enabled: self.redshift_enabled as i32,
+            redshift_exaggeration: self.redshift_exaggeration,
+            observer_region: self.observer_region as i32,
+            ..BlackHoleUbo::default()
+        }
+    }
+
+    /// Sets how many geodesic steps each ray takes and how long they are. The step count is
+    /// clamped to [1, MAX_INTEGRATION_STEPS] and the step to [1e5, 1e9] m, scaled by the
+    /// Schwarzschild radius relative to Sagittarius A*; the rays reach `steps * step` meters
+    /// before they are counted as escaped.
+    pub fn set_integration_quality(&mut self, steps: i32, step: f32) {
+        let scale = (self.schwarzschild_radius() / SAGA_RS) as f32;
+        self.max_steps = steps.clamp(1, MAX_INTEGRATION_STEPS);
+        self.step_size = step.clamp(1e5 * scale, 1e9 * scale);
+        self.update_integrator();
+    }
+
+    /// Builds the spacetime grid shown with the T key: a square of `divisions` x `divisions` cells
+    /// reaching `extent` from the black hole along each axis. The grid shader bends it into
+    /// Flamm's paraboloid around the black hole and every object.
+    pub fn build_grid(&mut self, divisions: u32, extent: Length) {
+        let divisions = divisions.max(1);
+        let (vertices, mut indices) = grid_mesh(divisions, extent.in_meters() as f32);
+        self.grid_divisions = divisions;
+        self.grid_extent = extent;
+        self.grid_index_count = indices.len() as GLsizei;
+        let sheet = grid_sheet(divisions);
+        self.grid_sheet_count = sheet.len() as GLsizei;
+        indices.extend(sheet);
+        unsafe {
+            if self.grid_vao == 0 {
+                gl::GenVertexArrays(1, &mut self.grid_vao);
+                gl::GenBuffers(1, &mut self.grid_vbo);
+                gl::GenBuffers(1, &mut self.grid_ebo);
+            }
+            gl::BindVertexArray(self.grid_vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.grid_vbo);
+            gl::BufferData(gl::ARRAY_BUFFER, mem::size_of_val(vertices.as_slice()) as GLsizeiptr, vertices.as_ptr() as *const c_void, gl::STATIC_DRAW);
+            gl::BindBuffer(gl::ELEMENT_ARRAY_BUFFER, self.grid_ebo);
+            gl::BufferData(gl::ELEMENT_ARRAY_BUFFER, mem::size_of_val(indices.as_slice()) as GLsizeiptr, indices.as_ptr() as *const c_void, gl::STATIC_DRAW);
+            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, (3 * mem::size_of::<GLfloat>()) as GLsizei, ptr::null());
+            gl::EnableVertexAttribArray(0);
+            gl::BindVertexArray(0);
+        }
+        gl_check("building the grid");
+    }
+
+    // The ray tracer's view as a column-major matrix, for lines drawn over the frame
+    fn view_proj_matrix(&self) -> Vec<GLfloat> {
+        let aspect = self.scene_aspect();
+        let radius = self.camera.distance() as f32;
+        // glm's look_at builds the same right/up/forward basis as Camera::basis
+        let (position, (forward, _, _)) = (self.camera.position(), self.camera.basis());
+        let view = glm::ext::look_at(position, position + forward * radius, vec3(0.0, 1.0, 0.0));
+        let projection = glm::ext::perspective(self.camera.fov_degrees.to_radians(), aspect, radius * 1e-3, radius * 1e3);
+        let view_proj = projection * view;
+        let columns = view_proj.as_array();
+        columns.iter().flat_map(|column| [column.x, column.y, column.z, column.w]).collect()
+    }
+
+    // Draws the grid lines over the frame with the same view as the ray tracer
+    fn draw_grid(&self) {
+        if !self.show_grid || self.grid_index_count == 0 {
+            return;
+        }
+        let matrix = self.view_proj_matrix();
+        unsafe {
+            gl::UseProgram(self.grid_shader_program);
+            let name = CString::new("viewProj").unwrap();
+            gl::UniformMatrix4fv(gl::GetUniformLocation(self.grid_shader_program, name.as_ptr()), 1, gl::FALSE, matrix.as_ptr());
+            let name = CString::new("bend").unwrap();
+            gl::Uniform1i(gl::GetUniformLocation(self.grid_shader_program, name.as_ptr()), 1);
+            let name = CString::new("line_color").unwrap();
+            let line_color = gl::GetUniformLocation(self.grid_shader_program, name.as_ptr());
+            let name = CString::new("warp_scale").unwrap();
+            gl::Uniform1f(gl::GetUniformLocation(self.grid_shader_program, name.as_ptr()), self.grid_warp);
+            let name = CString::new("extent").unwrap();
+            gl::Uniform1f(gl::GetUniformLocation(self.grid_shader_program, name.as_ptr()), self.grid_extent.in_meters() as f32);
+
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::BindVertexArray(self.grid_vao);
+            if !self.grid_wireframe {
+                // the sheet's indices follow the lines' in the same buffer
+                gl::Uniform4f(line_color, 0.4, 0.6, 1.0, 0.15);
+                let offset = self.grid_index_count as usize * mem::size_of::<u32>();
+                gl::DrawElements(gl::TRIANGLES, self.grid_sheet_count, gl::UNSIGNED_INT, offset as *const c_void);
+            }
+            gl::Uniform4f(line_color, 0.4, 0.6, 1.0, 0.6);
+            gl::DrawElements(gl::LINES, self.grid_index_count, gl::UNSIGNED_INT, ptr::null());
+            gl::Disable(gl::BLEND);
+        }
+        gl_check("drawing the grid");
+    }
+
+    /// Draws the spacetime grid as lines alone, or with `on` false fills its cells with a faint
+    /// translucent sheet under the lines, which shows the shape of the dips better from low angles.
+    pub fn set_grid_wireframe(&mut self, on: bool) {
+        self.grid_wireframe = on;
+    }
+
+    fn toggle_grid_wireframe(&mut self) {
+        self.set_grid_wireframe(!self.grid_wireframe);
+        info!("Grid wireframe turned {}", if self.grid_wireframe { "ON" } else { "OFF" });
+    }
+
+    /// Draws the paths of the rays through every `stride`th compute pixel across and down, from
+    /// the middle of the first cell, over the frame: red for the ones the black hole captures,
+    /// green for the ones that escape. The CPU traces them again with the shader's fixed-step
+    /// Schwarzschild integrator, whatever mode and spin the image uses, from where the camera is
+    /// now. They stay put when it moves, so orbiting away shows them bend around the photon
+    /// sphere from the side. 0 turns them off.
+    pub fn set_geodesic_debug(&mut self, stride: u32) {
+        self.geodesic_stride = stride;
+        self.geodesic_paths_stale = true;
+    }
+
+    // Halves the spacing from 64 down to 16 px, then turns the paths off
+    fn cycle_geodesic_debug(&mut self) {
+        let next = match self.geodesic_stride {
+            0 => 64,
+            stride if stride > 16 => stride / 2,
+            _ => 0,
+        };
+        self.set_geodesic_debug(next);
+        match next {
+            0 => info!("Geodesic paths turned OFF"),
+            stride => info!("Geodesic paths every {} px", stride),
+        }
+    }
+
+    // Traces the debug rays from the current camera and uploads them as lines
+    fn rebuild_geodesic_paths(&mut self) {
+        let aspect = self.scene_aspect();
+        let position = self.camera.position();
+        // the shader's escapeRadius, objects left out
+        let rs = self.schwarzschild_radius();
+        let escape = self.disk.outer_radius.in_meters().max(glm::length(position) as f64).max(3.0 * rs) * 1.01;
+        let (width, height) = (self.compute_width as u32, self.compute_height as u32);
+        let stride = self.geodesic_stride.max(1);
+        let mut lines: [Vec<Vec3>; 2] = [Vec::new(), Vec::new()];
+        for py in (stride / 2..height).step_by(stride as usize) {
+            for px in (stride / 2..width).step_by(stride as usize) {
+                let (fx, fy) = ((px as f64 + 0.5) / width as f64, (py as f64 + 0.5) / height as f64);
+                let dir = self.camera.ray_through(aspect, fx, fy);
+                let path = geodesic::trace(position, dir, rs, self.step_size as f64, self.max_steps, escape);
+                let out = &mut lines[(path.fate == Fate::Escaped) as usize];
+                out.extend(path.points.windows(2).flatten());
+            }
+        }
+        self.path_counts = [lines[0].len() as GLsizei, lines[1].len() as GLsizei];
+        let vertices: Vec<GLfloat> = lines.iter().flatten().flat_map(|p| [p.x, p.y, p.z]).collect();
+        unsafe {
+            if self.path_vao == 0 {
+                gl::GenVertexArrays(1, &mut self.path_vao);
+                gl::GenBuffers(1, &mut self.path_vbo);
+            }
+            gl::BindVertexArray(self.path_vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.path_vbo);
+            gl::BufferData(gl::ARRAY_BUFFER, mem::size_of_val(vertices.as_slice()) as GLsizeiptr, vertices.as_ptr() as *const c_void, gl::DYNAMIC_DRAW);
+            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, (3 * mem::size_of::<GLfloat>()) as GLsizei, ptr::null());
+            gl::EnableVertexAttribArray(0);
+            gl::BindVertexArray(0);
+        }
+        self.geodesic_paths_stale = false;
+        gl_check("tracing the geodesic paths");
+    }
+
+    // Draws the debug rays with the grid program, left flat
+    fn draw_geodesic_paths(&mut self) {
+        if self.geodesic_stride == 0 {
+            return;
+        }
+        if self.geodesic_paths_stale {
+            self.rebuild_geodesic_paths();
+        }
+        if self.path_vao == 0 {
+            return;
+        }
+        let matrix = self.view_proj_matrix();
+        unsafe {
+            gl::UseProgram(self.grid_shader_program);
+            let name = CString::new("viewProj").unwrap();
+            gl::UniformMatrix4fv(gl::GetUniformLocation(self.grid_shader_program, name.as_ptr()), 1, gl::FALSE, matrix.as_ptr());
+            let name = CString::new("bend").unwrap();
+            gl::Uniform1i(gl::GetUniformLocation(self.grid_shader_program, name.as_ptr()), 0);
+            let color = CString::new("line_color").unwrap();
+            let color = gl::GetUniformLocation(self.grid_shader_program, color.as_ptr());
+
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::BindVertexArray(self.path_vao);
+            gl::Uniform4f(color, 1.0, 0.3, 0.2, 0.8);
+            gl::DrawArrays(gl::LINES, 0, self.path_counts[0]);
+            gl::Uniform4f(color, 0.3, 1.0, 0.4, 0.8);
+            gl::DrawArrays(gl::LINES, self.path_counts[0], self.path_counts[1]);
+            gl::Disable(gl::BLEND);
+        }
+        gl_check("drawing the geodesic paths");
+    }
+
+    /// Drops a massive test particle `r` meters from the black hole in the disk plane, moving
+    /// around it at `tangential_velocity` meters per second of its own proper time, in place of
+    /// the one there was. It orbits under the Schwarzschild effective potential, see
+    /// `TestParticle`, leaving a fading trail, and is gone once it falls through the horizon.
+    /// `TestParticle::circular_speed` gives the speed of a circular orbit.
+    pub fn spawn_particle(&mut self, r: f64, tangential_velocity: f64) {
+        let rs = self.schwarzschild_radius();
+        if r <= rs {
+            warn!("No particle spawned at {:.2} Rs, inside the horizon", r / rs);
+            return;
+        }
+        let particle = TestParticle { pos: [r, 0.0, 0.0], vel: [0.0, 0.0, tangential_velocity] };
+        // over the angular speed of a circular orbit at r
+        self.particle_clock = PARTICLE_TIME_SCALE * (r.powi(3) / (G * self.mass)).sqrt();
+        self.particle_apsides = Apsides::default();
+        self.particle_apsides.observe(particle.pos);
+        self.particle = Some(particle);
+        self.particle_trail.clear();
+    }
+
+    /// How far the test particle's last full orbit, periapsis to periapsis, went past a full
+    /// turn, in degrees. `None` without a particle, before it has been round twice, or while its
+    /// orbit is too close to circular for its periapses to show.
+    pub fn particle_precession_deg(&self) -> Option<f64> {
+        self.particle.and(self.particle_apsides.last_orbit()).map(|orbit| orbit.precession.to_degrees())
+    }
+
+    // Drops a particle on the circular orbit at the ISCO, or with 5% more speed, which swings it
+    // out and back on a strongly precessing orbit
+    fn spawn_particle_at_isco(&mut self, boost: f64) {
+        let rs = self.schwarzschild_radius();
+        // the particle knows only Schwarzschild, a spinning hole's smaller ISCO has it plunge
+        let r = isco_radius(self.spin) * rs;
+        self.spawn_particle(r, TestParticle::circular_speed(r, rs) * boost);
+        self.notify(format!("Test particle dropped at {:.2} Rs, {:.0}% of the circular speed", r / rs, boost * 100.0));
+    }
+
+    fn clear_particle(&mut self) {
+        self.particle = None;
+        self.particle_trail.clear();
+        self.notify("Test particle removed".to_string());
+    }
+
+    // Moves the particle on by `dt` seconds on screen, in steps no longer than its step limit
+    fn advance_particle(&mut self, dt: f64) {
+        let Some(particle) = &mut self.particle else { return };
+        let rs = schwarzschild_radius(self.mass);
+        let mut time = dt * self.particle_clock;
+        while time > 0.0 && particle.radius() > rs {
+            let step = particle.step_limit(rs).min(time);
+            particle.step(rs, step);
+            self.particle_apsides.observe(particle.pos);
+            time -= step;
+        }
+        let [x, y, z] = particle.pos;
+        let captured = particle.radius() <= rs;
+        self.particle_trail.push_back(vec3(x as f32, y as f32, z as f32));
+        if self.particle_trail.len() > TRAIL_POINTS {
+            self.particle_trail.pop_front();
+        }
+        if captured {
+            self.particle = None;
+            self.notify("Test particle fell through the horizon".to_string());
+        }
+    }
+
+    // Draws the trail with the grid program as a line strip in a few stretches, each one older
+    // than the next and fainter
+    fn draw_particle_trail(&mut self) {
+        const FADE_STEPS: usize = 8;
+        if self.particle_trail.len() < 2 {
+            return;
+        }
+        let vertices: Vec<GLfloat> = self.particle_trail.iter().flat_map(|p| [p.x, p.y, p.z]).collect();
+        let count = self.particle_trail.len();
+        let matrix = self.view_proj_matrix();
+        unsafe {
+            if self.trail_vao == 0 {
+                gl::GenVertexArrays(1, &mut self.trail_vao);
+                gl::GenBuffers(1, &mut self.trail_vbo);
+            }
+            gl::BindVertexArray(self.trail_vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.trail_vbo);
+            gl::BufferData(gl::ARRAY_BUFFER, mem::size_of_val(vertices.as_slice()) as GLsizeiptr, vertices.as_ptr() as *const c_void, gl::STREAM_DRAW);
+            gl::VertexAttribPointer(0, 3, gl::FLOAT, gl::FALSE, (3 * mem::size_of::<GLfloat>()) as GLsizei, ptr::null());
+            gl::EnableVertexAttribArray(0);
+
+            gl::UseProgram(self.grid_shader_program);
+            let name = CString::new("viewProj").unwrap();
+            gl::UniformMatrix4fv(gl::GetUniformLocation(self.grid_shader_program, name.as_ptr()), 1, gl::FALSE, matrix.as_ptr());
+            let name = CString::new("bend").unwrap();
+            gl::Uniform1i(gl::GetUniformLocation(self.grid_shader_program, name.as_ptr()), 0);
+            let color = CString::new("line_color").unwrap();
+            let color = gl::GetUniformLocation(self.grid_shader_program, color.as_ptr());
+
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            for i in 0..FADE_STEPS {
+                // the stretches share their end points so the strip stays unbroken
+                let (start, end) = (i * (count - 1) / FADE_STEPS, (i + 1) * (count - 1) / FADE_STEPS);
+                if end > start {
+                    gl::Uniform4f(color, 1.0, 0.9, 0.4, (i + 1) as f32 / FADE_STEPS as f32);
+                    gl::DrawArrays(gl::LINE_STRIP, start as GLint, (end - start + 1) as GLsizei);
+                }
+            }
+            gl::Disable(gl::BLEND);
+            gl::BindVertexArray(0);
+        }
+        gl_check("drawing the particle trail");
+    }
+
+    /// Multiplies the depth of the grid's dips, clamped to [0, 100]. At 1 the grid is the true
+    /// embedding diagram, where a star's dip is far too shallow to see next to the black hole's.
+    pub fn set_grid_warp(&mut self, scale: f32) {
+        self.grid_warp = scale.clamp(0.0, 100.0);
+    }
+
+    // Doubles the warp up to 16, then starts over at the true embedding
+    fn cycle_grid_warp(&mut self) {
+        let next = if self.grid_warp >= 16.0 { 1.0 } else { self.grid_warp * 2.0 };
+        self.set_grid_warp(next);
+        info!("Grid warp x{}", self.grid_warp);
+    }
+
+    fn toggle_grid(&mut self) {
+        self.show_grid = !self.show_grid;
+        info!("Spacetime grid turned {}", if self.show_grid { "ON" } else { "OFF" });
+    }
+
+    // Uses the steps of the current quality preset, sized for the current mass
+    fn apply_quality(&mut self) {
+        let (steps, step) = self.quality.params();
+        self.set_integration_quality(steps, step * (self.schwarzschild_radius() / SAGA_RS) as f32);
+    }
+
+    pub fn set_integration_mode(&mut self, mode: IntegrationMode) {
+        self.integration_mode = mode;
+        self.update_integrator();
+    }
+
+    /// Relative position error the adaptive mode allows per step, clamped to [1e-7, 1e-1].
+    /// Float precision makes anything much below 1e-6 just burn steps.
+    pub fn set_integration_tolerance(&mut self, tolerance: f32) {
+        self.tolerance = tolerance.clamp(1e-7, 1e-1);
+        self.update_integrator();
+    }
+
+    fn toggle_integration_mode(&mut self) {
+        self.set_integration_mode(match self.integration_mode {
+            IntegrationMode::Fixed => IntegrationMode::Adaptive,
+            IntegrationMode::Adaptive => IntegrationMode::Fixed,
+        });
+        info!("Integration mode {:?}", self.integration_mode);
+    }
+
+    fn update_integrator(&mut self) {
+        self.reset_accumulation();
+        ubo::upload(self.integrator_ubo, &self.integrator_block());
+    }
+
+    fn integrator_block(&self) -> IntegratorUbo {
+        IntegratorUbo {
+            max_steps: self.max_steps,
+            step_size: self.step_size,
+            integration_mode: self.integration_mode as GLint,
+            tolerance: self.tolerance,
+            horizon_epsilon: self.horizon_epsilon(),
+            ..IntegratorUbo::default()
+        }
+    }
+
+    /// Ends rays that come within `epsilon` Schwarzschild radii above the horizon as captured,
+    /// rather than letting the ones that creep towards it spend every step and speckle the
+    /// shadow's edge. Clamped to [0, MAX_HORIZON_EPSILON]: below the photon sphere at 1.5 rs
+    /// no light escapes a Schwarzschild hole, so no escaping ray is cut short. Kerr holes keep
+    /// at least their 1% margin. None follows the step size, ending rays one step above it.
+    pub fn set_horizon_epsilon(&mut self, epsilon: Option<f32>) {
+        self.horizon_epsilon = epsilon;
+        self.update_integrator();
+    }
+
+    fn horizon_epsilon(&self) -> f32 {
+        let one_step = (self.step_size as f64 / self.schwarzschild_radius()) as f32;
+        self.horizon_epsilon.unwrap_or(one_step).clamp(0.0, MAX_HORIZON_EPSILON)
+    }
+
+    fn cycle_quality(&mut self) {
+        self.quality = self.quality.next();
+        self.apply_quality();
+        info!("Lensing quality {:?}: {} steps of {:.0e} m", self.quality, self.max_steps, self.step_size);
+    }
+
+    /// Scatters a procedural starfield behind the scene. `density` is the chance that a cell of
+    /// the sky grid holds a star, clamped to [0, 1], and `seed` picks the arrangement.
+    pub fn set_starfield(&mut self, density: f32, seed: u32) {
+        self.star_density = density.clamp(0.0, 1.0);
+        self.star_seed = seed;
+        self.update_sky();
+    }
+
+    /// Seeds everything random in a frame: the star arrangement, on top of the starfield's own
+    /// seed, and where along its Halton sequence the anti-aliasing jitter starts. The same seed,
+    /// view and parameters render the same image on the same GPU and driver; seed 0 is the
+    /// unseeded look.
+    pub fn set_seed(&mut self, seed: u64) {
+        self.seed = seed;
+        self.update_sky();
+    }
+
+    /// Moves the simulation, the camera's motion, the test particle and the clocks, on by `dt`
+    /// seconds a frame whatever the frame took, so the same inputs play out the same way at any
+    /// framerate. None follows the wall clock again; the frame times are measured either way, so
+    /// switching doesn't make the next frame jump. Recordings step by it too, or by their own
+    /// frame rate when it is None. Values that aren't positive count as None.
+    pub fn set_fixed_timestep(&mut self, dt: Option<f64>) {
+        self.fixed_timestep = dt.filter(|dt| dt.is_finite() && *dt > 0.0);
+    }
+
+    fn toggle_starfield(&mut self) {
+        self.starfield_enabled = !self.starfield_enabled;
+        self.update_sky();
+        info!("Starfield turned {}", if self.starfield_enabled { "ON" } else { "OFF" });
+    }
+
+    /// Sets the linear color escaped rays see when no background image is loaded, the starfield
+    /// drawn on top of it. Black by default, like empty space; negative channels are clamped to 0.
+    pub fn set_background(&mut self, color: Vec3) {
+        self.background_color = vec3(color.x.max(0.0), color.y.max(0.0), color.z.max(0.0));
+        self.update_sky();
+    }
+
+    /// Loads an equirectangular panorama (PNG, JPEG, HDR, EXR or anything else `image` reads) and
+    /// shows it behind the scene in place of the starfield. Calling it again swaps the image in the
+    /// same texture, and on error the current background is kept.
+    pub fn load_background(&mut self, path: &str) -> Result<(), EngineError> {
+        let image = image::open(path).map_err(|source| EngineError::Image { path: path.to_string(), source })?;
+        // 8 and 16 bit images hold sRGB encoded colors, float ones (HDR, EXR) are already linear radiance
+        let encoded = !matches!(image.color(), image::ColorType::Rgb32F | image::ColorType::Rgba32F);
+        let mut image = image.into_rgba32f();
+        if encoded {
+            for pixel in image.pixels_mut() {
+                for channel in &mut pixel.0[..3] {
+                    *channel = srgb_to_linear(*channel);
+                }
+            }
+        }
+        let (width, height) = image.dimensions();
+        if width != 2 * height {
+            // still spread over the whole sphere, just stretched
+            warn!("Background {} is {}x{}, equirectangular images are twice as wide as they are tall", path, width, height);
+        }
+        unsafe {
+            if self.background == 0 {
+                gl::GenTextures(1, &mut self.background);
+                gl::BindTexture(gl::TEXTURE_2D, self.background);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+                // longitude wraps around at the seam, latitude stops at the poles
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::REPEAT as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+            }
+            gl::BindTexture(gl::TEXTURE_2D, self.background);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            // float storage keeps the range of HDR panoramas, rows go top-down so v = 0 is the north pole
+            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA16F as GLint, width as GLsizei, height as GLsizei, 0,
+                gl::RGBA, gl::FLOAT, image.as_raw().as_ptr() as *const c_void);
+        }
+        gl_check("uploading the background");
+        self.background_path = Some(path.to_string());
+        self.update_sky();
+        info!("Loaded {}x{} background {}", width, height, path);
+        Ok(())
+    }
+
+    /// Loads an image as the disk's emission: the horizontal axis runs from the inner edge of the
+    /// disk to the outer one and the vertical axis once around it, so a single row is a plain
+    /// radial ramp. How the texels are read follows the disk profile, see `set_disk_profile`; the
+    /// gradient profile switches to the color one. Loading again, say after editing the image,
+    /// replaces the texture.
+    pub fn load_disk_texture(&mut self, path: &str) -> Result<(), EngineError> {
+        let image = image::open(path).map_err(|source| EngineError::Image { path: path.to_string(), source })?;
+        // colors are sRGB encoded like the background's, a temperature ramp is read as plain values
+        let encoded = !matches!(image.color(), image::ColorType::Rgb32F | image::ColorType::Rgba32F);
+        let mut image = image.into_rgba32f();
+        if encoded && self.disk_profile != DiskProfile::Temperature {
+            for pixel in image.pixels_mut() {
+                for channel in &mut pixel.0[..3] {
+                    *channel = srgb_to_linear(*channel);
+                }
+            }
+        }
+        let (width, height) = image.dimensions();
+        self.upload_disk_texture(width, height, image.as_raw());
+        self.disk_texture_path = Some(path.to_string());
+        self.disk_peak_temperature = None;
+        if self.disk_profile == DiskProfile::Gradient {
+            self.disk_profile = DiskProfile::Color;
+        }
+        let disk = self.disk;
+        self.update_disk(&disk);
+        info!("Loaded {}x{} disk texture {}", width, height, path);
+        Ok(())
+    }
+
+    /// Makes the disk glow in blackbody colors for a thin disk whose inner edge is at `peak_k`
+    /// kelvin, cooling outwards as r^(-3/4): red at the rim of a cool disk, blue-white at a hot
+    /// one's center. The colors go into the disk texture, replacing any loaded image, and the
+    /// Doppler and gravitational shifts tint them further. Clamped to [1000, 1e6] K; past
+    /// 40000 K the color doesn't change any more.
+    pub fn set_disk_temperature(&mut self, peak_k: f32) {
+        self.disk_peak_temperature = Some(peak_k.clamp(1000.0, 1e6));
+        self.disk_texture_path = None;
+        self.disk_profile = DiskProfile::Color;
+        self.build_temperature_ramp();
+        let disk = self.disk;
+        self.update_disk(&disk);
+    }
+
+    // Fills the disk texture with the colors of the temperature profile, one texel row from the
+    // inner edge to the outer one
+    fn build_temperature_ramp(&mut self) {
+        const TEXELS: u32 = 256;
+        let Some(peak) = self.disk_peak_temperature else {
+            return;
+        };
+        let (inner, outer) = (self.disk.inner_radius.in_meters(), self.disk.outer_radius.in_meters());
+        let texels: Vec<f32> = (0..TEXELS).flat_map(|i| {
+            // texel centers, so the edge texels hold the edge temperatures under the clamp
+            let r = inner + (outer - inner) * (i as f64 + 0.5) / TEXELS as f64;
+            let color = blackbody_rgb(disk_temperature(r, inner, peak));
+            [color.x, color.y, color.z, 1.0]
+        }).collect();
+        self.upload_disk_texture(TEXELS, 1, &texels);
+        self.disk_ramp_ratio = self.disk.outer_radius / self.disk.inner_radius;
+    }
+
+    // A row of 256 turbo colors, sampled between texel centers by the compute shader
+    fn colormap_texture() -> gl::types::GLuint {
+        const TEXELS: usize = 256;
+        let texels: Vec<f32> = (0..TEXELS).flat_map(|i| {
+            let color = turbo_rgb(i as f32 / (TEXELS - 1) as f32);
+            [color.x, color.y, color.z, 1.0]
+        }).collect();
+        let mut texture = 0;
+        unsafe {
+            gl::GenTextures(1, &mut texture);
+            gl::BindTexture(gl::TEXTURE_2D, texture);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA16F as GLint, TEXELS as GLsizei, 1, 0,
+                gl::RGBA, gl::FLOAT, texels.as_ptr() as *const c_void);
+        }
+        gl_check("uploading the colormap");
+        texture
+    }
+
+    // (Re)creates the disk texture from RGBA float texels, rows from the top of the image
+    fn upload_disk_texture(&mut self, width: u32, height: u32, texels: &[f32]) {
+        unsafe {
+            if self.disk_texture == 0 {
+                gl::GenTextures(1, &mut self.disk_texture);
+                gl::BindTexture(gl::TEXTURE_2D, self.disk_texture);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::LINEAR as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::LINEAR as GLint);
+                // the edge texels hold at the disk's edges, the angle wraps around
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+                gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::REPEAT as GLint);
+            }
+            gl::BindTexture(gl::TEXTURE_2D, self.disk_texture);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA16F as GLint, width as GLsizei, height as GLsizei, 0,
+                gl::RGBA, gl::FLOAT, texels.as_ptr() as *const c_void);
+        }
+        gl_check("uploading the disk texture");
+    }
+
+    /// Reads the disk texture again from where it was loaded, keeping the old one on failure.
+    pub fn reload_disk_texture(&mut self) {
+        if let Some(path) = self.disk_texture_path.clone()
+            && let Err(error) = self.load_disk_texture(&path) {
+            error!("{}", error);
+        }
+    }
+
+    /// Picks how the disk texture is read. The texture is loaded again when the choice between
+    /// colors and temperatures changes, as only colors are decoded from sRGB.
+    pub fn set_disk_profile(&mut self, profile: DiskProfile) {
+        let reload = (profile == DiskProfile::Temperature) != (self.disk_profile == DiskProfile::Temperature);
+        self.disk_profile = profile;
+        if reload && profile != DiskProfile::Gradient {
+            self.reload_disk_texture();
+        }
+        let disk = self.disk;
+        self.update_disk(&disk);
+    }
+
+    // Steps through the profiles, only the gradient while no disk texture is loaded
+    fn cycle_disk_profile(&mut self) {
+        if self.disk_texture == 0 {
+            info!("No disk texture loaded, set disk.texture in the scene");
+            return;
+        }
+        self.set_disk_profile(match self.disk_profile {
+            DiskProfile::Gradient => DiskProfile::Color,
+            DiskProfile::Color => DiskProfile::Temperature,
+            DiskProfile::Temperature => DiskProfile::Gradient,
+        });
+        info!("Disk profile {:?}", self.disk_profile);
+    }
+
+    /// Picks how the disk is drawn, `DiskModel::None` hides it to show the lensing alone.
+    pub fn set_disk_model(&mut self, model: DiskModel) {
+        self.disk_model = model;
+        let disk = self.disk;
+        self.update_disk(&disk);
+    }
+
+    /// Tilts the disk away from the equatorial plane: `inclination` is the angle in radians from
+    /// the black hole's axis to the disk's, 0 lying in the plane and PI / 2 standing on edge in it,
+    /// and `azimuth` turns the direction it leans towards around the hole's axis. The gas keeps
+    /// orbiting around the disk's own axis, a retrograde disk is an inclination of PI.
+    pub fn set_disk_orientation(&mut self, inclination: f64, azimuth: f64) {
+        self.disk_inclination = inclination.clamp(0.0, PI);
+        self.disk_azimuth = azimuth.rem_euclid(2.0 * PI);
+        let mut disk = self.disk;
+        disk.axis = disk_axis(self.disk_inclination, self.disk_azimuth);
+        self.update_disk(&disk);
+    }
+
+    // Ctrl + drag: sideways turns the lean around, up and down tilts the disk further
+    fn drag_disk_tilt(&mut self, x: f64, y: f64) {
+        let (dx, dy) = (x - self.tilt_cursor.0, y - self.tilt_cursor.1);
+        self.tilt_cursor = (x, y);
+        let rate = self.camera.orbit_speed * self.camera.sensitivity;
+        self.set_disk_orientation(self.disk_inclination + dy * rate, self.disk_azimuth + dx * rate);
+    }
+
+    /// Scales the gas of the thick disk: `density` multiplies its opacity, at 1 straight through
+    /// the inner edge has an optical depth of 3, and `emission` how brightly it glows. Thinner
+    /// gas lets more of the lensed sky through, above all at the faded rims. Negatives count as 0.
+    pub fn set_disk_volume(&mut self, density: f32, emission: f32) {
+        self.disk_density_scale = density.max(0.0);
+        self.disk_emission_scale = emission.max(0.0);
+        let disk = self.disk;
+        self.update_disk(&disk);
+    }
+
+    /// Switches what the image shows, see `RenderChannel`.
+    pub fn set_channel(&mut self, channel: RenderChannel) {
+        self.render_channel = channel;
+        self.reset_accumulation();
+    }
+
+    fn cycle_channel(&mut self) {
+        self.set_channel(match self.render_channel {
+            RenderChannel::Color => RenderChannel::DeflectionAngle,
+            RenderChannel::DeflectionAngle => RenderChannel::Redshift,
+            RenderChannel::Redshift => RenderChannel::TravelTime,
+            RenderChannel::TravelTime => RenderChannel::Termination,
+            RenderChannel::Termination => RenderChannel::Color,
+        });
+        info!("Render channel {:?}", self.render_channel);
+    }
+
+    /// Lets rays cross the thin disk up to `order` times, adding what it sends back at each
+    /// crossing, clamped to [1, MAX_IMAGE_ORDER]. 1 is the opaque disk; 2 also shows the
+    /// secondary image, the thin ring of the far side's underside lensed over and under the
+    /// shadow, and higher orders the fainter rings inside it. Rays that wind more need more
+    /// steps, raise the quality if the rings break up.
+    pub fn set_max_image_order(&mut self, order: u32) {
+        self.max_image_order = order.clamp(1, MAX_IMAGE_ORDER);
+        self.reset_accumulation();
+    }
+
+    // Counts the image order up to MAX_IMAGE_ORDER and back to 1
+    fn cycle_image_order(&mut self) {
+        self.set_max_image_order(self.max_image_order % MAX_IMAGE_ORDER + 1);
+        info!("Disk images up to order {}", self.max_image_order);
+    }
+
+    fn cycle_disk_model(&mut self) {
+        self.set_disk_model(match self.disk_model {
+            DiskModel::Thin => DiskModel::Thick,
+            DiskModel::Thick => DiskModel::None,
+            DiskModel::None => DiskModel::Thin,
+        });
+        info!("Disk model {:?}", self.disk_model);
+    }
+
+    /// Sets the temperature a red value of 1 stands for in the temperature profile, clamped to
+    /// [1000, 40000] K where the blackbody colors are fitted.
+    pub fn set_disk_max_temperature(&mut self, kelvin: f32) {
+        self.disk_max_temperature = kelvin.clamp(1000.0, 40000.0);
+        let disk = self.disk;
+        self.update_disk(&disk);
+    }
+
+    fn update_sky(&mut self) {
+        self.reset_accumulation();
+        ubo::upload(self.sky_ubo, &SkyUbo {
+            star_density: self.star_density,
+            star_brightness: self.star_brightness,
+            star_seed: self.star_seed ^ fold_seed(self.seed),
+            starfield_enabled: self.starfield_enabled as i32,
+            background_enabled: (self.background != 0) as i32,
+            background_color: [self.background_color.x, self.background_color.y, self.background_color.z, 0.0],
+            ..SkyUbo::default()
+        });
+    }
+
+    // Nudges the spin in steps of 0.05, landing on MAX_SPIN at the top
+    fn step_spin(&mut self, steps: f64) {
+        self.set_spin(self.spin + steps * 0.05);
+        self.notify(format!("Black hole spin {:.3}", self.spin));
+    }
+
+    /// Turns relativistic Doppler beaming of the disk on or off; off renders the disk flat shaded.
+    pub fn set_doppler(&mut self, enabled: bool) {
+        self.doppler_enabled = enabled;
+        let disk = self.disk;
+        self.update_disk(&disk);
+    }
+
+    fn add_object(&mut self, object: GravObject) {
+        self.objects.push(object);
+        self.upload_objects();
+    }
+
+    /// Adds an object with the mass of the sun where the view ray through the window point `x`, `y`
+    /// (cursor coordinates) meets the disk plane. The ray is the one the renderer starts for that
+    /// pixel, taken as straight: far from the black hole it lands under the cursor, close to it
+    /// the lensed image of the plane is somewhere else. Returns whether an object was placed.
+    pub fn place_object_at_screen(&mut self, x: f64, y: f64) -> bool {
+        if self.objects.len() >= MAX_OBJECTS {
+            warn!("There are already {} objects, the most the shader renders", MAX_OBJECTS);
+            return false;
+        }
+        let dir = self.cursor_ray(x, y);
+        let Some(pos) = disk_plane_hit(self.camera.position(), dir) else {
+            info!("Nothing placed, the cursor points away from the disk plane");
+            return false;
+        };
+        let radius = (self.schwarzschild_radius() * 0.5) as f32;
+        self.add_object(GravObject { pos, radius, color: vec3(0.6, 0.8, 1.0), mass: SOLAR_MASS });
+        self.selected_object = Some(self.objects.len() - 1);
+        info!("Placed object {} at ({:.3e}, {:.3e}) m", self.objects.len(), pos.x, pos.z);
+        true
+    }
+
+    /// Removes the object closest to the view ray through the window point `x`, `y`. Removing
+    /// the selected object clears the selection; the others keep theirs.
+    pub fn remove_object_at_screen(&mut self, x: f64, y: f64) {
+        if let Some(index) = self.object_at_screen(x, y) {
+            self.objects.remove(index);
+            self.selected_object = selection_after_removal(self.selected_object, index);
+            self.upload_objects();
+            info!("Removed object {}, {} left", index + 1, self.objects.len());
+        }
+    }
+
+    /// Selects the object closest to the view ray through the window point `x`, `y`, the one
+    /// `set_selected_object_mass` and `set_selected_object_color` edit. Returns whether there was one.
+    pub fn select_object_at_screen(&mut self, x: f64, y: f64) -> bool {
+        self.selected_object = self.object_at_screen(x, y);
+        if let Some(index) = self.selected_object {
+            let mass = self.objects[index].mass / SOLAR_MASS;
+            self.notify(format!("Selected object {}, {:.3e} solar masses", index + 1, mass));
+        }
+        self.selected_object.is_some()
+    }
+
+    // Index of the object closest to the view ray through a point in window coordinates
+    fn object_at_screen(&self, x: f64, y: f64) -> Option<usize> {
+        let origin = self.camera.position();
+        let dir = self.cursor_ray(x, y);
+        // distance from the object center to the ray, measured along it only ahead of the camera
+        let miss = |object: &GravObject| {
+            let offset = object.pos - origin;
+            glm::length(offset - dir * glm::dot(offset, dir).max(0.0))
+        };
+        self.objects.iter().enumerate()
+            .min_by(|(_, a), (_, b)| miss(a).total_cmp(&miss(b)))
+            .map(|(index, _)| index)
+    }
+
+    fn selected(&self) -> Option<&GravObject> {
+        self.selected_object.and_then(|index| self.objects.get(index))
+    }
+
+    /// Sets the mass of the selected object in kg, clamped to [0, MAX_MASS]; does nothing
+    /// without a selection.
+    pub fn set_selected_object_mass(&mut self, kg: f64) {
+        let Some(index) = self.selected_object else { return };
+        self.objects[index].mass = kg.clamp(0.0, MAX_MASS);
+        self.upload_objects();
+    }
+
+    /// Sets the linear color of the selected object, each channel clamped to [0, 1]; does
+    /// nothing without a selection.
+    pub fn set_selected_object_color(&mut self, color: Vec3) {
+        let Some(index) = self.selected_object else { return };
+        self.objects[index].color = vec3(color.x.clamp(0.0, 1.0), color.y.clamp(0.0, 1.0), color.z.clamp(0.0, 1.0));
+        self.upload_objects();
+    }
+
+    // Multiplies the selected object's mass by 10^steps, from a solar mass up if it has none
+    fn step_selected_object_mass(&mut self, steps: i32) {
+        let Some(object) = self.selected() else {
+            self.notify("No object selected, Shift + click one while placing".to_string());
+            return;
+        };
+        let mass = if object.mass > 0.0 { object.mass } else { SOLAR_MASS };
+        self.set_selected_object_mass(mass * 10f64.powi(steps));
+        let mass = self.selected().map_or(0.0, |object| object.mass);
+        self.notify(format!("Object mass {:.3e} kg ({:.3e} solar masses)", mass, mass / SOLAR_MASS));
+    }
+
+    // Steps the selected object's color around a small palette
+    fn cycle_selected_object_color(&mut self) {
+        const PALETTE: [[f32; 3]; 6] = [
+            [0.6, 0.8, 1.0], [1.0, 1.0, 0.0], [1.0, 0.0, 0.0], [0.2, 1.0, 0.3], [1.0, 0.5, 0.1], [1.0, 1.0, 1.0],
+        ];
+        let Some(object) = self.selected() else {
+            self.notify("No object selected, Shift + click one while placing".to_string());
+            return;
+        };
+        let current = PALETTE.iter().position(|color| vec3(color[0], color[1], color[2]) == object.color);
+        let [r, g, b] = PALETTE[current.map_or(0, |i| (i + 1) % PALETTE.len())];
+        self.set_selected_object_color(vec3(r, g, b));
+        self.notify(format!("Object color {:.1} {:.1} {:.1}", r, g, b));
+    }
+
+    // View ray through a point in window coordinates, as the cursor reports it
+    fn cursor_ray(&self, x: f64, y: f64) -> Vec3 {
+        let (fx, fy) = self.cursor_in_scene(x, y);
+        self.camera.ray_through(self.scene_aspect(), fx, fy)
+    }
+
+    // A point in window coordinates as fractions of the scene's width and height from its top
+    // left corner, outside [0, 1] over the letterbox bars
+    fn cursor_in_scene(&self, x: f64, y: f64) -> (f64, f64) {
+        let (px, py) = self.cursor_pixels(x, y);
+        let [left, top, width, height] = self.scene_rect();
+        ((px as f64 - left as f64) / width as f64, (py as f64 - top as f64) / height as f64)
+    }
+
+    // Where the scene goes in the window, see `letterbox`
+    fn scene_rect(&self) -> [u32; 4] {
+        letterbox(self.win_width, self.win_height, self.output_aspect)
+    }
+
+    // Width over height of the scene, what the rays are spread over
+    fn scene_aspect(&self) -> f32 {
+        let [_, _, width, height] = self.scene_rect();
+        width as f32 / height as f32
+    }
+
+    // The scene as a GL viewport, whose rows count from the bottom
+    fn scene_viewport(&self) -> (GLint, GLint, GLsizei, GLsizei) {
+        let [left, top, width, height] = self.scene_rect();
+        (left as GLint, (self.win_height - top - height) as GLint, width as GLsizei, height as GLsizei)
+    }
+
+    /// Locks the picture to `ratio`, width over height, whatever the window's shape: the scene
+    /// is traced for and drawn into the largest centered rectangle of that shape with black bars
+    /// around it, and the compute resolution follows the rectangle so the pixels stay square.
+    /// Screenshots of the scene, renders and recorded frames hold just the rectangle. None, or
+    /// a ratio that isn't positive, fills the window again.
+    pub fn set_output_aspect(&mut self, ratio: Option<f32>) {
+        self.output_aspect = ratio.filter(|ratio| ratio.is_finite() && *ratio > 0.0);
+        self.apply_resolution_scale();
+    }
+
+    // Off, then the usual cinema and video ratios
+    fn cycle_output_aspect(&mut self) {
+        const RATIOS: [Option<f32>; 4] = [None, Some(2.39), Some(1.85), Some(16.0 / 9.0)];
+        let current = RATIOS.iter().position(|&ratio| ratio == self.output_aspect).unwrap_or(0);
+        self.set_output_aspect(RATIOS[(current + 1) % RATIOS.len()]);
+        match self.output_aspect {
+            Some(ratio) => info!("Output aspect locked to {:.2}:1", ratio),
+            None => info!("Output aspect unlocked"),
+        }
+    }
+
+    fn toggle_object_placement(&mut self) {
+        self.placing_objects = !self.placing_objects;
+        if self.placing_objects {
+            info!("Object placement ON: left click adds an object, Shift + left click selects the nearest, right click removes it");
+        } else {
+            info!("Object placement OFF");
+        }
+    }
+
+    fn upload_objects(&mut self) {
+        self.reset_accumulation();
+        if self.objects.len() > MAX_OBJECTS {
+            warn!("{} objects in the scene, only the first {} are rendered", self.objects.len(), MAX_OBJECTS);
+        }
+        let count = self.objects.len().min(MAX_OBJECTS);
+
+        let mut block = ObjectsUbo { num_objects: count as GLint, ..ObjectsUbo::default() };
+        for (i, object) in self.objects.iter().take(count).enumerate() {
+            block.obj_pos_radius[i] = [object.pos.x, object.pos.y, object.pos.z, object.radius];
+            block.obj_color[i] = [object.color.x, object.color.y, object.color.z, 1.0];
+            block.mass[i][0] = object.mass as GLfloat;
+        }
+        ubo::upload(self.objects_ubo, &block);
+        gl_check("uploading the objects");
+    }
+
+    // Grows or shrinks the inner disk edge in steps of 0.1 Rs, staying outside the horizon and inside the outer edge
+    fn step_disk_inner_radius(&mut self, steps: f32) {
+        let rs = Length::rs(self.mass);
+        let step = rs * 0.1;
+        let mut disk = self.disk;
+        disk.inner_radius = (disk.inner_radius + step * steps as f64).clamp(rs, disk.outer_radius - step);
+        self.update_disk(&disk);
+        self.notify(format!("Disk inner radius {:.2} Rs", disk.inner_radius / rs));
+    }
+
+    // The rays spread over the camera's vertical field of view and `aspect`, the framebuffer's
+    // width over its height, times that across, so the pixels stay square at any window shape
+    fn camera_ubo_data(camera: &Camera, aspect: f32) -> CameraUbo {
+        let position = camera.position();
+        let (forward, right, up) = camera.basis();
+        let tan_half_fov = camera.tan_half_fov();
+
+        CameraUbo {
+            cam_pos: [position.x, position.y, position.z],
+            cam_right: [right.x, right.y, right.z],
+            cam_up: [up.x, up.y, up.z],
+            cam_forward: [forward.x, forward.y, forward.z],
+            tan_half_fov,
+            aspect,
+            moving: camera.moving as u32,
+            ..CameraUbo::default()
+        }
+    }
+
+    fn update_camera(&mut self) {
+        let data = Engine::camera_ubo_data(&self.camera, self.scene_aspect());
+        // the recenter key or a resize changes the view without the camera reporting any motion
+        if self.camera.moving || data != self.last_camera_data {
+            self.reset_accumulation();
+        }
+        self.last_camera_data = data;
+        ubo::upload(self.camera_ubo, &data);
+        gl_check("updating the camera");
+    }
+
+    // (Re)allocates the compute output texture at the current compute resolution
+    fn allocate_texture(&mut self) {
+        self.reset_accumulation();
+        unsafe {
+            for texture in [self.texture, self.bloom_textures[0], self.bloom_textures[1]] {
+                gl::BindTexture(gl::TEXTURE_2D, texture);
+                gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA16F as gl::types::GLint,
+                             self.compute_width, self.compute_height, 0, gl::RGBA, gl::FLOAT, ptr::null());
+            }
+            // full floats so the running mean doesn't round away the 1/n contribution of late frames
+            gl::BindTexture(gl::TEXTURE_2D, self.accum_texture);
+            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA32F as gl::types::GLint,
+                         self.compute_width, self.compute_height, 0, gl::RGBA, gl::FLOAT, ptr::null());
+            gl::BindTexture(gl::TEXTURE_2D, self.edge_texture);
+            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::R8 as gl::types::GLint,
+                         self.compute_width, self.compute_height, 0, gl::RED, gl::UNSIGNED_BYTE, ptr::null());
+        }
+        gl_check("allocating the compute textures");
+    }
+
+    /// Sizes the picture for a framebuffer of `w` x `h` pixels, the window's or, with
+    /// `render_into`, the viewport's, and the compute resolution with it.
+    pub fn resize(&mut self, w: u32, h: u32) {
+        // a minimized window reports 0x0, keep the previous size until it's restored
+        if w == 0 || h == 0 {
+            return;
+        }
+
+        self.win_width = w;
+        self.win_height = h;
+        unsafe { gl::Viewport(0, 0, w as GLsizei, h as GLsizei); }
+
+        // keep the compute resolution at the same fraction of the window
+        self.apply_resolution_scale();
+    }
+
+    /// Waits for the display's vertical blank before showing each frame when `on`, capping the
+    /// frame rate at the refresh rate; off, frames are shown as soon as they are drawn.
+    pub fn set_vsync(&mut self, on: bool) {
+        self.vsync = on;
+        if let Some(app) = &mut self.app_window {
+            app.glfw.set_swap_interval(if on { glfw::SwapInterval::Sync(1) } else { glfw::SwapInterval::None });
+        }
+    }
+
+    fn toggle_vsync(&mut self) {
+        self.set_vsync(!self.vsync);
+        info!("VSync turned {}", if self.vsync { "ON" } else { "OFF" });
+    }
+
+    /// Switches between windowed and borderless fullscreen on the monitor given with `--monitor`,
+    /// otherwise the one under the window's center (the primary one if it's off every screen),
+    /// restoring the windowed position and size on the way back. Keeps the current mode when no
+    /// monitor or video mode is available.
+    pub fn toggle_fullscreen(&mut self) {
+        let Some(app) = &mut self.app_window else {
+            warn!("Can't go fullscreen, the engine doesn't own the window");
+            return;
+        };
+        let fullscreen = app.window.with_window_mode(|mode| matches!(mode, glfw::WindowMode::FullScreen(_)));
+        if fullscreen {
+            let (w, h) = (self.win_width as i32, self.win_height as i32);
+            let ((x, y), (w, h)) = self.windowed_placement.take().unwrap_or(((100, 100), (w, h)));
+            app.window.set_monitor(glfw::WindowMode::Windowed, x, y, w as u32, h as u32, None);
+        } else {
+            let (position, size) = (app.window.get_pos(), app.window.get_size());
+            let center = (position.0 + size.0 / 2, position.1 + size.1 / 2);
+            if !monitors_connected(&mut app.glfw) {
+                warn!("Can't go fullscreen, no monitor is connected");
+                return;
+            }
+            let (window, requested) = (&mut app.window, self.monitor);
+            let switched = app.glfw.with_connected_monitors(|_, monitors| {
+                // GLFW lists the primary monitor first
+                let monitor = match requested {
+                    Some(index) => requested_monitor(monitors, index),
+                    None => monitors.iter().find(|monitor| {
+                        let (x, y) = monitor.get_pos();
+                        monitor.get_video_mode().is_some_and(|mode| {
+                            (x..x + mode.width as i32).contains(&center.0) && (y..y + mode.height as i32).contains(&center.1)
+                        })
+                    }).or(monitors.first()).map(|monitor| &**monitor),
+                };
+                let Some(monitor) = monitor else {
+                    return Err("no monitor is connected");
+                };
+                let Some(mode) = monitor.get_video_mode() else {
+                    return Err("the monitor reports no video mode");
+                };
+                // matching the desktop mode gives a borderless window instead of a mode switch
+                window.set_monitor(glfw::WindowMode::FullScreen(monitor), 0, 0, mode.width, mode.height, Some(mode.refresh_rate));
+                Ok(())
+            });
+            if let Err(reason) = switched {
+                warn!("Can't go fullscreen, {}", reason);
+                return;
+            }
+            self.windowed_placement = Some((position, size));
+        }
+
+        // the framebuffer size event follows later, size the viewport and compute texture right away
+        let (w, h) = app.window.get_framebuffer_size();
+        self.resize(w.max(0) as u32, h.max(0) as u32);
+        info!("Fullscreen turned {}", if fullscreen { "OFF" } else { "ON" });
+    }
+
+    fn set_compute_resolution(&mut self, w: i32, h: i32) {
+        self.compute_width = w.max(1);
+        self.compute_height = h.max(1);
+        // the image unit is rebound to the new storage on the next dispatch
+        self.allocate_texture();
+    }
+
+    fn apply_resolution_scale(&mut self) {
+        let [_, _, width, height] = self.scene_rect();
+        let w = ((width as f32 * self.resolution_scale).round() as i32).max(1);
+        let h = ((height as f32 * self.resolution_scale).round() as i32).max(1);
+        let factor = self.supersampling as i32;
+        self.set_compute_resolution(w * factor, h * factor);
+    }
+
+    /// Traces `factor` x `factor` rays per pixel of the output, clamped to [1, MAX_SUPERSAMPLING]:
+    /// the compute texture grows to `factor` times the resolution the scale gives and the quad
+    /// shader averages each block back down, on top of the linear upscale when the scale is
+    /// below 1. Unlike the accumulated frames this sharpens a single frame, at `factor`^2 the
+    /// memory and tracing time.
+    pub fn set_supersampling(&mut self, factor: u32) {
+        self.supersampling = factor.clamp(1, MAX_SUPERSAMPLING);
+        self.apply_resolution_scale();
+    }
+
+    /// Keeps the viewer at `target_fps` by lowering the resolution scale when frames take too
+    /// long and raising it, up to the window's resolution, when there's time to spare, see
+    /// `ResolutionController`. Every change restarts the accumulation. None, or a rate that isn't
+    /// positive, leaves the scale where it is and to the keys and panel again.
+    pub fn set_dynamic_resolution(&mut self, target_fps: Option<f64>) {
+        self.dynamic_resolution = target_fps.filter(|fps| fps.is_finite() && *fps > 0.0).map(ResolutionController::new);
+    }
+
+    // Off, then holding 60 FPS
+    fn toggle_dynamic_resolution(&mut self) {
+        self.set_dynamic_resolution(if self.dynamic_resolution.is_some() { None } else { Some(60.0) });
+        match self.dynamic_resolution {
+            Some(controller) => info!("Dynamic resolution holding {} FPS", controller.target_fps),
+            None => info!("Dynamic resolution turned OFF"),
+        }
+    }
+
+    // Runs once a second with the frame rate just measured. Only a second in which every frame
+    // traced says what tracing costs, converged and paused frames skip the dispatch
+    fn adjust_dynamic_resolution(&mut self, traced_every_frame: bool) {
+        let Some(controller) = &mut self.dynamic_resolution else {
+            return;
+        };
+        if !traced_every_frame {
+            return;
+        }
+        // vsync holds the wall time at the refresh rate however much headroom there is, the
+        // GPU's own time for the frame shows it
+        let gpu_ms = self.profiler.as_ref().and_then(FrameProfiler::latest).map(|timings| timings.total_ns as f64 * 1e-6);
+        let frame_ms = if self.vsync { gpu_ms.unwrap_or(self.frame_ms) } else { self.frame_ms };
+        if let Some(scale) = controller.update(self.resolution_scale, frame_ms) {
+            self.resolution_scale = scale;
+            self.apply_resolution_scale();
+            debug!("Dynamic resolution at {:.2} ms: {}x{} ({:.0}%)", frame_ms, self.compute_width, self.compute_height, scale * 100.0);
+        }
+    }
+
+    fn step_resolution_scale(&mut self, steps: f32) {
+        self.resolution_scale = glm::clamp(self.resolution_scale + steps * 0.05, MIN_RESOLUTION_SCALE, MAX_RESOLUTION_SCALE);
+        self.apply_resolution_scale();
+        info!("Compute resolution {}x{} ({:.0}%)", self.compute_width, self.compute_height, self.resolution_scale * 100.0);
+    }
+
+    /// Sets the vertical field of view in degrees, clamped to [MIN_FOV, MAX_FOV]; the horizontal
+    /// one follows from the window's aspect ratio. Unlike moving the camera in, this zooms the
+    /// lens: the camera stays where it is and only the spread of its rays changes, so a wide
+    /// angle shows the photon ring small in a sky stretched towards the edges.
+    pub fn set_fov(&mut self, degrees: f32) {
+        self.camera.fov_degrees = degrees.clamp(MIN_FOV, MAX_FOV);
+    }
+
+    // Zooms the lens by 5 degrees a step, positive narrows the view
+    fn step_fov(&mut self, steps: f32) {
+        self.set_fov(self.camera.fov_degrees - 5.0 * steps);
+        info!("Field of view {:.0} deg", self.camera.fov_degrees);
+    }
+
+    fn screenshot(&self, path: &str, source: CaptureSource) -> Result<(), EngineError> {
+        let [left, top, width, height] = match source {
+            CaptureSource::Window => [0, 0, self.win_width, self.win_height],
+            CaptureSource::Scene => self.scene_rect(),
+            CaptureSource::Compute => [0, 0, self.compute_width as u32, self.compute_height as u32],
+        };
+        let mut pixels: Vec<u8> = vec![0; (width * height * 4) as usize];
+        unsafe {
+            // rows are tightly packed in `pixels`, don't let GL pad them to its default 4 byte alignment
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            match source {
+                CaptureSource::Window | CaptureSource::Scene => {
+                    gl::BindFramebuffer(gl::READ_FRAMEBUFFER, 0);
+                    let bottom = self.win_height - top - height;
+                    gl::ReadPixels(left as GLsizei, bottom as GLsizei, width as GLsizei, height as GLsizei, gl::RGBA, gl::UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut c_void);
+                },
+                CaptureSource::Compute => {
+                    let mut radiance: Vec<f32> = vec![0.0; pixels.len()];
+                    gl::BindTexture(gl::TEXTURE_2D, self.texture);
+                    gl::GetTexImage(gl::TEXTURE_2D, 0, gl::RGBA, gl::FLOAT, radiance.as_mut_ptr() as *mut c_void);
+                    for (i, (byte, value)) in pixels.iter_mut().zip(radiance).enumerate() {
+                        // alpha isn't a color
+                        let encoded = if i % 4 == 3 { value.clamp(0.0, 1.0) } else { linear_to_srgb(value) };
+                        *byte = (encoded * 255.0).round() as u8;
+                    }
+                }
+            }
+        }
+
+        save_pixels(path, width, height, pixels)
+    }
+
+    /// Renders one still at `opts.width` x `opts.height` without showing the window: the view from
+    /// the scene is traced at the High quality preset, accumulated over `opts.samples` jittered
+    /// frames, tone mapped and written to `path` as a PNG.
+    pub fn render_to_file(&mut self, opts: RenderOptions, path: &str) -> Result<(), EngineError> {
+        self.prepare_offline(opts);
+        info!("Rendering {}x{} with {} samples", opts.width, opts.height, self.aa_samples);
+        let start = self.now();
+        let pixels = self.render_converged();
+        let [_, _, width, height] = self.scene_rect();
+        save_pixels(path, width, height, pixels)?;
+        info!("Saved render to {} ({:.1} s)", path, self.now() - start);
+        Ok(())
+    }
+
+    /// Sets up offline rendering at the size and sample count of `opts` with the High quality preset.
+    pub fn prepare_offline(&mut self, opts: RenderOptions) {
+        // the window is never presented, the render size stands in for it so the aspect and the
+        // viewport of the post-processing passes follow
+        self.win_width = opts.width;
+        self.win_height = opts.height;
+        self.resolution_scale = 1.0;
+        self.set_supersampling(opts.supersampling);
+        self.quality = QualityPreset::High;
+        self.apply_quality();
+        self.set_aa_samples(opts.samples);
+    }
+
+    /// Sweeps the camera azimuth through `revolutions` full turns over `frames` frames, accumulating
+    /// each one to convergence and writing it to `out_dir` as frame_00000.png onwards. The last frame
+    /// stops one step short of the start, so the sequence loops without a repeated frame. The
+    /// simulation moves on by the fixed timestep between frames, or by 1 / `fps` without one.
+    pub fn record_orbit(&mut self, frames: u32, revolutions: f64, fps: u32, out_dir: &str) -> Result<(), EngineError> {
+        std::fs::create_dir_all(out_dir).map_err(|source| EngineError::Io { path: out_dir.to_string(), source })?;
+        let start_azimuth = self.camera.azimuth;
+        let start = self.now();
+        let dt = self.fixed_timestep.unwrap_or(1.0 / fps.max(1) as f64);
+        for frame in 0..frames {
+            self.camera.azimuth = start_azimuth + std::f64::consts::TAU * revolutions * frame as f64 / frames as f64;
+            self.record_frame(out_dir, frame, frames, start)?;
+            self.step_simulation(dt);
+        }
+        self.camera.azimuth = start_azimuth;
+        Ok(())
+    }
+
+    /// Steps through the loaded camera timeline at its `fps`, accumulating every frame to
+    /// convergence and writing it to `out_dir` as frame_00000.png onwards. A looping timeline leaves
+    /// out the frame on its last keyframe, which would repeat the first. The simulation moves on
+    /// by the fixed timestep between frames, or by the timeline's frame time without one.
+    pub fn record_timeline(&mut self, out_dir: &str) -> Result<(), EngineError> {
+        let Some(timeline) = self.timeline.clone() else {
+            warn!("No camera timeline loaded, nothing to record");
+            return Ok(());
+        };
+        std::fs::create_dir_all(out_dir).map_err(|source| EngineError::Io { path: out_dir.to_string(), source })?;
+        let steps = (timeline.duration() * timeline.fps as f64).round() as u32;
+        let frames = if timeline.looping { steps } else { steps + 1 };
+        let start = self.now();
+        let dt = self.fixed_timestep.unwrap_or(1.0 / timeline.fps as f64);
+        for frame in 0..frames {
+            self.camera.apply_keyframe(&timeline.sample(timeline.start() + frame as f64 / timeline.fps as f64));
+            self.record_frame(out_dir, frame, frames, start)?;
+            self.step_simulation(dt);
+        }
+        Ok(())
+    }
+
+    // Renders the current view to convergence as frame number `frame` of a sequence in `out_dir`
+    fn record_frame(&mut self, out_dir: &str, frame: u32, frames: u32, start: f64) -> Result<(), EngineError> {
+        let pixels = self.render_converged();
+        let path = format!("{}/frame_{:05}.png", out_dir, frame);
+        let [_, _, width, height] = self.scene_rect();
+        save_pixels(&path, width, height, pixels)?;
+        info!("Saved frame {}/{} to {} ({:.1} s)", frame + 1, frames, path, self.now() - start);
+        Ok(())
+    }
+
+    /// Times the scene's view at every combination of the compute sizes and step counts in `opts`
+    /// and prints a table of the results. Each frame traces every pixel from scratch without
+    /// accumulating, then waits for the GPU to finish; the camera holds still, so the same
+    /// scene always gives the same work. A step count spreads the scene's ray length over that
+    /// many steps, so only the accuracy changes and not how far the rays reach. The sizes, steps and
+    /// samples the engine had are put back afterwards.
+    pub fn benchmark(&mut self, opts: &BenchmarkOptions) -> Result<Vec<BenchmarkResult>, EngineError> {
+        let sizes = match opts.sizes.as_slice() {
+            [] => vec![(self.compute_width as u32, self.compute_height as u32)],
+            sizes => sizes.to_vec(),
+        };
+        let steps = match opts.steps.as_slice() {
+            [] => vec![self.max_steps],
+            steps => steps.to_vec(),
+        };
+        let reach = self.max_steps as f64 * self.step_size as f64;
+        let timer = GpuTimer::new();
+        if timer.is_none() {
+            warn!("This context has no GPU timestamps, only frame times are measured");
+        }
+        let (win_width, win_height) = (self.win_width, self.win_height);
+        let (compute_width, compute_height) = (self.compute_width, self.compute_height);
+        let (max_steps, step_size, aa_samples) = (self.max_steps, self.step_size, self.aa_samples);
+        self.set_aa_samples(1);
+
+        let mut results = Vec::new();
+        for &(width, height) in &sizes {
+            // the post-processing passes and the aspect follow the window size
+            self.win_width = width;
+            self.win_height = height;
+            self.set_compute_resolution(width as i32, height as i32);
+            for &count in &steps {
+                self.set_integration_quality(count, (reach / count as f64) as f32);
+                info!("Benchmarking {}x{} at {} steps, {} frames", width, height, self.max_steps, opts.frames);
+                let mut frame_ms = Vec::with_capacity(opts.frames as usize);
+                let mut gpu_ms = 0.0;
+                for frame in 0..opts.warmup + opts.frames {
+                    let start = self.now();
+                    if let Some(timer) = &timer { timer.start(); }
+                    self.benchmark_frame();
+                    if let Some(timer) = &timer { timer.stop(); }
+                    unsafe { gl::Finish(); }
+                    if frame >= opts.warmup {
+                        frame_ms.push((self.now() - start) * 1000.0);
+                        gpu_ms += timer.as_ref().map_or(0.0, GpuTimer::elapsed_ms);
+                    }
+                }
+                results.push(BenchmarkResult {
+                    width, height, steps: self.max_steps,
+                    frames: FrameStats::from_samples(&frame_ms).unwrap(),
+                    gpu_ms: timer.as_ref().map(|_| gpu_ms / opts.frames as f64),
+                });
+            }
+        }
+        self.win_width = win_width;
+        self.win_height = win_height;
+        self.set_compute_resolution(compute_width, compute_height);
+        self.set_integration_quality(max_steps, step_size);
+        self.set_aa_samples(aa_samples);
+        print!("{}", benchmark::format_table(&results));
+        Ok(results)
+    }
+
+    // One viewer frame without the overlay or the swap, traced from scratch
+    fn benchmark_frame(&mut self) {
+        self.reset_accumulation();
+        self.update_camera();
+        self.dispatch_compute();
+        self.apply_bloom();
+        self.apply_lens_flare();
+        let (x, y, width, height) = self.scene_viewport();
+        unsafe { gl::Viewport(x, y, width, height); }
+        self.draw_fullscreen_quad();
+        gl_check("benchmark frame");
+    }
+
+    // Shift+number stores the current view in a slot, the number alone goes back to it
+    fn use_bookmark(&mut self, slot: usize, store: bool) {
+        if store {
+            self.bookmarks[slot] = Some(self.camera.bookmark());
+            self.bookmarks_changed = true;
+            info!("Stored bookmark {}", slot + 1);
+        } else if let Some(state) = self.bookmarks[slot] {
+            self.camera.restore(&state);
+            info!("Recalled bookmark {}", slot + 1);
+        } else {
+            info!("Bookmark {} is empty, Shift+{} stores the current view", slot + 1, slot + 1);
+        }
+    }
+
+    // Zooms out or in until the whole disk is in view, whichever way the window is stretched
+    fn frame_disk(&mut self) {
+        let tan_half_fov = self.camera.tan_half_fov() as f64;
+        let narrower = tan_half_fov.min(tan_half_fov * self.scene_aspect() as f64);
+        self.camera.frame_disk(self.disk.outer_radius.in_meters(), 2.0 * narrower.atan());
+        info!("Framing the disk, out to {:.1} Rs", self.disk.outer_radius.in_rs(self.mass));
+    }
+
+    fn toggle_timeline(&mut self) {
+        let Some(timeline) = &self.timeline else {
+            info!("No camera timeline loaded, pass one with --timeline");
+            return;
+        };
+        // play a finished timeline again from the top
+        if !self.timeline_playing && !timeline.looping && self.timeline_time >= timeline.end() {
+            self.timeline_time = timeline.start();
+        }
+        self.timeline_playing = !self.timeline_playing;
+        info!("Timeline {} at {:.2} s", if self.timeline_playing { "playing" } else { "paused" }, self.timeline_time);
+    }
+
+    // Moves the camera along the playing timeline, stopping at the end unless it loops
+    fn advance_timeline(&mut self, dt: f64) {
+        let Some(timeline) = &self.timeline else {
+            return;
+        };
+        self.timeline_time += dt;
+        self.camera.apply_keyframe(&timeline.sample(self.timeline_time));
+        if !timeline.looping && self.timeline_time >= timeline.end() {
+            self.timeline_playing = false;
+            info!("Timeline finished");
+        }
+    }
+
+    /// Freezes the image on screen, or lets it go on. The camera still moves while paused, but the
+    /// view is only traced again on resuming or stepping; the accumulated samples are kept.
+    pub fn set_paused(&mut self, paused: bool) {
+        self.paused = paused;
+        self.step_pending = false;
+    }
+
+    fn toggle_pause(&mut self) {
+        self.set_paused(!self.paused);
+        info!("{}", if self.paused { "Paused, N advances one frame" } else { "Resumed" });
+    }
+
+    // Renders exactly one more frame and stays paused, pausing first if running
+    fn step_frame(&mut self) {
+        self.paused = true;
+        self.step_pending = true;
+    }
+
+    // Accumulates the current view until it has all its samples and reads back the tone mapped
+    // image, bottom row first
+    fn render_converged(&mut self) -> Vec<u8> {
+        self.update_camera();
+        while self.frame_index < self.sample_target() {
+            self.dispatch_compute();
+            // wait for each frame, queuing them all up front would trip the driver's watchdog
+            unsafe { gl::Finish(); }
+        }
+        self.apply_bloom();
+        self.apply_lens_flare();
+
+        // just the scene, without the letterbox bars
+        let [_, _, width, height] = self.scene_rect();
+        let mut pixels: Vec<u8> = vec![0; (width * height * 4) as usize];
+        let (width, height) = (width as GLsizei, height as GLsizei);
+        let mut fbo = 0;
+        let mut target = 0;
+        unsafe {
+            gl::GenTextures(1, &mut target);
+            gl::BindTexture(gl::TEXTURE_2D, target);
+            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::RGBA8 as GLint, width, height, 0, gl::RGBA, gl::UNSIGNED_BYTE, ptr::null());
+            gl::GenFramebuffers(1, &mut fbo);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, fbo);
+            gl::FramebufferTexture2D(gl::FRAMEBUFFER, gl::COLOR_ATTACHMENT0, gl::TEXTURE_2D, target, 0);
+            gl::Viewport(0, 0, width, height);
+            self.draw_fullscreen_quad();
+
+            gl::PixelStorei(gl::PACK_ALIGNMENT, 1);
+            gl::ReadPixels(0, 0, width, height, gl::RGBA, gl::UNSIGNED_BYTE, pixels.as_mut_ptr() as *mut c_void);
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.target_fbo);
+            gl::DeleteFramebuffers(1, &fbo);
+            gl::DeleteTextures(1, &target);
+        }
+        gl_check("reading back the render");
+        pixels
+    }
+
+    /// Hides everything drawn over the image, the grid, the ray paths, the overlay with the
+    /// crosshair and the panel, until turned off again, for clean captures.
+    pub fn set_photo_mode(&mut self, on: bool) {
+        self.photo_mode = on;
+    }
+
+    /// Saves a screenshot of the next frame with everything over the image hidden for that frame
+    /// alone, whether photo mode is on or not.
+    pub fn take_photo(&mut self, source: CaptureSource) {
+        self.pending_screenshot = Some(source);
+        self.photo_pending = true;
+    }
+
+    fn toggle_photo_mode(&mut self) {
+        self.set_photo_mode(!self.photo_mode);
+        info!("Photo mode turned {}", if self.photo_mode { "ON" } else { "OFF" });
+    }
+
+    // Whether this frame leaves out what is drawn over the image
+    fn hides_ui(&self) -> bool {
+        self.photo_mode || self.photo_pending
+    }
+
+    fn take_pending_screenshot(&mut self) {
+        // the frame just drawn is the one read back, a photo's UI comes back on the next
+        self.photo_pending = false;
+        if let Some(source) = self.pending_screenshot.take() {
+            let seconds = std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|d| d.as_secs()).unwrap_or(0);
+            let path = format!("screenshot_{}.png", seconds);
+            match self.screenshot(&path, source) {
+                Ok(()) => info!("Saved screenshot to {}", path),
+                Err(error) => error!("{}", error),
+            }
+        }
+    }
+
+    fn toggle_overlay(&mut self) {
+        self.show_overlay = !self.show_overlay;
+    }
+
+    fn draw_overlay(&mut self) {
+        self.queue_reference_circles();
+        self.queue_selection_marker();
+        self.queue_crosshair();
+        self.queue_split_divider();
+        self.queue_feedback();
+        if self.show_overlay {
+            self.queue_stats();
+            self.queue_scale_bar();
+        }
+        self.draw_ui();
+        // laid out in points, which the overlay's projection stretches over the framebuffer
+        let (ui_width, ui_height) = self.ui_size();
+        self.overlay.draw(ui_width, ui_height);
+    }
+
+    /// Queues the slider panel, when it's shown, with the current value of every parameter.
+    pub fn draw_ui(&mut self) {
+        if !self.panel.visible {
+            return;
+        }
+        let values: Vec<f64> = SLIDERS.iter().map(|slider| self.param(slider.param)).collect();
+        let (ui_width, _) = self.ui_size();
+        self.panel.queue(&mut self.overlay, ui_width, &values);
+    }
+
+    fn param(&self, param: Param) -> f64 {
+        match param {
+            Param::Mass => self.mass / SOLAR_MASS,
+            Param::Spin => self.spin,
+            Param::DiskInner => self.disk.inner_radius.in_rs(self.mass),
+            Param::DiskOuter => self.disk.outer_radius.in_rs(self.mass),
+            Param::DiskTemperature => self.disk_max_temperature as f64,
+            Param::Steps => self.max_steps as f64,
+            Param::Tolerance => self.tolerance as f64,
+            Param::AaSamples => self.aa_samples as f64,
+            Param::ResolutionScale => self.resolution_scale as f64,
+            Param::Exposure => self.exposure as f64,
+            Param::BloomThreshold => self.bloom_threshold as f64,
+            Param::BloomIntensity => self.bloom_intensity as f64,
+            Param::RedshiftExaggeration => self.redshift_exaggeration as f64,
+            Param::GridWarp => self.grid_warp as f64,
+        }
+    }
+
+    // Moves the active parameter `steps` steps, see `ParamCursor`
+    fn step_active_param(&mut self, steps: i32) {
+        let param = self.param_cursor.active().param;
+        self.set_param(param, self.param_cursor.step(self.param(param), steps));
+        self.notify(self.param_cursor.describe(self.param(param)));
+    }
+
+    fn cycle_active_param(&mut self, steps: i32) {
+        self.param_cursor.cycle(steps);
+        let param = self.param_cursor.active().param;
+        self.notify(format!("[ ] STEP {}", self.param_cursor.describe(self.param(param))));
+    }
+
+    // Logs a change made from the keyboard and shows it at the bottom of the window for a moment
+    fn notify(&mut self, message: String) {
+        info!("{}", message);
+        self.feedback = Some((message, self.now()));
+    }
+
+    fn queue_feedback(&mut self) {
+        const SHOWN_FOR: f64 = 2.0; // seconds
+        let Some((message, since)) = &self.feedback else {
+            return;
+        };
+        if self.now() - since > SHOWN_FOR {
+            self.feedback = None;
+            return;
+        }
+        let text = message.to_uppercase();
+        let scale = 2.0;
+        let (w, h) = TextOverlay::measure(&text, scale);
+        let (ui_width, ui_height) = self.ui_size();
+        let (x, y) = ((ui_width as f32 - w) / 2.0, ui_height as f32 - h - 48.0);
+        self.overlay.rect(x - 4.0, y - 4.0, w + 8.0, h + 4.0, [0.0, 0.0, 0.0, 0.5]);
+        self.overlay.text(x, y, scale, [1.0, 1.0, 1.0, 1.0], &text);
+    }
+
+    // Hands a slider's value to the setter of its parameter
+    fn set_param(&mut self, param: Param, value: f64) {
+        let rs = Length::rs(self.mass);
+        match param {
+            Param::Mass => self.set_mass(value * SOLAR_MASS),
+            Param::Spin => self.set_spin(value),
+            Param::DiskInner => self.set_disk_radii(rs * value, self.disk.outer_radius),
+            Param::DiskOuter => self.set_disk_radii(self.disk.inner_radius, rs * value),
+            Param::DiskTemperature => self.set_disk_max_temperature(value as f32),
+            Param::Steps => {
+                // keep the reach of the rays, only the step count changes
+                let reach = self.max_steps as f64 * self.step_size as f64;
+                self.set_integration_quality(value as i32, (reach / value) as f32);
+            }
+            Param::Tolerance => self.set_integration_tolerance(value as f32),
+            Param::AaSamples => self.set_aa_samples(value as u32),
+            Param::ResolutionScale => {
+                self.resolution_scale = value as f32;
+                self.apply_resolution_scale();
+            }
+            Param::Exposure => self.set_exposure(value as f32),
+            Param::BloomThreshold => self.set_bloom(value as f32, self.bloom_intensity),
+            Param::BloomIntensity => self.set_bloom(self.bloom_threshold, value as f32),
+            Param::RedshiftExaggeration => self.set_redshift_exaggeration(value as f32),
+            Param::GridWarp => self.set_grid_warp(value as f32),
+        }
+    }
+
+    // Seconds since the engine was made
+    fn now(&self) -> f64 {
+        self.clock.elapsed().as_secs_f64()
+    }
+
+    // The cursor in window coordinates, the top left corner without a window of the engine's own
+    fn cursor_pos(&self) -> (f64, f64) {
+        self.app_window.as_ref().map_or((0.0, 0.0), |app| app.window.get_cursor_pos())
+    }
+
+    // Asks the window to close, which ends `run` after the frame
+    fn close(&mut self) {
+        if let Some(app) = &mut self.app_window {
+            app.window.set_should_close(true);
+        }
+    }
+
+    // The cursor in framebuffer pixels, which differ from window coordinates on high DPI screens
+    fn cursor_pixels(&self, x: f64, y: f64) -> (f32, f32) {
+        let (w, h) = self.app_window.as_ref().map_or((self.win_width as i32, self.win_height as i32), |app| app.window.get_size());
+        ((x * self.win_width as f64 / w.max(1) as f64) as f32, (y * self.win_height as f64 / h.max(1) as f64) as f32)
+    }
+
+    // The cursor in points, the overlay's and panel's units and what camera drags are measured
+    // in, so a drag turns the view as far on any screen
+    fn cursor_points(&self, x: f64, y: f64) -> (f64, f64) {
+        let (px, py) = self.cursor_pixels(x, y);
+        (px as f64 / self.ui_scale as f64, py as f64 / self.ui_scale as f64)
+    }
+
+    // The framebuffer's size in points
+    fn ui_size(&self) -> (u32, u32) {
+        ((self.win_width as f32 / self.ui_scale).round() as u32, (self.win_height as f32 / self.ui_scale).round() as u32)
+    }
+
+    // The window moved to a screen with another content scale, or its scale setting changed
+    fn set_ui_scale(&mut self, scale: (f32, f32)) {
+        self.ui_scale = ui_scale(scale);
+        // the framebuffer size event doesn't follow everywhere, pick the new size up here
+        if let Some(app) = &self.app_window {
+            let (w, h) = app.window.get_framebuffer_size();
+            self.resize(w.max(0) as u32, h.max(0) as u32);
+        }
+        info!("Content scale {:.2}, framebuffer {}x{}", self.ui_scale, self.win_width, self.win_height);
+    }
+
+    // Gives the panel first go at mouse events, returns whether it took the event
+    fn panel_event(&mut self, event: &glfw::WindowEvent) -> bool {
+        // a hidden panel takes no clicks
+        if self.photo_mode {
+            return false;
+        }
+        let slider = match *event {
+            glfw::WindowEvent::MouseButton(glfw::MouseButtonLeft, Action::Press, _) => {
+                let (x, y) = self.cursor_pos();
+                let (x, y) = self.cursor_points(x, y);
+                let (ui_width, _) = self.ui_size();
+                if !self.panel.contains(ui_width, x as f32, y as f32) {
+                    return false;
+                }
+                self.panel.press(ui_width, x as f32, y as f32)
+            }
+            glfw::WindowEvent::MouseButton(glfw::MouseButtonLeft, Action::Release, _) => return self.panel.release(),
+            glfw::WindowEvent::CursorPos(x, y) if self.panel.dragging() => {
+                // the camera keeps track of the cursor so it doesn't jump on the next drag
+                let (x, y) = self.cursor_points(x, y);
+                self.camera.process_mouse_move(x, y);
+                self.panel.drag(self.ui_size().0, x as f32)
+            }
+            // releases go through, whatever was pressed outside lets go wherever the cursor is
+            glfw::WindowEvent::MouseButton(_, Action::Press, _) | glfw::WindowEvent::Scroll(_, _) => {
+                let (x, y) = self.cursor_pos();
+                let (x, y) = self.cursor_points(x, y);
+                return self.panel.contains(self.ui_size().0, x as f32, y as f32);
+            }
+            _ => return false,
+        };
+        if let Some((slider, fraction)) = slider {
+            self.set_param(slider.param, slider.value_at(fraction));
+        }
+        true
+    }
+
+    fn toggle_panel(&mut self) {
+        self.panel.visible = !self.panel.visible;
+        self.panel.release();
+    }
+
+    /// GPU time of a recent viewer frame split into the compute dispatch and the post-processing,
+    /// None before the first frame is in or when the context has no timestamp queries.
+    pub fn gpu_timings(&self) -> Option<GpuTimings> {
+        self.profiler.as_ref().and_then(FrameProfiler::latest)
+    }
+
+    fn queue_stats(&mut self) {
+        let gpu = match self.gpu_timings() {
+            Some(t) => format!("GPU {:.2} MS (COMPUTE {:.2}, POST {:.2})", t.total_ns as f64 * 1e-6, t.compute_ns as f64 * 1e-6, t.post_ns as f64 * 1e-6),
+            None => "GPU TIMING N/A".to_string(),
+        };
+        // every accumulated frame traces a block of rays per pixel, count them all
+        let supersampled = match self.supersampling {
+            1 => String::new(),
+            factor => format!(" ({}X{} SUPERSAMPLED)", factor, factor),
+        };
+        let tiles = if self.tiled {
+            format!(", TILE {}/{}", self.tile_index + 1, tile_count(self.tile_size, self.compute_width as u32, self.compute_height as u32))
+        } else {
+            String::new()
+        };
+        let dynamic = self.dynamic_resolution.map_or(String::new(), |controller| format!(" (DYNAMIC, {} FPS)", controller.target_fps));
+        let channel = match self.render_channel {
+            RenderChannel::Color => "",
+            RenderChannel::DeflectionAngle => "\nCHANNEL DEFLECTION (0-360 DEG)",
+            RenderChannel::Redshift => "\nCHANNEL REDSHIFT (G 0-2)",
+            RenderChannel::TravelTime => "\nCHANNEL TRAVEL TIME",
+            RenderChannel::Termination => "\nCHANNEL TERMINATION (HORIZON, ESCAPED, HIT, OUT OF STEPS)",
+        };
+        let selection = match (self.selected_object, self.selected()) {
+            (Some(index), Some(object)) => format!("\nSELECTED {}: {:.2E} SOLAR MASSES, COLOR {:.1} {:.1} {:.1}",
+                index + 1, object.mass / SOLAR_MASS, object.color.x, object.color.y, object.color.z),
+            _ => String::new(),
+        };
+        let rs = self.schwarzschild_radius();
+        let particle = match (self.particle, self.particle_apsides.last_orbit(), self.particle_precession_deg()) {
+            (Some(_), Some(orbit), Some(precession)) => format!("\nPARTICLE {:.2}-{:.2} RS, PRECESSION {:.3} DEG/ORBIT (GR {:.3})",
+                orbit.periapsis / rs, orbit.apoapsis / rs, precession, orbit.predicted_precession(rs).to_degrees()),
+            (Some(particle), _, _) => format!("\nPARTICLE AT {:.2} RS", particle.radius() / rs),
+            _ => String::new(),
+        };
+        let dilation = self.camera.time_dilation_factor(self.mass);
+        let clocks = if dilation > 0.0 {
+            format!("CLOCKS INFINITY {:.1} S, CAMERA {:.1} S (X{:.4})", self.coordinate_time, self.camera_proper_time, dilation)
+        } else {
+            format!("CLOCKS INFINITY {:.1} S, CAMERA {:.1} S (STOPPED, INSIDE THE HORIZON)", self.coordinate_time, self.camera_proper_time)
+        };
+        let text = format!("{}FPS {:.1} ({:.2} MS, VSYNC {})\n{}\nCOMPUTE {}X{}{}, SHADER {}\nGRAVITY {}\nMASS {:.2E} SOLAR MASSES\nCAMERA {} {:.2} RS ({:.3} AU), FOV {:.0} DEG\nDISK {} {:.2}-{:.2} RS\nSPIN {:.3}\nQUALITY {:?} ({} STEPS, {:?})\nDOPPLER {}\nREDSHIFT {} (X{})\nBLOOM {}{}\nEXPOSURE {:+.1} EV\nACCUMULATED {}/{} SAMPLES{}{}\nOBJECTS {}/{}{}{}{}\n{}{}",
+            if self.paused { "PAUSED (N STEPS)\n" } else { "" }, self.fps, self.frame_ms, if self.vsync { "ON" } else { "OFF" }, gpu, self.compute_width, self.compute_height, dynamic,
+            self.compute_variants[self.compute_variant].name,
+            if self.gravity_enabled { "ON" } else { "OFF" }, self.mass / SOLAR_MASS,
+            if self.camera.mode == CameraMode::Fly { "FLY" } else { "ORBIT" }, self.to_rs(self.camera.distance()),
+            Length::meters(self.camera.distance()).in_au(), self.camera.fov_degrees,
+            format!("{:?}", self.disk_model).to_uppercase(), self.disk.inner_radius.in_rs(self.mass), self.disk.outer_radius.in_rs(self.mass), self.spin, self.quality, self.max_steps, self.integration_mode,
+            if self.doppler_enabled { "ON" } else { "OFF" },
+            if self.redshift_enabled { "ON" } else { "OFF" }, self.redshift_exaggeration,
+            if self.bloom_enabled { "ON" } else { "OFF" }, if self.lens_flare_enabled { ", LENS FLARE" } else { "" }, self.exposure,
+            self.frame_index * self.supersampling.pow(2), self.sample_target() * self.supersampling.pow(2), supersampled, tiles,
+            self.objects.len(), MAX_OBJECTS, if self.placing_objects { " (PLACING)" } else { "" }, selection, particle, clocks, channel);
+        let scale = 2.0;
+        let (w, h) = TextOverlay::measure(&text, scale);
+        self.overlay.rect(4.0, 4.0, w + 8.0, h + 4.0, [0.0, 0.0, 0.0, 0.5]);
+        self.overlay.text(8.0, 8.0, scale, [1.0, 1.0, 1.0, 1.0], &text);
+    }
+
+    // A bar in the bottom left corner spanning a round number of Rs at the distance of the target
+    fn queue_scale_bar(&mut self) {
+        let tan_half_fov = self.camera.tan_half_fov() as f64;
+        let points_per_rs = self.scene_rect()[3] as f64 / self.ui_scale as f64 / (2.0 * tan_half_fov * self.to_rs(self.camera.distance()));
+        let length = nice_length(160.0 / points_per_rs);
+        let (x, y, w) = (16.0, self.ui_size().1 as f32 - 16.0, (length * points_per_rs) as f32);
+        let color = [1.0, 1.0, 1.0, 0.9];
+        self.overlay.line(x, y, x + w, y, 2.0, color);
+        self.overlay.line(x, y - 5.0, x, y + 1.0, 2.0, color);
+        self.overlay.line(x + w, y - 5.0, x + w, y + 1.0, 2.0, color);
+        self.overlay.text(x, y - 24.0, 2.0, color, &format!("{} RS", length));
+    }
+
+    // A ring around where the selected object's center projects to, labelled with its number
+    fn queue_selection_marker(&mut self) {
+        const SEGMENTS: usize = 32;
+        const RADIUS: f32 = 12.0;
+        let Some(index) = self.selected_object.filter(|&index| index < self.objects.len()) else { return };
+        let [left, top, width, height] = self.scene_rect().map(|v| v as f32 / self.ui_scale);
+        let Some((fx, fy)) = self.camera.project(self.objects[index].pos, self.scene_aspect()) else { return };
+        let (x, y) = (left + fx as f32 * width, top + fy as f32 * height);
+        let color = [1.0, 1.0, 1.0, 0.9];
+        for i in 0..SEGMENTS {
+            let [a0, a1] = [i, i + 1].map(|i| i as f32 / SEGMENTS as f32 * std::f32::consts::TAU);
+            self.overlay.line(x + RADIUS * a0.cos(), y + RADIUS * a0.sin(), x + RADIUS * a1.cos(), y + RADIUS * a1.sin(), 2.0, color);
+        }
+        self.overlay.text(x + RADIUS + 4.0, y - 6.0, 2.0, color, &format!("OBJECT {}", index + 1));
+    }
+
+    // The prograde photon orbit and ISCO, the ones switched on, as circles in the disk plane projected
+    // with straight rays: they show where the orbits are, not where gravity lenses their image to
+    fn queue_reference_circles(&mut self) {
+        const SEGMENTS: usize = 96;
+        let aspect = self.scene_aspect();
+        // the scene's place in the window, in points
+        let [left, top, width, height] = self.scene_rect().map(|v| v as f32 / self.ui_scale);
+        let rs = self.schwarzschild_radius();
+        let circles = [
+            // the label offsets keep the two apart where the circles run close
+            (self.show_photon_orbit, photon_orbit_radius(self.spin), "PHOTON ORBIT", [1.0, 0.5, 0.2, 0.9], -18.0),
+            (self.show_isco, isco_radius(self.spin), "ISCO", [0.3, 0.8, 1.0, 0.9], 4.0),
+        ];
+        for (_, radius, name, color, label_offset) in circles.into_iter().filter(|circle| circle.0) {
+            let points: Vec<Option<(f32, f32)>> = (0..=SEGMENTS).map(|i| {
+                let angle = i as f64 / SEGMENTS as f64 * std::f64::consts::TAU;
+                let point = vec3((radius * rs * angle.cos()) as f32, 0.0, (radius * rs * angle.sin()) as f32);
+                self.camera.project(point, aspect).map(|(fx, fy)| (left + fx as f32 * width, top + fy as f32 * height))
+            }).collect();
+            for pair in points.windows(2) {
+                if let [Some((x0, y0)), Some((x1, y1))] = *pair {
+                    self.overlay.line(x0, y0, x1, y1, 2.0, color);
+                }
+            }
+            // label the rightmost point of the circle
+            if let Some((x, y)) = points.iter().flatten().copied().max_by(|a, b| a.0.total_cmp(&b.0)) {
+                self.overlay.text(x + 6.0, y + label_offset, 2.0, color, &format!("{} {:.2} RS", name, radius));
+            }
+        }
+    }
+
+    // Drawn in the overlay at window resolution after the blit, so the marks stay sharp however
+    // far the compute resolution is scaled down
+    fn queue_crosshair(&mut self) {
+        const ARM: f32 = 10.0;
+        const GAP: f32 = 3.0;
+        let [left, top, width, height] = self.scene_rect().map(|v| v as f32 / self.ui_scale);
+        if self.show_crosshair {
+            let (x, y) = (left + width / 2.0, top + height / 2.0);
+            // a dark line under the light one keeps it visible against the disk and the sky alike
+            for (line_width, color) in [(3.0, [0.0, 0.0, 0.0, 0.5]), (1.0, [1.0, 1.0, 1.0, 0.9])] {
+                for (dx, dy) in [(1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0)] {
+                    self.overlay.smooth_line(x + dx * GAP, y + dy * GAP, x + dx * (GAP + ARM), y + dy * (GAP + ARM), line_width, color);
+                }
+            }
+        }
+        if self.show_center_marker {
+            // the same projection the reference circles go through, from the basis the camera
+            // block is uploaded with
+            let Some((fx, fy)) = self.camera.project(vec3(0.0, 0.0, 0.0), self.scene_aspect()) else {
+                return;
+            };
+            if !(0.0..=1.0).contains(&fx) || !(0.0..=1.0).contains(&fy) {
+                return;
+            }
+            let (x, y) = (left + fx as f32 * width, top + fy as f32 * height);
+            let color = [1.0, 0.3, 0.8, 0.9];
+            let corners = [(x + 6.0, y), (x, y + 6.0), (x - 6.0, y), (x, y - 6.0), (x + 6.0, y)];
+            for pair in corners.windows(2) {
+                self.overlay.smooth_line(pair[0].0, pair[0].1, pair[1].0, pair[1].1, 1.0, color);
+            }
+        }
+    }
+
+    /// Shows or hides the crosshair at the middle of the view.
+    pub fn set_crosshair(&mut self, on: bool) {
+        self.show_crosshair = on;
+        info!("Crosshair turned {}", if on { "ON" } else { "OFF" });
+    }
+
+    fn toggle_center_marker(&mut self) {
+        self.show_center_marker = !self.show_center_marker;
+        info!("Black hole center marker turned {}", if self.show_center_marker { "ON" } else { "OFF" });
+    }
+
+    fn toggle_photon_orbit(&mut self) {
+        self.show_photon_orbit = !self.show_photon_orbit;
+        info!("Photon orbit circle turned {}", if self.show_photon_orbit { "ON" } else { "OFF" });
+    }
+
+    fn toggle_isco(&mut self) {
+        self.show_isco = !self.show_isco;
+        info!("ISCO circle turned {}", if self.show_isco { "ON" } else { "OFF" });
+    }
+
+    /// Drops the accumulated frames, the next dispatch starts the average over. Called whenever
+    /// anything the compute shader reads changes.
+    fn reset_accumulation(&mut self) {
+        self.frame_index = 0;
+        self.tile_index = 0;
+    }
+
+    /// Spreads each frame of the compute pass over several: every frame traces just the next
+    /// `tile_size` square, from the top left, so however large the compute resolution a frame's
+    /// work stays bounded and the window keeps responding. A whole frame takes `tile_count`
+    /// frames, the rest of the image shows the previous one meanwhile, and any change, a camera
+    /// move included, starts again at the top left.
+    pub fn set_tiled(&mut self, on: bool) {
+        self.tiled = on;
+        self.reset_accumulation();
+    }
+
+    fn toggle_tiled(&mut self) {
+        self.set_tiled(!self.tiled);
+        info!("Tiled tracing turned {} ({} tiles of {} px)", if self.tiled { "ON" } else { "OFF" },
+            tile_count(self.tile_size, self.compute_width as u32, self.compute_height as u32), self.tile_size);
+    }
+
+    /// Sets how many frames, each with its rays offset by a different sub-pixel jitter, are
+    /// averaged while the view stays still, clamped to [1, MAX_AA_SAMPLES]. Once they are in
+    /// the image is converged and the compute pass rests until something changes.
+    pub fn set_aa_samples(&mut self, n: u32) {
+        self.aa_samples = n.clamp(1, MAX_AA_SAMPLES);
+        self.reset_accumulation();
+    }
+
+    /// Keeps sampling just the pixels where the image has an edge once the `set_aa_samples`
+    /// frames are in: the ones whose 3x3 neighbourhood spans more than `threshold` in luminance
+    /// compressed to [0, 1) as L / (1 + L), such as the photon ring and the rims of the disk,
+    /// get `extra_samples` more jittered frames while smooth sky and disk are left as they are.
+    /// The edges are found once, on the converged uniform samples. 0 extra samples turns it off.
+    pub fn set_adaptive_aa(&mut self, threshold: f32, extra_samples: u32) {
+        self.edge_threshold = threshold.max(0.0);
+        self.edge_samples = extra_samples.min(MAX_AA_SAMPLES);
+        self.reset_accumulation();
+    }
+
+    // Frames the image takes to converge, the uniform ones and then the edges' extra ones
+    fn sample_target(&self) -> u32 {
+        self.aa_samples + self.edge_samples
+    }
+
+    // Marks the pixels that go on being sampled in edge_texture, from the image accumulated so far
+    fn detect_edges(&self) {
+        unsafe {
+            let location = |name: &str| {
+                let name = CString::new(name).unwrap();
+                gl::GetUniformLocation(self.compute_program, name.as_ptr())
+            };
+            gl::Uniform1f(location("edge_threshold"), self.edge_threshold);
+            gl::Uniform1i(location("edge_pass"), 1);
+            self.dispatch_rect([0, 0, self.compute_width, self.compute_height]);
+            gl::Uniform1i(location("edge_pass"), 0);
+            gl::MemoryBarrier(gl::SHADER_IMAGE_ACCESS_BARRIER_BIT);
+        }
+    }
+
+    // Sub-pixel offset of the rays in the current frame, in [-0.5, 0.5) pixels. The first frame after
+    // a change goes through the pixel centers, so nothing shimmers while the camera moves.
+    fn jitter(&self) -> (f32, f32) {
+        if self.frame_index == 0 {
+            return (0.0, 0.0);
+        }
+        let index = self.frame_index.wrapping_add(fold_seed(self.seed));
+        (halton(index, 2) - 0.5, halton(index, 3) - 0.5)
+    }
+
+    fn dispatch_compute(&mut self) {
+        if self.frame_index >= self.sample_target() {
+            return; // converged, the textures still hold the finished image
+        }
+        self.bind_compute_inputs();
+        // past the uniform samples only the edges are traced, the rest keep their mean
+        let edges_only = self.frame_index >= self.aa_samples;
+        if edges_only && self.frame_index == self.aa_samples && self.tile_index == 0 {
+            self.detect_edges();
+        }
+        unsafe {
+            let name = CString::new("frame_index").unwrap();
+            gl::Uniform1i(gl::GetUniformLocation(self.compute_program, name.as_ptr()), self.frame_index as GLint);
+            let (jitter_x, jitter_y) = self.jitter();
+            let name = CString::new("jitter").unwrap();
+            gl::Uniform2f(gl::GetUniformLocation(self.compute_program, name.as_ptr()), jitter_x, jitter_y);
+            let name = CString::new("resample_edges").unwrap();
+            gl::Uniform1i(gl::GetUniformLocation(self.compute_program, name.as_ptr()), edges_only as GLint);
+
+            let (width, height) = (self.compute_width as u32, self.compute_height as u32);
+            let tile = if self.tiled {
+                tile_rect(self.tile_index, self.tile_size, width, height)
+            } else {
+                [0, 0, width as i32, height as i32]
+            };
+            match self.split {
+                None => self.dispatch_rect(tile),
+                Some(sides) => {
+                    // each half under its own blocks, then the engine's own back for everything else that reads them
+                    for (side, rect) in sides.iter().zip(split_rect(tile, self.split_column())) {
+                        if rect[0] < rect[2] {
+                            self.upload_split_side(side);
+                            self.dispatch_rect(rect);
+                        }
+                    }
+                    ubo::upload(self.black_hole_ubo, &self.black_hole_block());
+                    ubo::upload(self.disk_ubo, &self.disk_block());
+                    ubo::upload(self.integrator_ubo, &self.integrator_block());
+                }
+            }
+
+            // make the image writes visible to the texture fetch in the fullscreen pass
+            gl::MemoryBarrier(gl::SHADER_IMAGE_ACCESS_BARRIER_BIT | gl::TEXTURE_FETCH_BARRIER_BIT);
+        }
+        gl_check("dispatching the compute shader");
+        if self.tiled {
+            // the frame is only in once its last tile is
+            self.tile_index += 1;
+            if self.tile_index < tile_count(self.tile_size, self.compute_width as u32, self.compute_height as u32) {
+                return;
+            }
+            self.tile_index = 0;
+        }
+        self.frame_index += 1;
+    }
+
+    // Traces the pixels of `rect`, [x0, y0, x1, y1) of the compute image
+    fn dispatch_rect(&self, rect: [i32; 4]) {
+        unsafe {
+            let name = CString::new("tile").unwrap();
+            gl::Uniform4i(gl::GetUniformLocation(self.compute_program, name.as_ptr()), rect[0], rect[1], rect[2], rect[3]);
+            // one work group covers LOCAL_SIZE x LOCAL_SIZE pixels, round up so the edges are covered too
+            let groups_x = ((rect[2] - rect[0]) as u32).div_ceil(LOCAL_SIZE);
+            let groups_y = ((rect[3] - rect[1]) as u32).div_ceil(LOCAL_SIZE);
+            gl::DispatchCompute(groups_x, groups_y, 1);
+        }
+    }
+
+    /// Splits the view into two halves side by side, the left one rendered with `left` and the
+    /// right one with `right`, from the same camera. Each half traces only its own pixels, so a
+    /// frame costs about what an unsplit one does and each half refreshes at that rate. The spin
+    /// is clamped to [0, MAX_SPIN] and left at 0 in the interior view, the step count to
+    /// [1, MAX_INTEGRATION_STEPS]. While the split is on, these settings of the engine itself
+    /// only show in the overlay and in what reads the blocks outside the compute pass.
+    pub fn set_split(&mut self, left: SplitParams, right: SplitParams) {
+        let clamp = |side: SplitParams| SplitParams {
+            spin: side.spin.clamp(0.0, MAX_SPIN),
+            max_steps: side.max_steps.clamp(1, MAX_INTEGRATION_STEPS),
+            ..side
+        };
+        self.split = Some([clamp(left), clamp(right)]);
+        self.reset_accumulation();
+    }
+
+    /// Goes back to a single view with the engine's own settings.
+    pub fn clear_split(&mut self) {
+        self.split = None;
+        self.dragging_split = false;
+        self.reset_accumulation();
+    }
+
+    /// Moves the divider of the split to `fraction` of the scene's width from the left, clamped
+    /// to [0, 1].
+    pub fn set_split_position(&mut self, fraction: f32) {
+        self.split_position = fraction.clamp(0.0, 1.0);
+        self.reset_accumulation();
+    }
+
+    // The engine's current settings as one side of the split
+    fn split_params(&self) -> SplitParams {
+        SplitParams {
+            spin: self.spin,
+            doppler: self.doppler_enabled,
+            redshift: self.redshift_enabled,
+            max_steps: self.max_steps,
+            integration_mode: self.integration_mode,
+        }
+    }
+
+    // Compares the current settings without spin on the left to spinning on the right: at the
+    // current spin, or at 0.9 when there is none
+    fn toggle_split(&mut self) {
+        if self.split.is_some() {
+            self.clear_split();
+            info!("Split view turned OFF");
+            return;
+        }
+        let current = self.split_params();
+        let spin = if self.spin > 0.0 { self.spin } else { 0.9 };
+        self.set_split(SplitParams { spin: 0.0, ..current }, SplitParams { spin, ..current });
+        info!("Split view turned ON: spin 0 on the left, {:.3} on the right", spin);
+    }
+
+    // The compute image column the divider sits on, the first one of the right half
+    fn split_column(&self) -> i32 {
+        (self.split_position * self.compute_width as f32).round() as i32
+    }
+
+    // Uploads the blocks the compute pass reads with one side's settings in place of the engine's
+    fn upload_split_side(&self, side: &SplitParams) {
+        let interior = self.observer_region == ObserverRegion::Interior;
+        ubo::upload(self.black_hole_ubo, &BlackHoleUbo {
+            // the interior view has no rotating metric
+            spin: if interior { 0.0 } else { side.spin as GLfloat },
+            redshift_enabled: side.redshift as i32,
+            ..self.black_hole_block()
+        });
+        ubo::upload(self.disk_ubo, &DiskUbo { doppler_enabled: side.doppler as i32, ..self.disk_block() });
+        ubo::upload(self.integrator_ubo, &IntegratorUbo {
+            max_steps: side.max_steps,
+            integration_mode: side.integration_mode as GLint,
+            ..self.integrator_block()
+        });
+    }
+
+    // Whether the cursor is close enough to the divider to grab it
+    fn cursor_over_split_divider(&self) -> bool {
+        if self.split.is_none() {
+            return false;
+        }
+        let (x, y) = self.cursor_pos();
+        let (fx, fy) = self.cursor_in_scene(x, y);
+        let [_, _, width, _] = self.scene_rect();
+        let divider = self.split_column() as f64 / self.compute_width as f64;
+        // 6 points either side
+        (0.0..=1.0).contains(&fy) && (fx - divider).abs() * width as f64 <= 6.0 * self.ui_scale as f64
+    }
+
+    // The divider on the texel boundary the halves meet at, drawn at window resolution so it
+    // stays a sharp line however far the compute resolution is scaled down
+    fn queue_split_divider(&mut self) {
+        if self.split.is_none() {
+            return;
+        }
+        let [left, top, width, height] = self.scene_rect().map(|v| v as f32 / self.ui_scale);
+        let x = left + self.split_column() as f32 / self.compute_width as f32 * width;
+        for (line_width, color) in [(3.0, [0.0, 0.0, 0.0, 0.5]), (1.0, [1.0, 1.0, 1.0, 0.9])] {
+            self.overlay.smooth_line(x, top, x, top + height, line_width, color);
+        }
+        let color = [1.0, 1.0, 1.0, 0.9];
+        self.overlay.text(x - 24.0, top + 8.0, 2.0, color, "A");
+        self.overlay.text(x + 14.0, top + 8.0, 2.0, color, "B");
+    }
+
+    // Makes the compute program current with its images and textures bound
+    fn bind_compute_inputs(&self) {
+        unsafe {
+            gl::UseProgram(self.compute_program);
+            // the running mean is read and written in place on image unit 1
+            gl::BindImageTexture(1, self.accum_texture, 0, gl::FALSE, 0, gl::READ_WRITE, gl::RGBA32F);
+            // bind the output image as image unit 0 (binding = 0 in the compute shader)
+            gl::BindImageTexture(0, self.texture, 0, gl::FALSE, 0, gl::WRITE_ONLY, gl::RGBA16F);
+            // the adaptive AA's edge mask on image unit 2, every pixel is traced unless a
+            // dispatch asks for the edges alone
+            gl::BindImageTexture(2, self.edge_texture, 0, gl::FALSE, 0, gl::READ_WRITE, gl::R8);
+            let name = CString::new("resample_edges").unwrap();
+            gl::Uniform1i(gl::GetUniformLocation(self.compute_program, name.as_ptr()), 0);
+            // background panorama on texture unit 1 (binding = 1), unit 0 is the quad's
+            gl::ActiveTexture(gl::TEXTURE1);
+            gl::BindTexture(gl::TEXTURE_2D, self.background);
+            // and the disk's emission on unit 2 (binding = 2)
+            gl::ActiveTexture(gl::TEXTURE2);
+            gl::BindTexture(gl::TEXTURE_2D, self.disk_texture);
+            // and the false color channels' colormap on unit 3 (binding = 3)
+            gl::ActiveTexture(gl::TEXTURE3);
+            gl::BindTexture(gl::TEXTURE_2D, self.colormap_texture);
+            let name = CString::new("render_channel").unwrap();
+            gl::Uniform1i(gl::GetUniformLocation(self.compute_program, name.as_ptr()), self.render_channel as GLint);
+            let name = CString::new("max_disk_crossings").unwrap();
+            gl::Uniform1i(gl::GetUniformLocation(self.compute_program, name.as_ptr()), self.max_image_order as GLint);
+        }
+    }
+
+    /// Traces the ray through the center of compute pixel `pixel`, counted from the top left and
+    /// clamped to the image, once more on the GPU with the current view and integrator, and
+    /// writes the point after every step as CSV of step, x, y, z in meters and the affine
+    /// parameter to `path`. It is the path the frame's ray for that pixel takes, bent exactly as
+    /// the image shows it, and leaves the image as it is.
+    pub fn export_geodesic(&self, pixel: (i32, i32), path: &str) -> Result<(), EngineError> {
+        let (width, height) = (self.compute_width, self.compute_height);
+        // the shader counts rows from the bottom
+        let (x, y) = (pixel.0.clamp(0, width - 1), height - 1 - pixel.1.clamp(0, height - 1));
+        self.bind_compute_inputs();
+        let mut count: GLint = 0;
+        unsafe {
+            gl::BindBuffer(gl::SHADER_STORAGE_BUFFER, self.debug_ray_ssbo);
+            gl::BufferSubData(gl::SHADER_STORAGE_BUFFER, 0, mem::size_of::<GLint>() as GLsizeiptr, &count as *const GLint as *const c_void);
+            let name = CString::new("jitter").unwrap();
+            gl::Uniform2f(gl::GetUniformLocation(self.compute_program, name.as_ptr()), 0.0, 0.0);
+            let name = CString::new("tile").unwrap();
+            gl::Uniform4i(gl::GetUniformLocation(self.compute_program, name.as_ptr()), x, y, x + 1, y + 1);
+            let debug_ray = CString::new("debug_ray").unwrap();
+            let debug_ray = gl::GetUniformLocation(self.compute_program, debug_ray.as_ptr());
+            gl::Uniform1i(debug_ray, y * width + x);
+            gl::DispatchCompute(1, 1, 1);
+            gl::Uniform1i(debug_ray, -1);
+            gl::MemoryBarrier(gl::BUFFER_UPDATE_BARRIER_BIT);
+            gl::GetBufferSubData(gl::SHADER_STORAGE_BUFFER, 0, mem::size_of::<GLint>() as GLsizeiptr, &mut count as *mut GLint as *mut c_void);
+        }
+        let mut points = vec![[0.0f32; 4]; count.max(0) as usize];
+        unsafe {
+            gl::GetBufferSubData(gl::SHADER_STORAGE_BUFFER, 16, mem::size_of_val(points.as_slice()) as GLsizeiptr, points.as_mut_ptr() as *mut c_void);
+        }
+        gl_check("reading back the debug ray");
+        std::fs::write(path, geodesic::to_csv(&points)).map_err(|source| EngineError::Io { path: path.to_string(), source })
+    }
+
+    // Exports the ray under the cursor to GEODESIC_CSV_PATH
+    fn export_geodesic_under_cursor(&self) {
+        let (x, y) = self.cursor_pos();
+        let (fx, fy) = self.cursor_in_scene(x, y);
+        let pixel = ((fx * self.compute_width as f64) as i32, (fy * self.compute_height as f64) as i32);
+        match self.export_geodesic(pixel, GEODESIC_CSV_PATH) {
+            Ok(()) => info!("Exported the geodesic through pixel {:?} to {}", pixel, GEODESIC_CSV_PATH),
+            Err(error) => error!("{}", error),
+        }
+    }
+
+    /// The scene as it is now, in the form a scene file loads: the camera's viewpoint (an orbit
+    /// around the point a flying camera looks at), the black hole, the disk, the integrator, the
+    /// image settings, the sky and the objects as placed and edited. The window and compute sizes and the compute variants are the
+    /// ones the scene was loaded with.
+    pub fn scene(&self) -> Config {
+        let view = self.camera.bookmark();
+        let mut scene = self.defaults.clone();
+        scene.camera_azimuth = view.azimuth;
+        scene.camera_elevation = view.elevation;
+        scene.camera_radius = view.radius;
+        scene.camera_target = view.target;
+        scene.camera_min_radius = self.camera.min_radius;
+        scene.camera_max_radius = self.camera.max_radius;
+        scene.camera_sensitivity = self.camera.sensitivity;
+        scene.camera_transition_time = self.camera.transition_time;
+        scene.camera_fov_degrees = self.camera.fov_degrees;
+        scene.disk_inner_radius = self.disk.inner_radius;
+        scene.disk_outer_radius = self.disk.outer_radius;
+        scene.disk_thickness = self.disk.thickness as f64;
+        scene.disk_model = self.disk_model;
+        scene.disk_inclination = self.disk_inclination;
+        scene.disk_azimuth = self.disk_azimuth;
+        scene.disk_density_scale = self.disk_density_scale;
+        scene.disk_emission_scale = self.disk_emission_scale;
+        scene.disk_texture = self.disk_texture_path.clone();
+        // a generated temperature ramp isn't a texture the scene names, loading sets the profile again
+        scene.disk_profile = if scene.disk_texture.is_some() { self.disk_profile } else { DiskProfile::Gradient };
+        scene.disk_max_temperature = self.disk_max_temperature;
+        scene.disk_peak_temperature = self.disk_peak_temperature;
+        scene.black_hole_mass = self.mass;
+        scene.black_hole_spin = self.spin;
+        scene.quality = self.quality;
+        scene.integration_steps = Some(self.max_steps);
+        scene.integration_step_size = Some(self.step_size);
+        scene.integration_mode = self.integration_mode;
+        scene.integration_tolerance = self.tolerance;
+        scene.horizon_epsilon = self.horizon_epsilon;
+        scene.exposure = self.exposure;
+        scene.bloom = self.bloom_enabled;
+        scene.bloom_threshold = self.bloom_threshold;
+        scene.bloom_intensity = self.bloom_intensity;
+        scene.lens_flare = self.lens_flare_enabled;
+        scene.lens_flare_intensity = self.lens_flare_intensity;
+        scene.edge_threshold = self.edge_threshold;
+        scene.edge_samples = self.edge_samples;
+        scene.gravity = self.gravity_enabled;
+        scene.seed = self.seed;
+        scene.fixed_timestep = self.fixed_timestep;
+        scene.starfield = self.starfield_enabled;
+        scene.star_density = self.star_density;
+        scene.star_brightness = self.star_brightness;
+        scene.star_seed = self.star_seed;
+        scene.background = self.background_path.clone();
+        scene.background_color = self.background_color;
+        scene.objects = self.objects.clone();
+        scene
+    }
+
+    /// Saves `scene()` to `path` as a TOML scene file, which `--scene` loads back into this view.
+    pub fn export_scene(&self, path: &str) -> Result<(), EngineError> {
+        std::fs::write(path, self.scene().to_toml()).map_err(|source| EngineError::Io { path: path.to_string(), source })
+    }
+
+    // Saves the scene to SCENE_EXPORT_PATH
+    fn export_scene_to_file(&self) {
+        match self.export_scene(SCENE_EXPORT_PATH) {
+            Ok(()) => info!("Saved the scene to {}", SCENE_EXPORT_PATH),
+            Err(error) => error!("{}", error),
+        }
+    }
+
+    /// Makes pixels brighter than `threshold` glow: their excess luminance is blurred and added
+    /// back on top of the image, scaled by `intensity`.
+    pub fn set_bloom(&mut self, threshold: f32, intensity: f32) {
+        self.bloom_threshold = threshold.max(0.0);
+        self.bloom_intensity = intensity.max(0.0);
+    }
+
+    // Extracts the bright pixels of the compute output into bloom_textures[0] and blurs them
+    // there, ping-ponging through bloom_textures[1]
+    fn apply_bloom(&self) {
+        if !self.bloom_enabled {
+            return;
+        }
+        unsafe {
+            let location = |name: &str| {
+                let name = CString::new(name).unwrap();
+                gl::GetUniformLocation(self.bloom_program, name.as_ptr())
+            };
+            gl::UseProgram(self.bloom_program);
+            gl::BindVertexArray(self.quad_vao);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::Viewport(0, 0, self.compute_width, self.compute_height);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::Uniform1i(location("source"), 0);
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.bloom_fbos[0]);
+            gl::BindTexture(gl::TEXTURE_2D, self.texture);
+            gl::Uniform1i(location("pass"), 0);
+            gl::Uniform1f(location("threshold"), self.bloom_threshold);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+
+            gl::Uniform1i(location("pass"), 1);
+            // taps a supersampled block apart, so the glow spans as many output pixels whatever the factor
+            let spacing = self.supersampling as f32;
+            let texel = (spacing / self.compute_width as f32, spacing / self.compute_height as f32);
+            for _ in 0..BLOOM_BLUR_PASSES {
+                // horizontally into the second target, then vertically back into the first
+                for (target, direction) in [(1, (texel.0, 0.0)), (0, (0.0, texel.1))] {
+                    gl::BindFramebuffer(gl::FRAMEBUFFER, self.bloom_fbos[target]);
+                    gl::BindTexture(gl::TEXTURE_2D, self.bloom_textures[1 - target]);
+                    gl::Uniform2f(location("direction"), direction.0, direction.1);
+                    gl::DrawArrays(gl::TRIANGLES, 0, 6);
+                }
+            }
+
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.target_fbo);
+            gl::Viewport(0, 0, self.win_width as GLsizei, self.win_height as GLsizei);
+        }
+        gl_check("applying bloom");
+    }
+
+    /// Scales the HDR radiance by 2^`ev` ahead of the tone mapping, clamped to
+    /// [MIN_EXPOSURE, MAX_EXPOSURE].
+    pub fn set_exposure(&mut self, ev: f32) {
+        self.exposure = ev.clamp(MIN_EXPOSURE, MAX_EXPOSURE);
+    }
+
+    /// Strings ghosts of the brightest spot of the HDR image along the line through the center,
+    /// a camera lens artifact for a stylized look. They grow with how far the spot's luminance
+    /// goes past 1, scaled by `intensity`; dimmer images have none.
+    pub fn set_lens_flare(&mut self, intensity: f32) {
+        self.lens_flare_intensity = intensity.max(0.0);
+    }
+
+    // Finds the brightest spot of the compute output for the lens flare: the brightest of each
+    // region into flare_textures[0], then the brightest of those into the 1x1 flare_textures[1]
+    fn apply_lens_flare(&self) {
+        if !self.lens_flare_enabled {
+            return;
+        }
+        unsafe {
+            let location = |name: &str| {
+                let name = CString::new(name).unwrap();
+                gl::GetUniformLocation(self.flare_program, name.as_ptr())
+            };
+            gl::UseProgram(self.flare_program);
+            gl::BindVertexArray(self.quad_vao);
+            gl::Disable(gl::DEPTH_TEST);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::Uniform1i(location("source"), 0);
+            gl::Uniform1i(location("regions"), LENS_FLARE_REGIONS);
+            for (pass, size, source) in [(0, LENS_FLARE_REGIONS, self.texture), (1, 1, self.flare_textures[0])] {
+                gl::BindFramebuffer(gl::FRAMEBUFFER, self.flare_fbos[pass]);
+                gl::Viewport(0, 0, size, size);
+                gl::BindTexture(gl::TEXTURE_2D, source);
+                gl::Uniform1i(location("pass"), pass as GLint);
+                gl::DrawArrays(gl::TRIANGLES, 0, 6);
+            }
+            gl::BindFramebuffer(gl::FRAMEBUFFER, self.target_fbo);
+            gl::Viewport(0, 0, self.win_width as GLsizei, self.win_height as GLsizei);
+        }
+        gl_check("finding the lens flare source");
+    }
+
+    fn toggle_lens_flare(&mut self) {
+        self.lens_flare_enabled = !self.lens_flare_enabled;
+        info!("Lens flare turned {}", if self.lens_flare_enabled { "ON" } else { "OFF" });
+    }
+
+    fn toggle_bloom(&mut self) {
+        self.bloom_enabled = !self.bloom_enabled;
+        info!("Bloom turned {}", if self.bloom_enabled { "ON" } else { "OFF" });
+    }
+
+    fn draw_fullscreen_quad(&self) {
+        unsafe {
+            gl::UseProgram(self.shader_program);
+            gl::BindVertexArray(self.quad_vao);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.texture);
+            let name = CString::new("screenTexture").unwrap();
+            gl::Uniform1i(gl::GetUniformLocation(self.shader_program, name.as_ptr()), 0);
+            gl::ActiveTexture(gl::TEXTURE1);
+            gl::BindTexture(gl::TEXTURE_2D, self.bloom_textures[0]);
+            let name = CString::new("bloomTexture").unwrap();
+            gl::Uniform1i(gl::GetUniformLocation(self.shader_program, name.as_ptr()), 1);
+            let name = CString::new("bloomIntensity").unwrap();
+            let intensity = if self.bloom_enabled { self.bloom_intensity } else { 0.0 };
+            gl::Uniform1f(gl::GetUniformLocation(self.shader_program, name.as_ptr()), intensity);
+            gl::ActiveTexture(gl::TEXTURE2);
+            gl::BindTexture(gl::TEXTURE_2D, self.flare_textures[1]);
+            let name = CString::new("flareTexture").unwrap();
+            gl::Uniform1i(gl::GetUniformLocation(self.shader_program, name.as_ptr()), 2);
+            let name = CString::new("flareIntensity").unwrap();
+            let intensity = if self.lens_flare_enabled { self.lens_flare_intensity } else { 0.0 };
+            gl::Uniform1f(gl::GetUniformLocation(self.shader_program, name.as_ptr()), intensity);
+            let name = CString::new("exposure").unwrap();
+            gl::Uniform1f(gl::GetUniformLocation(self.shader_program, name.as_ptr()), self.exposure.exp2());
+            let name = CString::new("supersampling").unwrap();
+            gl::Uniform1i(gl::GetUniformLocation(self.shader_program, name.as_ptr()), self.supersampling as GLint);
+            let name = CString::new("false_color").unwrap();
+            gl::Uniform1i(gl::GetUniformLocation(self.shader_program, name.as_ptr()), (self.render_channel != RenderChannel::Color) as GLint);
+
+            gl::Disable(gl::DEPTH_TEST);
+            gl::DrawArrays(gl::TRIANGLES, 0, 6);
+        }
+        gl_check("drawing the fullscreen quad");
+    }
+
+    // Swaps a freshly built program into `slot`, keeping the old one if the build failed
+    fn swap_program(slot: &mut gl::types::GLuint, result: Result<gl::types::GLuint, EngineError>) -> bool {
+        match result {
+            Ok(program) => {
+                unsafe { gl::DeleteProgram(*slot); }
+                *slot = program;
+                true
+            },
+            Err(error) => {
+                error!("{}", error);
+                info!("Keeping the previous shader");
+                false
+            }
+        }
+    }
+
+    /// Builds the compute shader at `path` and traces with it from the next frame on, keeping the
+    /// current one if it doesn't compile. A path missing from the scene's `[[compute_variant]]`
+    /// list joins it, named after its file.
+    pub fn load_compute_variant(&mut self, path: &str) -> Result<(), EngineError> {
+        let program = shader::create_compute_program(path)?;
+        unsafe { gl::DeleteProgram(self.compute_program); }
+        self.compute_program = program;
+        self.compute_variant = match self.compute_variants.iter().position(|variant| variant.path == path) {
+            Some(index) => index,
+            None => {
+                self.compute_variants.push(ComputeVariant::from_path(path));
+                self.compute_variants.len() - 1
+            }
+        };
+        // hot reloading follows the shader in use
+        self.shader_paths.compute = path.to_string();
+        #[cfg(feature = "hot-reload")]
+        {
+            self.shader_stamps = self.shader_paths.stamps();
+        }
+        self.reset_accumulation();
+        info!("Tracing with the {} compute shader ({})", self.compute_variants[self.compute_variant].name, path);
+        Ok(())
+    }
+
+    // Moves `step` places along the list of compute shaders, past any that fail to build
+    fn cycle_compute_variant(&mut self, step: isize) {
+        let count = self.compute_variants.len() as isize;
+        if count < 2 {
+            info!("No other compute shader to switch to, list them as [[compute_variant]] in the scene");
+            return;
+        }
+        for offset in 1..count {
+            let index = (self.compute_variant as isize + step * offset).rem_euclid(count) as usize;
+            let path = self.compute_variants[index].path.clone();
+            match self.load_compute_variant(&path) {
+                Ok(()) => return,
+                Err(error) => error!("{}", error),
+            }
+        }
+        info!("Keeping the {} compute shader", self.compute_variants[self.compute_variant].name);
+    }
+
+    fn reload_shaders(&mut self) {
+        self.reset_accumulation();
+        let paths = &self.shader_paths;
+        let main_ok = Engine::swap_program(&mut self.shader_program, shader::create_shader_program(&paths.main_vs, &paths.main_fs));
+        let compute_ok = Engine::swap_program(&mut self.compute_program, shader::create_compute_program(&paths.compute));
+        let bloom_ok = Engine::swap_program(&mut self.bloom_program, shader::create_shader_program(&paths.main_vs, &paths.bloom_fs));
+        let flare_ok = Engine::swap_program(&mut self.flare_program, shader::create_shader_program(&paths.main_vs, &paths.flare_fs));
+        let grid_ok = Engine::swap_program(&mut self.grid_shader_program, shader::create_shader_program(&paths.grid_vs, &paths.grid_fs));
+        let text_ok = Engine::swap_program(&mut self.overlay.program, shader::create_shader_program(&paths.text_vs, &paths.text_fs));
+        if main_ok && compute_ok && bloom_ok && flare_ok && grid_ok && text_ok {
+            info!("Shaders reloaded");
+        }
+    }
+
+    // Polls the shader sources twice a second and reloads when any of them changed
+    #[cfg(feature = "hot-reload")]
+    fn watch_shaders(&mut self) {
+        let now = self.now();
+        if now - self.last_reload_check < 0.5 {
+            return;
+        }
+        self.last_reload_check = now;
+
+        let stamps = self.shader_paths.stamps();
+        if stamps != self.shader_stamps {
+            self.shader_stamps = stamps;
+            self.reload_shaders();
+        }
+    }
+
+    // The command bound to a key event, one of the Ctrl shortcuts first when Ctrl is held, and
+    // key repeats only for the commands that step a value
+    fn bound_command(&self, event: &glfw::WindowEvent) -> Option<(Command, glfw::Modifiers)> {
+        match *event {
+            glfw::WindowEvent::Key(key, _, action @ (Action::Press | Action::Repeat), mods) => {
+                let bindings = &self.defaults.key_bindings;
+                mods.contains(glfw::Modifiers::Control).then(|| bindings.control_command(key)).flatten()
+                    .or_else(|| bindings.command(key))
+                    .filter(|command| action == Action::Press || command.repeats())
+                    .map(|command| (command, mods))
+            }
+            _ => None,
+        }
+    }
+
+    fn run_command(&mut self, command: Command, mods: glfw::Modifiers) {
+        let shift = mods.contains(glfw::Modifiers::Shift);
+        match command {
+            Command::Quit | Command::Exit => self.close(),
+            Command::SaveScene => self.export_scene_to_file(),
+            Command::ToggleSplit => self.toggle_split(),
+            Command::Reload => {
+                self.reload_shaders();
+                self.reload_disk_texture();
+            }
+            Command::Screenshot => {
+                // Shift saves the raw compute texture instead, Ctrl the whole window with the
+                // letterbox bars and the overlay
+                self.pending_screenshot = Some(if shift {
+                    CaptureSource::Compute
+                } else if mods.contains(glfw::Modifiers::Control) {
+                    CaptureSource::Window
+                } else {
+                    CaptureSource::Scene
+                });
+            }
+            Command::Pause => self.toggle_pause(),
+            // Shift saves one clean frame and leaves everything shown
+            Command::PhotoMode => {
+                if shift {
+                    self.take_photo(CaptureSource::Scene);
+                } else {
+                    self.toggle_photo_mode();
+                }
+            }
+            Command::StepFrame => self.step_frame(),
+            Command::Reset => self.reset(),
+            Command::ToggleGravity => {
+                self.gravity_enabled = !self.gravity_enabled;
+                self.notify(format!("Gravity turned {}", if self.gravity_enabled { "ON"} else {"OFF"}));
+            }
+            Command::ToggleGrid => {
+                if shift {
+                    self.cycle_grid_warp();
+                } else if mods.contains(glfw::Modifiers::Control) {
+                    self.toggle_grid_wireframe();
+                } else {
+                    self.toggle_grid();
+                }
+            }
+            Command::ToggleOverlay => self.toggle_overlay(),
+            Command::TogglePanel => self.toggle_panel(),
+            Command::ToggleTimeline => self.toggle_timeline(),
+            Command::SpawnParticle => {
+                if mods.contains(glfw::Modifiers::Control) {
+                    self.clear_particle();
+                } else {
+                    self.spawn_particle_at_isco(if shift { 1.05 } else { 1.0 });
+                }
+            }
+            Command::ToggleObjectPlacement => {
+                if shift {
+                    self.cycle_selected_object_color();
+                } else {
+                    self.toggle_object_placement();
+                }
+            }
+            Command::TogglePhotonOrbit => {
+                if shift {
+                    self.toggle_isco();
+                } else {
+                    self.toggle_photon_orbit();
+                }
+            }
+            Command::ToggleCrosshair => {
+                if shift {
+                    self.toggle_center_marker();
+                } else {
+                    self.set_crosshair(!self.show_crosshair);
+                }
+            }
+            Command::ToggleObserverRegion => self.toggle_observer_region(),
+            Command::ToggleFullscreen => self.toggle_fullscreen(),
+            Command::ToggleBloom => {
+                if shift {
+                    self.toggle_lens_flare();
+                } else {
+                    self.toggle_bloom();
+                }
+            }
+            Command::ToggleVsync => self.toggle_vsync(),
+            Command::ToggleTiled => self.toggle_tiled(),
+            Command::ToggleDynamicResolution => self.toggle_dynamic_resolution(),
+            Command::ToggleIntegrationMode => self.toggle_integration_mode(),
+            Command::ToggleDoppler => {
+                self.set_doppler(!self.doppler_enabled);
+                info!("Doppler beaming turned {}", if self.doppler_enabled { "ON" } else { "OFF" });
+            }
+            Command::ToggleRedshift => {
+                self.set_gravitational_redshift(!self.redshift_enabled);
+                info!("Gravitational redshift turned {}", if self.redshift_enabled { "ON" } else { "OFF" });
+            }
+            Command::ToggleStarfield => self.toggle_starfield(),
+            Command::Recenter => {
+                self.camera.recenter();
+                info!("Camera recentered on the black hole");
+            }
+            Command::CameraMode => {
+                self.camera.toggle_mode();
+                info!("Camera mode {:?}", self.camera.mode);
+            }
+            Command::FrameDisk => self.frame_disk(),
+            Command::CycleQuality => self.cycle_quality(),
+            Command::CycleGeodesicDebug => self.cycle_geodesic_debug(),
+            Command::ExportGeodesic => self.export_geodesic_under_cursor(),
+            Command::CycleOutputAspect => self.cycle_output_aspect(),
+            Command::CycleComputeVariant => self.cycle_compute_variant(if shift { -1 } else { 1 }),
+            Command::CycleDiskProfile => self.cycle_disk_profile(),
+            Command::CycleDiskModel => self.cycle_disk_model(),
+            Command::CycleImageOrder => self.cycle_image_order(),
+            Command::CycleChannel => self.cycle_channel(),
+            Command::CycleRedshiftExaggeration => self.cycle_redshift_exaggeration(),
+            Command::IncreaseDiskInnerRadius => self.step_disk_inner_radius(1.0),
+            Command::DecreaseDiskInnerRadius => self.step_disk_inner_radius(-1.0),
+            Command::IncreaseMass if shift => self.step_selected_object_mass(1),
+            Command::DecreaseMass if shift => self.step_selected_object_mass(-1),
+            Command::IncreaseMass => self.step_mass(1),
+            Command::DecreaseMass => self.step_mass(-1),
+            Command::IncreaseSpin => self.step_spin(1.0),
+            Command::DecreaseSpin => self.step_spin(-1.0),
+            Command::IncreaseResolution => self.step_resolution_scale(1.0),
+            Command::DecreaseResolution => self.step_resolution_scale(-1.0),
+            Command::IncreaseFov => self.step_fov(1.0),
+            Command::DecreaseFov => self.step_fov(-1.0),
+            // Shift picks the active parameter, the plain keys step it
+            Command::NextParam => {
+                if shift {
+                    self.cycle_active_param(1);
+                } else {
+                    self.step_active_param(1);
+                }
+            }
+            Command::PreviousParam => {
+                if shift {
+                    self.cycle_active_param(-1);
+                } else {
+                    self.step_active_param(-1);
+                }
+            }
+        }
+    }
+
+    fn handle_window_event(&mut self, event: glfw::WindowEvent) {
+        if let glfw::WindowEvent::Key(key, _, action, mods) = event {
+            // Ctrl+S and the like are commands, not camera moves
+            if action != Action::Press || !mods.contains(glfw::Modifiers::Control) {
+                self.camera.process_key(key, action);
+            }
+        }
+        if self.panel_event(&event) {
+            return;
+        }
+        if let Some((command, mods)) = self.bound_command(&event) {
+            self.run_command(command, mods);
+            return;
+        }
+        match event {
+            // The quit and exit keys, Escape and Ctrl+Q unless rebound, and the window's close
+            // button all leave through `shutdown`
+            glfw::WindowEvent::Close => {
+                self.close()
+            }
+            glfw::WindowEvent::CursorPos(x, y) if self.dragging_split => {
+                let (fx, _) = self.cursor_in_scene(x, y);
+                self.set_split_position(fx as f32);
+                let (x, y) = self.cursor_points(x, y);
+                self.camera.process_mouse_move(x, y);
+            }
+            glfw::WindowEvent::CursorPos(x, y) if self.tilting_disk => {
+                let (x, y) = self.cursor_points(x, y);
+                self.drag_disk_tilt(x, y);
+                // the camera keeps track of the cursor so it doesn't jump on the next drag
+                self.camera.process_mouse_move(x, y);
+            }
+            glfw::WindowEvent::CursorPos(x, y) => {
+                let (x, y) = self.cursor_points(x, y);
+                self.camera.process_mouse_move(x, y);
+            }
+            glfw::WindowEvent::MouseButton(glfw::MouseButtonLeft, Action::Press, _) if self.cursor_over_split_divider() => {
+                self.dragging_split = true;
+            }
+            glfw::WindowEvent::MouseButton(glfw::MouseButtonLeft, Action::Release, _) if self.dragging_split => {
+                self.dragging_split = false;
+            }
+            glfw::WindowEvent::MouseButton(glfw::MouseButtonLeft, Action::Press, mods) if mods.contains(glfw::Modifiers::Control) => {
+                self.tilting_disk = true;
+                let (x, y) = self.cursor_pos();
+                self.tilt_cursor = self.cursor_points(x, y);
+            }
+            glfw::WindowEvent::MouseButton(glfw::MouseButtonLeft, Action::Release, _) if self.tilting_disk => {
+                self.tilting_disk = false;
+                info!("Disk inclination {:.1} deg, azimuth {:.1} deg", self.disk_inclination.to_degrees(), self.disk_azimuth.to_degrees());
+            }
+            glfw::WindowEvent::MouseButton(button, Action::Press, mods) if self.placing_objects
+                && (button == glfw::MouseButtonLeft || button == glfw::MouseButtonRight) => {
+                // placement takes the clicks over from the camera and the gravity button
+                let (x, y) = self.cursor_pos();
+                if button == glfw::MouseButtonLeft && mods.contains(glfw::Modifiers::Shift) {
+                    self.select_object_at_screen(x, y);
+                } else if button == glfw::MouseButtonLeft {
+                    self.place_object_at_screen(x, y);
+                } else {
+                    self.remove_object_at_screen(x, y);
+                }
+            }
+            glfw::WindowEvent::MouseButton(button, action, mods) => {
+                // gravity stays on for as long as the right button is held
+                if button == glfw::MouseButtonRight {
+                    match action {
+                        Action::Press => self.gravity_enabled = true,
+                        Action::Release => self.gravity_enabled = false,
+                        Action::Repeat => {}
+                    }
+                }
+                let (x, y) = self.cursor_pos();
+                self.camera.process_mouse_button(button, action, mods, self.cursor_points(x, y));
+            }
+            glfw::WindowEvent::Scroll(xoffset, yoffset) => {
+                self.camera.process_scroll(xoffset, yoffset);
+            }
+            glfw::WindowEvent::FramebufferSize(w, h) => {
+                self.resize(w.max(0) as u32, h.max(0) as u32);
+            }
+            glfw::WindowEvent::ContentScale(x, y) => {
+                self.set_ui_scale((x, y));
+            }
+            glfw::WindowEvent::Key(key @ (Key::Num1 | Key::Num2 | Key::Num3 | Key::Num4 | Key::Num5
+                | Key::Num6 | Key::Num7 | Key::Num8 | Key::Num9), _, Action::Press, mods) => {
+                self.use_bookmark(key as usize - Key::Num1 as usize, mods.contains(glfw::Modifiers::Shift));
+            }
+            // 0 beside the bookmarks resets too, whatever key the reset command has
+            glfw::WindowEvent::Key(Key::Num0, _, Action::Press, _) => {
+                self.reset();
+            }
+            _ => {}
+        }
+    }
+
+    /// The viewer: handles the window's events and draws and shows frames until it is closed.
+    /// Does nothing for an engine made with `new_with_context`, whose caller runs the loop.
+    pub fn run(&mut self) {
+        if self.app_window.is_none() {
+            warn!("The engine doesn't own a window to run in, draw it with render_into");
+            return;
+        }
+        while self.app_window.as_ref().is_some_and(|app| !app.window.should_close()) {
+            let events: Vec<_> = match &mut self.app_window {
+                Some(app) => {
+                    app.glfw.poll_events();
+                    glfw::flush_messages(&app.events).collect()
+                }
+                None => Vec::new(),
+            };
+            for (_, event) in events {
+                self.handle_window_event(event);
+            }
+
+            #[cfg(feature = "hot-reload")]
+            self.watch_shaders();
+
+            let now = self.now();
+            // cap the step so a stalled frame doesn't fling the camera
+            let dt = self.fixed_timestep.unwrap_or((now - self.last_frame_time).min(0.1));
+            self.last_frame_time = now;
+            self.camera.update(dt);
+            // render_into takes the pending step
+            let render = !self.paused || self.step_pending;
+            if self.timeline_playing && render {
+                // a single step moves the timeline on by one frame of its own rate, not by the wall clock
+                let step = match &self.timeline {
+                    Some(timeline) if self.paused => 1.0 / timeline.fps as f64,
+                    _ => dt,
+                };
+                self.advance_timeline(step);
+            }
+
+            if let Some(profiler) = &mut self.profiler {
+                profiler.begin_frame();
+            }
+            unsafe {
+                gl::ClearColor(0.0, 0.0, 0.0, 1.0);
+                gl::Clear(gl::COLOR_BUFFER_BIT | gl::DEPTH_BUFFER_BIT);
+            }
+
+            self.render_into(0, self.scene_viewport());
+            if render {
+                self.step_simulation(dt);
+            }
+            unsafe { gl::Viewport(0, 0, self.win_width as GLsizei, self.win_height as GLsizei); }
+            if let Some(profiler) = &self.profiler {
+                profiler.post_done();
+            }
+            if !self.hides_ui() {
+                self.draw_overlay();
+            }
+            if let Some(profiler) = &mut self.profiler {
+                profiler.end_frame();
+            }
+            // read back before the swap, the back buffer is undefined afterwards
+            self.take_pending_screenshot();
+
+            if let Some(app) = &mut self.app_window {
+                app.window.swap_buffers();
+            }
+
+            self.frames_this_second += 1;
+            if now - self.last_fps_print >= 1.0 {
+                self.fps = self.frames_this_second as f64 / (now - self.last_fps_print);
+                self.frame_ms = 1000.0 / self.fps;
+                self.last_fps_print = now;
+                self.adjust_dynamic_resolution(self.traced_this_second == self.frames_this_second);
+                self.frames_this_second = 0;
+                self.traced_this_second = 0;
+            }
+        }
+
+        self.shutdown();
+    }
+
+    /// Traces the view and draws it into the framebuffer `target_fbo`, 0 for the window's, over
+    /// `viewport`: x, y, width and height in its pixels from the bottom left. The grid, ray paths
+    /// and particle trail that are switched on are drawn over it; the overlay and panel are not,
+    /// nor is anything outside the viewport cleared. It neither polls events nor swaps buffers,
+    /// so an app that owns the window and the GL context can render the black hole into a
+    /// texture of its own and composite it, `run` is the viewer's loop around it. The compute
+    /// resolution and aspect follow `resize`, which should be given the viewport's size. While
+    /// paused it redraws the last image. Leaves `target_fbo` bound.
+    pub fn render_into(&mut self, target_fbo: gl::types::GLuint, viewport: (GLint, GLint, GLsizei, GLsizei)) {
+        let trace = !self.paused || mem::take(&mut self.step_pending);
+        self.target_fbo = target_fbo;
+        self.update_camera();
+        if trace {
+            if self.frame_index < self.sample_target() {
+                self.traced_this_second += 1;
+            }
+            self.dispatch_compute();
+        }
+        if let Some(profiler) = &self.profiler {
+            profiler.compute_done();
+        }
+        self.apply_bloom();
+        self.apply_lens_flare();
+        let (x, y, width, height) = viewport;
+        unsafe {
+            gl::BindFramebuffer(gl::FRAMEBUFFER, target_fbo);
+            gl::Viewport(x, y, width, height);
+        }
+        self.draw_fullscreen_quad();
+        if !self.hides_ui() {
+            self.draw_grid();
+            self.draw_geodesic_paths();
+            self.draw_particle_trail();
+        }
+    }
+
+    /// Runs once the viewer is closing: saves the bookmarks if any changed and waits for the GPU
+    /// to finish what was queued, so the `Drop` impl releases resources nothing still uses. A
+    /// screenshot key pressed in the last frame has already been taken, the loop reads back before
+    /// it checks for the close.
+    pub fn shutdown(&mut self) {
+        if self.bookmarks_changed {
+            match bookmarks::save(BOOKMARKS_PATH, &self.bookmarks) {
+                Ok(()) => info!("Saved bookmarks to {}", BOOKMARKS_PATH),
+                Err(error) => error!("{}", error),
+            }
+            self.bookmarks_changed = false;
+        }
+        unsafe { gl::Finish(); }
+    }
+}
+
+/// Releases every GL object owned by the engine.
+///
+/// Besides the programs, buffers and textures this covers the bloom and lens flare framebuffers:
+/// they only reference `bloom_textures` and `flare_textures`, so both the framebuffers and their
+/// color attachments are deleted.
+///
+/// The engine's GL context must still be current when this runs: its own window's for an engine
+/// made by `new_windowed`, the caller's for one made by `new_with_context`. Fields are dropped
+/// only after `drop` returns, so an owned window (and with it the context) outlives these deletes.
+/// An embedding app must drop the engine before it destroys its context, and callers that make
+/// another context current must switch back first.
+impl Drop for Engine {
+    fn drop(&mut self) {
+        unsafe {
+            for program in [self.shader_program, self.compute_program, self.bloom_program, self.flare_program, self.grid_shader_program] {
+                if program != 0 { gl::DeleteProgram(program); }
+            }
+            for vao in [self.quad_vao, self.grid_vao, self.path_vao, self.trail_vao] {
+                if vao != 0 { gl::DeleteVertexArrays(1, &vao); }
+            }
+            for buffer in [self.quad_vbo, self.grid_vbo, self.grid_ebo, self.path_vbo, self.trail_vbo, self.camera_ubo, self.disk_ubo, self.black_hole_ubo, self.integrator_ubo, self.sky_ubo, self.objects_ubo, self.debug_ray_ssbo] {
+                if buffer != 0 { gl::DeleteBuffers(1, &buffer); }
+            }
+            for fbo in self.bloom_fbos.into_iter().chain(self.flare_fbos) {
+                if fbo != 0 { gl::DeleteFramebuffers(1, &fbo); }
+            }
+            for texture in [self.texture, self.accum_texture, self.edge_texture, self.background, self.disk_texture, self.colormap_texture, self.bloom_textures[0], self.bloom_textures[1], self.flare_textures[0], self.flare_textures[1]] {
+                if texture != 0 { gl::DeleteTextures(1, &texture); }
+            }
+        }
+        self.overlay.delete();
+        if let Some(profiler) = &mut self.profiler {
+            profiler.delete();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blackbody_colors_run_from_red_to_blue_white() {
+        let cool = blackbody_rgb(1500.0);
+        assert!(cool.x == 1.0 && cool.y < 0.5 * cool.x && cool.z < 0.1);
+        let white = blackbody_rgb(6600.0);
+        assert!(white.x > 0.9 && white.y > 0.9 && white.z > 0.9);
+        let hot = blackbody_rgb(30000.0);
+        assert!(hot.z == 1.0 && hot.x < hot.z);
+        assert_eq!(blackbody_rgb(1e6), blackbody_rgb(40000.0));
+
+        assert_eq!(disk_temperature(3.0, 3.0, 1e4), 1e4);
+        assert!((disk_temperature(48.0, 3.0, 1e4) - 1250.0).abs() < 1e-2);
+    }
+
+    #[test]
+    fn halton_fills_the_unit_interval_evenly() {
+        let base2: Vec<f32> = (1..=4).map(|i| halton(i, 2)).collect();
+        assert_eq!(base2, [0.5, 0.25, 0.75, 0.125]);
+        let base3: Vec<f32> = (1..=3).map(|i| halton(i, 3)).collect();
+        for (value, expected) in base3.iter().zip([1.0 / 3.0, 2.0 / 3.0, 1.0 / 9.0]) {
+            assert!((value - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn camera_ubo_position_matches_camera() {
+        let mut camera = Camera::new();
+        camera.azimuth = 0.7;
+        camera.elevation = 1.1;
+        camera.radius = 3e10;
+
+        let data = Engine::camera_ubo_data(&camera, 4.0 / 3.0);
+        let expected = camera.position();
+        for (uploaded, expected) in data.cam_pos.iter().zip([expected.x, expected.y, expected.z]) {
+            assert!((uploaded - expected).abs() <= expected.abs() * f32::EPSILON);
+        }
+    }
+
+    // The compute shader's primary ray through the center of pixel (px, py) of a width x height image
+    fn shader_ray(data: &CameraUbo, px: u32, py: u32, width: u32, height: u32) -> Vec3 {
+        let [right, up, forward] = [data.cam_right, data.cam_up, data.cam_forward].map(|v| vec3(v[0], v[1], v[2]));
+        let u = (2.0 * (px as f32 + 0.5) / width as f32 - 1.0) * data.aspect * data.tan_half_fov;
+        let v = (1.0 - 2.0 * (py as f32 + 0.5) / height as f32) * data.tan_half_fov;
+        glm::normalize(right * u - up * v + forward)
+    }
+
+    #[test]
+    fn rays_spread_evenly_across_and_down() {
+        let camera = Camera::new();
+        let forward = camera.basis().0;
+        let angle = |dir: Vec3| glm::dot(dir, forward).clamp(-1.0, 1.0).acos();
+        // a square window mirrors the rays across its diagonal
+        let data = Engine::camera_ubo_data(&camera, 1.0);
+        for (px, py) in [(0, 10), (5, 60), (63, 0), (20, 41)] {
+            let (a, b) = (angle(shader_ray(&data, px, py, 64, 64)), angle(shader_ray(&data, py, px, 64, 64)));
+            assert!((a - b).abs() < 1e-5, "({}, {}): {} vs {}", px, py, a, b);
+        }
+        // on a wide one a step across turns the ray as far as a step down
+        let data = Engine::camera_ubo_data(&camera, 1920.0 / 1080.0);
+        let across = angle(shader_ray(&data, 960 + 100, 540, 1920, 1080));
+        let down = angle(shader_ray(&data, 960, 540 + 100, 1920, 1080));
+        assert!((across - down).abs() < 1e-4, "{} vs {}", across, down);
+    }
+
+    #[test]
+    fn picking_ray_lands_on_the_disk_plane() {
+        let mut camera = Camera::new();
+        camera.elevation = 0.6;
+        camera.target = vec3(2e10, 0.0, -1e10);
+        // the center of the window looks straight at the target, which lies in the plane
+        let hit = disk_plane_hit(camera.position(), camera.ray_through(4.0 / 3.0, 0.5, 0.5)).unwrap();
+        assert!(glm::length(hit - camera.target) < 1e10 * 1e-4);
+        assert!(disk_plane_hit(vec3(0.0, 1e10, 0.0), vec3(0.0, 1.0, 0.0)).is_none(), "points away from the plane");
+    }
+
+    #[test]
+    fn removing_an_object_keeps_the_selection_on_the_same_object() {
+        assert_eq!(selection_after_removal(Some(2), 2), None, "the selected object itself");
+        assert_eq!(selection_after_removal(Some(2), 0), Some(1), "an earlier one shifts it down");
+        assert_eq!(selection_after_removal(Some(2), 3), Some(2), "a later one leaves it");
+        assert_eq!(selection_after_removal(None, 0), None);
+    }
+
+    #[test]
+    fn reference_orbits_match_the_schwarzschild_values_and_shrink_with_spin() {
+        assert!((photon_orbit_radius(0.0) - 1.5).abs() < 1e-12);
+        assert!((isco_radius(0.0) - 3.0).abs() < 1e-12);
+        // an extremal hole pulls both down to the horizon at M = 0.5 Rs
+        assert!((photon_orbit_radius(1.0) - 0.5).abs() < 1e-6);
+        assert!((isco_radius(1.0) - 0.5).abs() < 1e-6);
+        assert!(isco_radius(0.5) < isco_radius(0.0) && photon_orbit_radius(0.5) < photon_orbit_radius(0.0));
+    }
+
+    #[test]
+    fn dynamic_resolution_settles_instead_of_hunting() {
+        let mut controller = ResolutionController::new(60.0);
+        // half the target rate needs half the pixels, 0.71 of the scale, rounded down to a step
+        let lower = controller.update(1.0, 1000.0 / 30.0).unwrap();
+        assert!((lower - 0.7).abs() < 1e-4, "{}", lower);
+        // the measurement spanning the switch is passed over
+        assert_eq!(controller.update(lower, 1000.0 / 10.0), None);
+        // just over the target isn't enough headroom for the next step up
+        assert_eq!(controller.update(lower, 1000.0 / 62.0), None);
+        assert_eq!(controller.update(lower, 1000.0 / 57.0), None);
+        let higher = controller.update(0.5, 1000.0 / 120.0).unwrap();
+        assert!((higher - 0.55).abs() < 1e-4, "{}", higher);
+        controller.update(0.5, 1000.0);
+        // never past the window's resolution or below the smallest scale
+        assert_eq!(controller.update(1.0, 1.0), None);
+        assert_eq!(controller.update(MIN_RESOLUTION_SCALE, 1000.0), None);
+    }
+
+    #[test]
+    fn srgb_encoding_inverts_the_decoding() {
+        for byte in [0u8, 1, 10, 11, 64, 128, 200, 255] {
+            let v = byte as f32 / 255.0;
+            assert!((linear_to_srgb(srgb_to_linear(v)) - v).abs() < 1e-5, "{}", byte);
+        }
+        // mid grey in linear light is far brighter than half the byte range once encoded
+        assert_eq!((linear_to_srgb(0.5) * 255.0).round(), 188.0);
+        assert_eq!(linear_to_srgb(-1.0), 0.0);
+        assert_eq!((linear_to_srgb(4.0) * 255.0).round(), 255.0);
+    }
+
+    #[test]
+    fn letterbox_centers_the_locked_aspect() {
+        assert_eq!(letterbox(1920, 1080, None), [0, 0, 1920, 1080]);
+        // bars above and below a scope picture, at the sides of a 4:3 one
+        assert_eq!(letterbox(1920, 1080, Some(2.39)), [0, 138, 1920, 803]);
+        assert_eq!(letterbox(1920, 1080, Some(4.0 / 3.0)), [240, 0, 1440, 1080]);
+        assert_eq!(letterbox(800, 600, Some(4.0 / 3.0)), [0, 0, 800, 600]);
+    }
+
+    #[test]
+    fn tiles_cover_the_image_once_from_the_top_left() {
+        let (width, height) = (600, 300);
+        assert_eq!(tile_count(256, width, height), 6);
+        assert_eq!(tile_rect(0, 256, width, height), [0, 44, 256, 300]);
+        assert_eq!(tile_rect(5, 256, width, height), [512, 0, 600, 44]);
+        let mut covered = vec![0; (width * height) as usize];
+        for index in 0..tile_count(256, width, height) {
+            let [x0, y0, x1, y1] = tile_rect(index, 256, width, height);
+            for y in y0..y1 {
+                for x in x0..x1 {
+                    covered[(y as u32 * width + x as u32) as usize] += 1;
+                }
+            }
+        }
+        assert!(covered.iter().all(|&n| n == 1));
+    }
+
+    #[test]
+    fn split_halves_share_the_divider_column() {
+        assert_eq!(split_rect([0, 0, 200, 150], 100), [[0, 0, 100, 150], [100, 0, 200, 150]]);
+        // a tile on one side leaves the other half empty
+        assert_eq!(split_rect([128, 22, 200, 150], 100), [[128, 22, 128, 150], [128, 22, 200, 150]]);
+        assert_eq!(split_rect([0, 0, 64, 64], 100), [[0, 0, 64, 64], [64, 0, 64, 64]]);
+    }
+
+    #[test]
+    fn grid_mesh_links_every_neighbour_once() {
+        let (vertices, indices) = grid_mesh(4, 10.0);
+        assert_eq!(vertices.len(), 5 * 5 * 3);
+        assert_eq!(indices.len(), 2 * (2 * 4 * 5), "4 segments on each of 5 lines, both ways");
+        assert_eq!(&vertices[0..3], &[-10.0, 0.0, -10.0]);
+        assert_eq!(&vertices[vertices.len() - 3..], &[10.0, 0.0, 10.0]);
+        assert!(indices.iter().all(|&i| (i as usize) < vertices.len() / 3));
+    }
+
+    #[test]
+    fn grid_sheet_faces_both_ways() {
+        let (vertices, _) = grid_mesh(4, 10.0);
+        let sheet = grid_sheet(4);
+        assert_eq!(sheet.len(), 4 * 4 * 12, "two triangles per cell, in both windings");
+        // the y component of each triangle's normal
+        let normal_y = |t: &[u32]| {
+            let p = |i: u32| (vertices[i as usize * 3], vertices[i as usize * 3 + 2]);
+            let (p0, p1, p2) = (p(t[0]), p(t[1]), p(t[2]));
+            (p1.1 - p0.1) * (p2.0 - p0.0) - (p1.0 - p0.0) * (p2.1 - p0.1)
+        };
+        let up = sheet.chunks(3).filter(|t| normal_y(t) > 0.0).count();
+        let down = sheet.chunks(3).filter(|t| normal_y(t) < 0.0).count();
+        assert_eq!((up, down), (sheet.len() / 6, sheet.len() / 6));
+    }
+
+    #[test]
+    fn fullscreen_quad_is_not_culled() {
+        // both triangles wind counter-clockwise in NDC, the front face culling keeps
+        for triangle in QUAD_VERTICES.chunks(12) {
+            let p = |i: usize| (triangle[i * 4], triangle[i * 4 + 1]);
+            let (p0, p1, p2) = (p(0), p(1), p(2));
+            assert!((p1.0 - p0.0) * (p2.1 - p0.1) - (p1.1 - p0.1) * (p2.0 - p0.0) > 0.0);
+        }
+    }
+
+    #[test]
+    fn nice_length_rounds_down_to_1_2_or_5() {
+        assert_eq!(nice_length(7.3), 5.0);
+        assert_eq!(nice_length(1.9), 1.0);
+        assert_eq!(nice_length(0.25), 0.2);
+        assert_eq!(nice_length(50.0), 50.0);
+    }
+
+    // Traces a fixed view through every stage of the pipeline, lensing, Doppler and redshift,
+    // bloom and the tone mapping quad, and compares it to tests/golden/lensed_disk.png with a
+    // few levels of slack per channel for driver rounding. Skipped where no GL 4.3 context can be
+    // made. Shader or layout changes that move the image on purpose rewrite the golden with
+    // BLESS=1 (libtest turns down unknown flags such as --bless):
+    //     BLESS=1 cargo test --features gpu-tests golden
+    #[cfg(feature = "gpu-tests")]
+    #[test]
+    fn render_matches_the_golden_image() {
+        const GOLDEN: &str = "tests/golden/lensed_disk.png";
+        const TOLERANCE: u8 = 3;
+        let options = EngineOptions { render: Some(GOLDEN.to_string()), seed: Some(1), gravity: true, ..EngineOptions::default() };
+        let mut engine = match Engine::new_windowed(&options) {
+            Ok(engine) => engine,
+            Err(error) => {
+                eprintln!("skipping the golden image test: {}", error);
+                return;
+            }
+        };
+        engine.camera.azimuth = 0.4;
+        engine.camera.elevation = 1.35;
+        engine.camera.radius = 1e11;
+        engine.set_doppler(true);
+        engine.set_gravitational_redshift(true);
+        engine.bloom_enabled = true;
+        engine.prepare_offline(RenderOptions { width: 160, height: 120, samples: 4, supersampling: 1 });
+        let actual = pixels_to_image(engine.win_width, engine.win_height, engine.render_converged());
+
+        if std::env::var_os("BLESS").is_some() {
+            std::fs::create_dir_all("tests/golden").unwrap();
+            actual.save(GOLDEN).unwrap();
+            return;
+        }
+        let golden = image::open(GOLDEN).expect("no golden image, make one with BLESS=1").to_rgba8();
+        assert_eq!(golden.dimensions(), actual.dimensions());
+        let off = golden.pixels().zip(actual.pixels())
+            .filter(|(g, a)| g.0.iter().zip(a.0).any(|(&g, a)| g.abs_diff(a) > TOLERANCE))
+            .count();
+        if off > 0 {
+            let path = std::env::temp_dir().join("lensed_disk.actual.png");
+            actual.save(&path).unwrap();
+            panic!("{} pixels differ from {} by more than {}, the render is at {}", off, GOLDEN, TOLERANCE, path.display());
+        }
+    }
+}
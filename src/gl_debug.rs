@@ -0,0 +1,203 @@
+use std::ffi::CStr;
+use std::os::raw::c_void;
+use std::ptr;
+use gl::types::{GLchar, GLenum, GLsizei, GLuint};
+use glfw::Context;
+use log::{log, Level};
+
+/// Name of a `glGetError` code.
+#[cfg_attr(not(feature = "gl-debug"), allow(dead_code))]
+pub fn error_name(code: GLenum) -> &'static str {
+    match code {
+        gl::NO_ERROR => "GL_NO_ERROR",
+        gl::INVALID_ENUM => "GL_INVALID_ENUM",
+        gl::INVALID_VALUE => "GL_INVALID_VALUE",
+        gl::INVALID_OPERATION => "GL_INVALID_OPERATION",
+        gl::INVALID_FRAMEBUFFER_OPERATION => "GL_INVALID_FRAMEBUFFER_OPERATION",
+        gl::OUT_OF_MEMORY => "GL_OUT_OF_MEMORY",
+        gl::STACK_UNDERFLOW => "GL_STACK_UNDERFLOW",
+        gl::STACK_OVERFLOW => "GL_STACK_OVERFLOW",
+        _ => "unknown GL error",
+    }
+}
+
+/// Logs every error GL has flagged since the last check, naming the operation in `label`.
+/// Only the `gl-debug` feature compiles the check in, otherwise this does nothing.
+#[cfg(feature = "gl-debug")]
+pub fn gl_check(label: &str) {
+    loop {
+        let code = unsafe { gl::GetError() };
+        if code == gl::NO_ERROR {
+            break;
+        }
+        log::error!(target: "gl", "{} (0x{:04X}) after {}", error_name(code), code, label);
+    }
+}
+
+#[cfg(not(feature = "gl-debug"))]
+#[inline(always)]
+pub fn gl_check(_label: &str) {}
+
+/// A `glGetString` value such as the version or renderer, empty when GL has none or the driver
+/// doesn't provide glGetString at all.
+pub fn gl_string(name: GLenum) -> String {
+    if !gl::GetString::is_loaded() {
+        return String::new();
+    }
+    let text = unsafe { gl::GetString(name) };
+    if text.is_null() {
+        return String::new();
+    }
+    unsafe { CStr::from_ptr(text as *const _) }.to_string_lossy().into_owned()
+}
+
+// A GL entry point's name and whether the loader found it
+type RequiredFunction = (&'static str, fn() -> bool);
+
+// Entry points the renderer can't start without: compute dispatch and image load/store, the
+// buffers and vertex arrays, shaders, textures and the framebuffers of the bloom
+const REQUIRED_FUNCTIONS: [RequiredFunction; 28] = [
+    ("glGetString", gl::GetString::is_loaded),
+    ("glGetStringi", gl::GetStringi::is_loaded),
+    ("glGetIntegerv", gl::GetIntegerv::is_loaded),
+    ("glDispatchCompute", gl::DispatchCompute::is_loaded),
+    ("glMemoryBarrier", gl::MemoryBarrier::is_loaded),
+    ("glBindImageTexture", gl::BindImageTexture::is_loaded),
+    ("glGenBuffers", gl::GenBuffers::is_loaded),
+    ("glBindBuffer", gl::BindBuffer::is_loaded),
+    ("glBindBufferBase", gl::BindBufferBase::is_loaded),
+    ("glBufferData", gl::BufferData::is_loaded),
+    ("glBufferSubData", gl::BufferSubData::is_loaded),
+    ("glGenVertexArrays", gl::GenVertexArrays::is_loaded),
+    ("glBindVertexArray", gl::BindVertexArray::is_loaded),
+    ("glVertexAttribPointer", gl::VertexAttribPointer::is_loaded),
+    ("glEnableVertexAttribArray", gl::EnableVertexAttribArray::is_loaded),
+    ("glCreateShader", gl::CreateShader::is_loaded),
+    ("glShaderSource", gl::ShaderSource::is_loaded),
+    ("glCompileShader", gl::CompileShader::is_loaded),
+    ("glCreateProgram", gl::CreateProgram::is_loaded),
+    ("glAttachShader", gl::AttachShader::is_loaded),
+    ("glLinkProgram", gl::LinkProgram::is_loaded),
+    ("glUseProgram", gl::UseProgram::is_loaded),
+    ("glGetUniformLocation", gl::GetUniformLocation::is_loaded),
+    ("glTexImage2D", gl::TexImage2D::is_loaded),
+    ("glActiveTexture", gl::ActiveTexture::is_loaded),
+    ("glGenFramebuffers", gl::GenFramebuffers::is_loaded),
+    ("glBindFramebuffer", gl::BindFramebuffer::is_loaded),
+    ("glFramebufferTexture2D", gl::FramebufferTexture2D::is_loaded),
+];
+
+/// The GL entry points the renderer needs that the driver left out, by name, after
+/// `gl::load_with`. Partial drivers can report a version they don't fully provide; empty when
+/// everything is there.
+pub fn missing_functions() -> Vec<&'static str> {
+    REQUIRED_FUNCTIONS.iter().filter(|(_, is_loaded)| !is_loaded()).map(|(name, _)| *name).collect()
+}
+
+/// Whether the current context runs compute shaders: GL 4.3 and up, or an older one with the
+/// GL_ARB_compute_shader extension.
+pub fn supports_compute_shaders() -> bool {
+    let (mut major, mut minor) = (0, 0);
+    unsafe {
+        gl::GetIntegerv(gl::MAJOR_VERSION, &mut major);
+        gl::GetIntegerv(gl::MINOR_VERSION, &mut minor);
+    }
+    (major, minor) >= (4, 3) || has_extension("GL_ARB_compute_shader")
+}
+
+/// Whether the current context lists `name` among its extensions.
+pub fn has_extension(name: &str) -> bool {
+    let mut count = 0;
+    unsafe { gl::GetIntegerv(gl::NUM_EXTENSIONS, &mut count); }
+    (0..count.max(0) as GLuint).any(|index| {
+        let text = unsafe { gl::GetStringi(gl::EXTENSIONS, index) };
+        !text.is_null() && unsafe { CStr::from_ptr(text as *const _) }.to_bytes() == name.as_bytes()
+    })
+}
+
+/// The GL_VERSION of a context made without any version hints, the best the driver offers when
+/// it turned down the one asked for. Uses a hidden 1x1 window that is closed again.
+pub fn probe_default_version(glfw: &mut glfw::Glfw) -> Option<String> {
+    glfw.default_window_hints();
+    glfw.window_hint(glfw::WindowHint::Visible(false));
+    let (mut window, _events) = glfw.create_window(1, 1, "", glfw::WindowMode::Windowed)?;
+    window.make_current();
+    gl::GetString::load_with(|s| window.get_proc_address(s).map_or(ptr::null(), |f| f as *const _));
+    let version = gl_string(gl::VERSION);
+    (!version.is_empty()).then_some(version)
+}
+
+/// Routes the driver's debug messages to the log when the context supports GL_KHR_debug, part of
+/// core GL since 4.3. Returns whether the callback was registered. Notifications are only asked
+/// for with the `gl-debug` feature and logged at debug level; the feature also makes messages
+/// synchronous so they arrive while the offending call is still on the stack.
+pub fn enable_debug_output() -> bool {
+    if !gl::DebugMessageCallback::is_loaded() {
+        return false;
+    }
+    unsafe {
+        gl::Enable(gl::DEBUG_OUTPUT);
+        if cfg!(feature = "gl-debug") {
+            gl::Enable(gl::DEBUG_OUTPUT_SYNCHRONOUS);
+        } else {
+            gl::DebugMessageControl(gl::DONT_CARE, gl::DONT_CARE, gl::DEBUG_SEVERITY_NOTIFICATION, 0, ptr::null(), gl::FALSE);
+        }
+        gl::DebugMessageCallback(Some(debug_callback), ptr::null());
+    }
+    true
+}
+
+extern "system" fn debug_callback(source: GLenum, kind: GLenum, id: GLuint, severity: GLenum, length: GLsizei,
+                                  message: *const GLchar, _user: *mut c_void) {
+    let text = if message.is_null() {
+        String::new()
+    } else if length >= 0 {
+        let bytes = unsafe { std::slice::from_raw_parts(message as *const u8, length as usize) };
+        String::from_utf8_lossy(bytes).into_owned()
+    } else {
+        unsafe { CStr::from_ptr(message) }.to_string_lossy().into_owned()
+    };
+    let level = match severity {
+        gl::DEBUG_SEVERITY_HIGH => Level::Error,
+        gl::DEBUG_SEVERITY_MEDIUM | gl::DEBUG_SEVERITY_LOW => Level::Warn,
+        _ => Level::Debug,
+    };
+    let source = match source {
+        gl::DEBUG_SOURCE_API => "API",
+        gl::DEBUG_SOURCE_WINDOW_SYSTEM => "window system",
+        gl::DEBUG_SOURCE_SHADER_COMPILER => "shader compiler",
+        gl::DEBUG_SOURCE_THIRD_PARTY => "third party",
+        gl::DEBUG_SOURCE_APPLICATION => "application",
+        _ => "other",
+    };
+    let kind = match kind {
+        gl::DEBUG_TYPE_ERROR => "error",
+        gl::DEBUG_TYPE_DEPRECATED_BEHAVIOR => "deprecated behavior",
+        gl::DEBUG_TYPE_UNDEFINED_BEHAVIOR => "undefined behavior",
+        gl::DEBUG_TYPE_PORTABILITY => "portability",
+        gl::DEBUG_TYPE_PERFORMANCE => "performance",
+        _ => "message",
+    };
+    log!(target: "gl", level, "{} {} {}: {}", source, kind, id, text.trim_end());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn error_names_cover_the_get_error_codes() {
+        assert_eq!(error_name(gl::INVALID_OPERATION), "GL_INVALID_OPERATION");
+        assert_eq!(error_name(gl::OUT_OF_MEMORY), "GL_OUT_OF_MEMORY");
+        assert_eq!(error_name(0xBEEF), "unknown GL error");
+    }
+
+    #[test]
+    fn unloaded_functions_are_reported_by_name() {
+        // the tests never load GL, so every entry point is missing
+        let missing = missing_functions();
+        assert_eq!(missing.len(), REQUIRED_FUNCTIONS.len());
+        assert!(missing.contains(&"glDispatchCompute"));
+        assert_eq!(gl_string(gl::VERSION), "");
+    }
+}
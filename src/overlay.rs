@@ -0,0 +1,275 @@
+use std::ffi::CString;
+use std::{mem, ptr};
+use std::os::raw::c_void;
+use gl::types::{GLfloat, GLint, GLsizei, GLsizeiptr};
+
+// 5x7 bitmap glyphs, one byte per row from the top, bit 4 is the leftmost column.
+// Lowercase letters are drawn with their uppercase glyph, anything unknown as '?'.
+const FONT: [(char, [u8; 7]); 60] = [
+    (' ', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00]),
+    ('0', [0x0E, 0x11, 0x13, 0x15, 0x19, 0x11, 0x0E]),
+    ('1', [0x04, 0x0C, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('2', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x08, 0x1F]),
+    ('3', [0x1F, 0x02, 0x04, 0x02, 0x01, 0x11, 0x0E]),
+    ('4', [0x02, 0x06, 0x0A, 0x12, 0x1F, 0x02, 0x02]),
+    ('5', [0x1F, 0x10, 0x1E, 0x01, 0x01, 0x11, 0x0E]),
+    ('6', [0x06, 0x08, 0x10, 0x1E, 0x11, 0x11, 0x0E]),
+    ('7', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x08, 0x08]),
+    ('8', [0x0E, 0x11, 0x11, 0x0E, 0x11, 0x11, 0x0E]),
+    ('9', [0x0E, 0x11, 0x11, 0x0F, 0x01, 0x02, 0x0C]),
+    ('A', [0x0E, 0x11, 0x11, 0x11, 0x1F, 0x11, 0x11]),
+    ('B', [0x1E, 0x11, 0x11, 0x1E, 0x11, 0x11, 0x1E]),
+    ('C', [0x0E, 0x11, 0x10, 0x10, 0x10, 0x11, 0x0E]),
+    ('D', [0x1C, 0x12, 0x11, 0x11, 0x11, 0x12, 0x1C]),
+    ('E', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x1F]),
+    ('F', [0x1F, 0x10, 0x10, 0x1E, 0x10, 0x10, 0x10]),
+    ('G', [0x0E, 0x11, 0x10, 0x17, 0x11, 0x11, 0x0F]),
+    ('H', [0x11, 0x11, 0x11, 0x1F, 0x11, 0x11, 0x11]),
+    ('I', [0x0E, 0x04, 0x04, 0x04, 0x04, 0x04, 0x0E]),
+    ('J', [0x07, 0x02, 0x02, 0x02, 0x02, 0x12, 0x0C]),
+    ('K', [0x11, 0x12, 0x14, 0x18, 0x14, 0x12, 0x11]),
+    ('L', [0x10, 0x10, 0x10, 0x10, 0x10, 0x10, 0x1F]),
+    ('M', [0x11, 0x1B, 0x15, 0x15, 0x11, 0x11, 0x11]),
+    ('N', [0x11, 0x11, 0x19, 0x15, 0x13, 0x11, 0x11]),
+    ('O', [0x0E, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('P', [0x1E, 0x11, 0x11, 0x1E, 0x10, 0x10, 0x10]),
+    ('Q', [0x0E, 0x11, 0x11, 0x11, 0x15, 0x12, 0x0D]),
+    ('R', [0x1E, 0x11, 0x11, 0x1E, 0x14, 0x12, 0x11]),
+    ('S', [0x0F, 0x10, 0x10, 0x0E, 0x01, 0x01, 0x1E]),
+    ('T', [0x1F, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04]),
+    ('U', [0x11, 0x11, 0x11, 0x11, 0x11, 0x11, 0x0E]),
+    ('V', [0x11, 0x11, 0x11, 0x11, 0x11, 0x0A, 0x04]),
+    ('W', [0x11, 0x11, 0x11, 0x15, 0x15, 0x15, 0x0A]),
+    ('X', [0x11, 0x11, 0x0A, 0x04, 0x0A, 0x11, 0x11]),
+    ('Y', [0x11, 0x11, 0x11, 0x0A, 0x04, 0x04, 0x04]),
+    ('Z', [0x1F, 0x01, 0x02, 0x04, 0x08, 0x10, 0x1F]),
+    ('.', [0x00, 0x00, 0x00, 0x00, 0x00, 0x0C, 0x0C]),
+    (',', [0x00, 0x00, 0x00, 0x00, 0x0C, 0x04, 0x08]),
+    (':', [0x00, 0x0C, 0x0C, 0x00, 0x0C, 0x0C, 0x00]),
+    ('-', [0x00, 0x00, 0x00, 0x1F, 0x00, 0x00, 0x00]),
+    ('+', [0x00, 0x04, 0x04, 0x1F, 0x04, 0x04, 0x00]),
+    ('=', [0x00, 0x00, 0x1F, 0x00, 0x1F, 0x00, 0x00]),
+    ('/', [0x00, 0x01, 0x02, 0x04, 0x08, 0x10, 0x00]),
+    ('%', [0x18, 0x19, 0x02, 0x04, 0x08, 0x13, 0x03]),
+    ('(', [0x02, 0x04, 0x08, 0x08, 0x08, 0x04, 0x02]),
+    (')', [0x08, 0x04, 0x02, 0x02, 0x02, 0x04, 0x08]),
+    ('[', [0x0E, 0x08, 0x08, 0x08, 0x08, 0x08, 0x0E]),
+    (']', [0x0E, 0x02, 0x02, 0x02, 0x02, 0x02, 0x0E]),
+    ('<', [0x02, 0x04, 0x08, 0x10, 0x08, 0x04, 0x02]),
+    ('>', [0x08, 0x04, 0x02, 0x01, 0x02, 0x04, 0x08]),
+    ('_', [0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x1F]),
+    ('*', [0x00, 0x04, 0x15, 0x0E, 0x15, 0x04, 0x00]),
+    ('#', [0x0A, 0x0A, 0x1F, 0x0A, 0x1F, 0x0A, 0x0A]),
+    ('!', [0x04, 0x04, 0x04, 0x04, 0x04, 0x00, 0x04]),
+    ('?', [0x0E, 0x11, 0x01, 0x02, 0x04, 0x00, 0x04]),
+    ('\'', [0x0C, 0x04, 0x08, 0x00, 0x00, 0x00, 0x00]),
+    ('"', [0x0A, 0x0A, 0x0A, 0x00, 0x00, 0x00, 0x00]),
+    ('|', [0x04, 0x04, 0x04, 0x04, 0x04, 0x04, 0x04]),
+    // fully lit cell used for solid backing rectangles
+    ('\u{7f}', [0x1F, 0x1F, 0x1F, 0x1F, 0x1F, 0x1F, 0x1F]),
+];
+const GLYPH_CELL_W: usize = 6; // 5 columns + 1 spacing
+const GLYPH_CELL_H: usize = 8; // 7 rows + 1 spacing
+
+/// Screen-space text and flat rectangles drawn on top of the frame at window resolution,
+/// batched into one dynamic vertex buffer per frame.
+pub struct TextOverlay {
+    pub program: gl::types::GLuint,
+    pub vao: gl::types::GLuint,
+    pub vbo: gl::types::GLuint,
+    pub atlas: gl::types::GLuint,
+    pub vertices: Vec<GLfloat>, // x, y, u, v, r, g, b, a per vertex
+}
+
+impl TextOverlay {
+    pub fn new(program: gl::types::GLuint) -> Self {
+        // bake the glyph table into a single-row R8 atlas
+        let atlas_w = FONT.len() * GLYPH_CELL_W;
+        let mut pixels = vec![0u8; atlas_w * GLYPH_CELL_H];
+        for (i, (_, rows)) in FONT.iter().enumerate() {
+            for (y, row) in rows.iter().enumerate() {
+                for x in 0..5 {
+                    if row & (0x10 >> x) != 0 {
+                        pixels[y * atlas_w + i * GLYPH_CELL_W + x] = 255;
+                    }
+                }
+            }
+        }
+
+        let (mut vao, mut vbo, mut atlas) = (0, 0, 0);
+        unsafe {
+            gl::GenVertexArrays(1, &mut vao);
+            gl::GenBuffers(1, &mut vbo);
+            gl::BindVertexArray(vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, vbo);
+            let stride = (8 * mem::size_of::<GLfloat>()) as GLsizei;
+            gl::VertexAttribPointer(0, 2, gl::FLOAT, gl::FALSE, stride, ptr::null());
+            gl::EnableVertexAttribArray(0);
+            gl::VertexAttribPointer(1, 2, gl::FLOAT, gl::FALSE, stride, (2 * mem::size_of::<GLfloat>()) as *const c_void);
+            gl::EnableVertexAttribArray(1);
+            gl::VertexAttribPointer(2, 4, gl::FLOAT, gl::FALSE, stride, (4 * mem::size_of::<GLfloat>()) as *const c_void);
+            gl::EnableVertexAttribArray(2);
+
+            gl::GenTextures(1, &mut atlas);
+            gl::BindTexture(gl::TEXTURE_2D, atlas);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MIN_FILTER, gl::NEAREST as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_MAG_FILTER, gl::NEAREST as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_S, gl::CLAMP_TO_EDGE as GLint);
+            gl::TexParameteri(gl::TEXTURE_2D, gl::TEXTURE_WRAP_T, gl::CLAMP_TO_EDGE as GLint);
+            gl::PixelStorei(gl::UNPACK_ALIGNMENT, 1);
+            gl::TexImage2D(gl::TEXTURE_2D, 0, gl::R8 as GLint, atlas_w as GLsizei, GLYPH_CELL_H as GLsizei, 0,
+                           gl::RED, gl::UNSIGNED_BYTE, pixels.as_ptr() as *const c_void);
+        }
+
+        TextOverlay { program, vao, vbo, atlas, vertices: Vec::new() }
+    }
+
+    fn glyph_index(ch: char) -> usize {
+        let ch = ch.to_ascii_uppercase();
+        FONT.iter().position(|(glyph, _)| *glyph == ch)
+            .unwrap_or_else(|| FONT.iter().position(|(glyph, _)| *glyph == '?').unwrap())
+    }
+
+    fn push_quad(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, glyph: usize, color: [f32; 4]) {
+        self.push_corners([(x0, y0), (x0, y1), (x1, y1), (x1, y0)], glyph, color);
+    }
+
+    // Two triangles of a glyph mapped onto any quad, corners in the order top left, bottom left,
+    // bottom right, top right of the glyph
+    fn push_corners(&mut self, corners: [(f32, f32); 4], glyph: usize, color: [f32; 4]) {
+        self.push_shaded(corners, glyph, [color; 4]);
+    }
+
+    // push_corners with a color at every corner, which the triangles blend between. The triangles
+    // are wound counter-clockwise on screen whichever way the corners go round, so back face
+    // culling keeps them.
+    fn push_shaded(&mut self, corners: [(f32, f32); 4], glyph: usize, colors: [[f32; 4]; 4]) {
+        let atlas_w = (FONT.len() * GLYPH_CELL_W) as f32;
+        let u0 = (glyph * GLYPH_CELL_W) as f32 / atlas_w;
+        let u1 = (glyph * GLYPH_CELL_W + 5) as f32 / atlas_w;
+        let v1 = 7.0 / GLYPH_CELL_H as f32;
+        let uvs = [(u0, 0.0), (u0, v1), (u1, v1), (u1, 0.0)];
+        // window y points down, so counter-clockwise on screen is clockwise in window pixels
+        let area: f32 = (0..4).map(|i| {
+            let ((x0, y0), (x1, y1)) = (corners[i], corners[(i + 1) % 4]);
+            x0 * y1 - x1 * y0
+        }).sum();
+        let order = if area <= 0.0 { [0, 1, 2, 0, 2, 3] } else { [0, 2, 1, 0, 3, 2] };
+        for i in order {
+            let ((x, y), (u, v), color) = (corners[i], uvs[i], colors[i]);
+            self.vertices.extend_from_slice(&[x, y, u, v, color[0], color[1], color[2], color[3]]);
+        }
+    }
+
+    /// Pixel size of `text` at `scale`, for laying out backing rectangles.
+    pub fn measure(text: &str, scale: f32) -> (f32, f32) {
+        let lines = text.lines().count().max(1) as f32;
+        let columns = text.lines().map(|line| line.chars().count()).max().unwrap_or(0) as f32;
+        (columns * GLYPH_CELL_W as f32 * scale, lines * (GLYPH_CELL_H + 1) as f32 * scale)
+    }
+
+    /// Queues `text` with its top-left corner at (x, y) window pixels, `scale` pixels per font dot.
+    pub fn text(&mut self, x: f32, y: f32, scale: f32, color: [f32; 4], text: &str) {
+        for (line_index, line) in text.lines().enumerate() {
+            let top = y + line_index as f32 * (GLYPH_CELL_H + 1) as f32 * scale;
+            for (column, ch) in line.chars().enumerate() {
+                if ch == ' ' {
+                    continue;
+                }
+                let left = x + column as f32 * GLYPH_CELL_W as f32 * scale;
+                self.push_quad(left, top, left + 5.0 * scale, top + 7.0 * scale, TextOverlay::glyph_index(ch), color);
+            }
+        }
+    }
+
+    pub fn rect(&mut self, x: f32, y: f32, w: f32, h: f32, color: [f32; 4]) {
+        self.push_quad(x, y, x + w, y + h, FONT.len() - 1, color);
+    }
+
+    /// A straight line `width` pixels wide between two window points.
+    pub fn line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, width: f32, color: [f32; 4]) {
+        let length = (x1 - x0).hypot(y1 - y0);
+        if length == 0.0 {
+            return;
+        }
+        // half the width along the normal of the line
+        let (nx, ny) = ((y0 - y1) / length * width / 2.0, (x1 - x0) / length * width / 2.0);
+        self.push_corners([(x0 + nx, y0 + ny), (x0 - nx, y0 - ny), (x1 - nx, y1 - ny), (x1 + nx, y1 + ny)], FONT.len() - 1, color);
+    }
+
+    /// `line` with anti-aliased edges: a solid core `width` pixels wide and on either side a
+    /// one pixel fringe that fades out, so thin lines stay smooth at any angle.
+    pub fn smooth_line(&mut self, x0: f32, y0: f32, x1: f32, y1: f32, width: f32, color: [f32; 4]) {
+        let length = (x1 - x0).hypot(y1 - y0);
+        if length == 0.0 {
+            return;
+        }
+        let (nx, ny) = ((y0 - y1) / length, (x1 - x0) / length);
+        let core = width / 2.0;
+        let clear = [color[0], color[1], color[2], 0.0];
+        self.line(x0, y0, x1, y1, width, color);
+        for side in [1.0, -1.0] {
+            let (inner_x, inner_y) = (nx * core * side, ny * core * side);
+            let (outer_x, outer_y) = (nx * (core + 1.0) * side, ny * (core + 1.0) * side);
+            self.push_shaded([(x0 + outer_x, y0 + outer_y), (x0 + inner_x, y0 + inner_y), (x1 + inner_x, y1 + inner_y), (x1 + outer_x, y1 + outer_y)],
+                             FONT.len() - 1, [clear, color, color, clear]);
+        }
+    }
+
+    /// Draws everything queued since the last call and clears the queue.
+    pub fn draw(&mut self, win_width: u32, win_height: u32) {
+        if self.vertices.is_empty() {
+            return;
+        }
+        unsafe {
+            gl::UseProgram(self.program);
+            let name = CString::new("screenSize").unwrap();
+            gl::Uniform2f(gl::GetUniformLocation(self.program, name.as_ptr()), win_width as f32, win_height as f32);
+            let name = CString::new("atlas").unwrap();
+            gl::Uniform1i(gl::GetUniformLocation(self.program, name.as_ptr()), 0);
+            gl::ActiveTexture(gl::TEXTURE0);
+            gl::BindTexture(gl::TEXTURE_2D, self.atlas);
+
+            gl::BindVertexArray(self.vao);
+            gl::BindBuffer(gl::ARRAY_BUFFER, self.vbo);
+            gl::BufferData(gl::ARRAY_BUFFER, (self.vertices.len() * mem::size_of::<GLfloat>()) as GLsizeiptr,
+                           self.vertices.as_ptr() as *const c_void, gl::STREAM_DRAW);
+
+            gl::Enable(gl::BLEND);
+            gl::BlendFunc(gl::SRC_ALPHA, gl::ONE_MINUS_SRC_ALPHA);
+            gl::DrawArrays(gl::TRIANGLES, 0, (self.vertices.len() / 8) as GLsizei);
+            gl::Disable(gl::BLEND);
+        }
+        self.vertices.clear();
+    }
+
+    pub fn delete(&self) {
+        unsafe {
+            if self.program != 0 { gl::DeleteProgram(self.program); }
+            if self.vao != 0 { gl::DeleteVertexArrays(1, &self.vao); }
+            if self.vbo != 0 { gl::DeleteBuffers(1, &self.vbo); }
+            if self.atlas != 0 { gl::DeleteTextures(1, &self.atlas); }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_triangle_winds_counter_clockwise_on_screen() {
+        let mut overlay = TextOverlay { program: 0, vao: 0, vbo: 0, atlas: 0, vertices: Vec::new() };
+        overlay.rect(10.0, 10.0, 40.0, 20.0, [1.0; 4]);
+        overlay.text(0.0, 0.0, 2.0, [1.0; 4], "Rs");
+        for (x1, y1) in [(90.0, 30.0), (10.0, 30.0), (50.0, 90.0), (50.0, -20.0)] {
+            overlay.smooth_line(50.0, 30.0, x1, y1, 2.0, [1.0; 4]);
+        }
+        // to NDC as text_vs.glsl does for a 100 x 100 window
+        let ndc: Vec<(f32, f32)> = overlay.vertices.chunks(8).map(|v| (v[0] / 50.0 - 1.0, 1.0 - v[1] / 50.0)).collect();
+        for t in ndc.chunks(3) {
+            let (a, b, c) = (t[0], t[1], t[2]);
+            assert!((b.0 - a.0) * (c.1 - a.1) - (b.1 - a.1) * (c.0 - a.0) > 0.0, "{t:?}");
+        }
+    }
+}
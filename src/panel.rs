@@ -0,0 +1,287 @@
+use crate::engine::{MAX_AA_SAMPLES, MAX_EXPOSURE, MAX_INTEGRATION_STEPS, MAX_RESOLUTION_SCALE, MAX_SPIN,
+    MIN_EXPOSURE, MIN_RESOLUTION_SCALE};
+use crate::overlay::TextOverlay;
+
+/// A parameter the control panel has a slider for, the engine maps each onto its setter.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Param {
+    Mass, // solar masses
+    Spin,
+    DiskInner, // Rs
+    DiskOuter, // Rs
+    DiskTemperature, // kelvin at full red in the temperature profile
+    Steps,
+    Tolerance,
+    AaSamples,
+    ResolutionScale,
+    Exposure, // EV
+    BloomThreshold,
+    BloomIntensity,
+    RedshiftExaggeration,
+    GridWarp,
+}
+
+/// One row of the panel: a named track from `min` to `max`, evenly spaced in the value or, for
+/// the ones spanning orders of magnitude, in its logarithm.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Slider {
+    pub param: Param,
+    pub label: &'static str,
+    pub min: f64,
+    pub max: f64,
+    pub log: bool,
+    pub integer: bool,
+}
+
+impl Slider {
+    const fn new(param: Param, label: &'static str, min: f64, max: f64, log: bool, integer: bool) -> Self {
+        Slider { param, label, min, max, log, integer }
+    }
+
+    /// Where `value` sits along the track, in [0, 1].
+    pub fn fraction(&self, value: f64) -> f64 {
+        let fraction = if self.log {
+            (value / self.min).ln() / (self.max / self.min).ln()
+        } else {
+            (value - self.min) / (self.max - self.min)
+        };
+        fraction.clamp(0.0, 1.0)
+    }
+
+    /// The value at `fraction` of the way along the track.
+    pub fn value_at(&self, fraction: f64) -> f64 {
+        let fraction = fraction.clamp(0.0, 1.0);
+        let value = if self.log {
+            self.min * (self.max / self.min).powf(fraction)
+        } else {
+            self.min + (self.max - self.min) * fraction
+        };
+        if self.integer { value.round() } else { value }
+    }
+
+    fn format(&self, value: f64) -> String {
+        match self {
+            Slider { integer: true, .. } => format!("{}", value),
+            Slider { log: true, .. } => format!("{:.2E}", value),
+            _ => format!("{:.3}", value),
+        }
+    }
+}
+
+pub const SLIDERS: [Slider; 14] = [
+    Slider::new(Param::Mass, "MASS (SOLAR)", 1.0, 1e11, true, false),
+    Slider::new(Param::Spin, "SPIN", 0.0, MAX_SPIN, false, false),
+    Slider::new(Param::DiskInner, "DISK INNER (RS)", 1.0, 50.0, false, false),
+    Slider::new(Param::DiskOuter, "DISK OUTER (RS)", 1.0, 50.0, false, false),
+    Slider::new(Param::DiskTemperature, "DISK TEMP (K)", 1000.0, 40000.0, false, true),
+    Slider::new(Param::Steps, "STEPS", 100.0, MAX_INTEGRATION_STEPS as f64, true, true),
+    Slider::new(Param::Tolerance, "TOLERANCE", 1e-7, 1e-1, true, false),
+    Slider::new(Param::AaSamples, "AA SAMPLES", 1.0, MAX_AA_SAMPLES as f64, true, true),
+    Slider::new(Param::ResolutionScale, "RESOLUTION", MIN_RESOLUTION_SCALE as f64, MAX_RESOLUTION_SCALE as f64, false, false),
+    Slider::new(Param::Exposure, "EXPOSURE (EV)", MIN_EXPOSURE as f64, MAX_EXPOSURE as f64, false, false),
+    Slider::new(Param::BloomThreshold, "BLOOM THRESHOLD", 0.0, 4.0, false, false),
+    Slider::new(Param::BloomIntensity, "BLOOM INTENSITY", 0.0, 4.0, false, false),
+    Slider::new(Param::RedshiftExaggeration, "REDSHIFT X", 1.0, 16.0, false, false),
+    Slider::new(Param::GridWarp, "GRID WARP", 0.0, 16.0, false, false),
+];
+
+/// How the bracket keys move a parameter: by adding `amount`, or for the ones spanning orders of
+/// magnitude by multiplying with it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stepping {
+    pub param: Param,
+    pub amount: f64,
+    pub log: bool,
+}
+
+impl Stepping {
+    const fn new(param: Param, amount: f64, log: bool) -> Self {
+        Stepping { param, amount, log }
+    }
+}
+
+/// The parameters `[` and `]` step, in the order Shift + `[` and `]` cycle through them.
+pub const STEPPED: [Stepping; 6] = [
+    Stepping::new(Param::Mass, 10.0, true),
+    Stepping::new(Param::Spin, 0.05, false),
+    Stepping::new(Param::DiskInner, 0.1, false),
+    Stepping::new(Param::DiskOuter, 0.5, false),
+    Stepping::new(Param::Exposure, 0.5, false),
+    Stepping::new(Param::Steps, 2.0, true),
+];
+
+/// The active parameter, the one of STEPPED the bracket keys move. A parameter joins them with
+/// a row in STEPPED, the engine already reads and sets every `Param`.
+#[derive(Debug, Default)]
+pub struct ParamCursor {
+    index: usize,
+}
+
+impl ParamCursor {
+    pub fn active(&self) -> &'static Stepping {
+        &STEPPED[self.index]
+    }
+
+    /// The slider of the active parameter, whose range and label the steps share.
+    pub fn slider(&self) -> &'static Slider {
+        SLIDERS.iter().find(|slider| slider.param == self.active().param).unwrap()
+    }
+
+    /// Makes the parameter `steps` rows on in STEPPED the active one, wrapping around.
+    pub fn cycle(&mut self, steps: i32) {
+        self.index = (self.index as i32 + steps).rem_euclid(STEPPED.len() as i32) as usize;
+    }
+
+    /// `value` of the active parameter moved `steps` steps, held to its slider's range.
+    pub fn step(&self, value: f64, steps: i32) -> f64 {
+        let Stepping { amount, log, .. } = *self.active();
+        let slider = self.slider();
+        let value = if log { value * amount.powi(steps) } else { value + amount * steps as f64 };
+        let value = value.clamp(slider.min, slider.max);
+        if slider.integer { value.round() } else { value }
+    }
+
+    /// The active parameter at `value` the way the panel labels it, like "SPIN 0.350".
+    pub fn describe(&self, value: f64) -> String {
+        format!("{} {}", self.slider().label, self.slider().format(value))
+    }
+}
+
+const WIDTH: f32 = 420.0;
+const MARGIN: f32 = 8.0;
+const ROW_HEIGHT: f32 = 26.0;
+const TEXT_SCALE: f32 = 2.0;
+const TRACK_LEFT: f32 = 200.0; // from the panel's left edge, the label goes before it
+const TRACK_HEIGHT: f32 = 6.0;
+
+/// The slider panel in the top right corner, toggled with Tab. Positions are window pixels with
+/// the origin at the top left, like the overlay's. While a slider is held the panel keeps the
+/// mouse, so the drag doesn't orbit the camera as well.
+#[derive(Debug, Default)]
+pub struct Panel {
+    pub visible: bool,
+    dragging: Option<usize>, // the index into SLIDERS being dragged
+}
+
+impl Panel {
+    // Top left corner of the panel
+    fn origin(win_width: u32) -> (f32, f32) {
+        (win_width as f32 - WIDTH - MARGIN, MARGIN)
+    }
+
+    fn height() -> f32 {
+        SLIDERS.len() as f32 * ROW_HEIGHT + MARGIN
+    }
+
+    fn track(win_width: u32) -> (f32, f32) {
+        let (x, _) = Panel::origin(win_width);
+        (x + TRACK_LEFT, x + WIDTH - MARGIN)
+    }
+
+    /// Whether (x, y) falls on the panel, where the mouse belongs to it.
+    pub fn contains(&self, win_width: u32, x: f32, y: f32) -> bool {
+        let (left, top) = Panel::origin(win_width);
+        self.visible && x >= left && x <= left + WIDTH && y >= top && y <= top + Panel::height()
+    }
+
+    pub fn dragging(&self) -> bool {
+        self.dragging.is_some()
+    }
+
+    /// Grabs the slider in the row under (x, y). Returns it with the fraction of its track at x.
+    pub fn press(&mut self, win_width: u32, x: f32, y: f32) -> Option<(&'static Slider, f64)> {
+        if !self.contains(win_width, x, y) {
+            return None;
+        }
+        let (_, top) = Panel::origin(win_width);
+        let row = ((y - top - MARGIN / 2.0) / ROW_HEIGHT).floor();
+        if row < 0.0 || row as usize >= SLIDERS.len() {
+            return None;
+        }
+        self.dragging = Some(row as usize);
+        self.drag(win_width, x)
+    }
+
+    /// The held slider and the fraction of its track at x, None when nothing is held.
+    pub fn drag(&self, win_width: u32, x: f32) -> Option<(&'static Slider, f64)> {
+        let (left, right) = Panel::track(win_width);
+        self.dragging.map(|row| (&SLIDERS[row], ((x - left) / (right - left)) as f64))
+    }
+
+    /// Lets go of the held slider, returns whether there was one.
+    pub fn release(&mut self) -> bool {
+        self.dragging.take().is_some()
+    }
+
+    /// Queues the panel with `values[i]` set on `SLIDERS[i]`.
+    pub fn queue(&self, overlay: &mut TextOverlay, win_width: u32, values: &[f64]) {
+        let (x, y) = Panel::origin(win_width);
+        let (left, right) = Panel::track(win_width);
+        overlay.rect(x, y, WIDTH, Panel::height(), [0.0, 0.0, 0.0, 0.6]);
+        for (row, (slider, &value)) in SLIDERS.iter().zip(values).enumerate() {
+            let top = y + MARGIN + row as f32 * ROW_HEIGHT;
+            let held = self.dragging == Some(row);
+            let color = if held { [1.0, 0.8, 0.3, 1.0] } else { [1.0, 1.0, 1.0, 1.0] };
+            overlay.text(x + MARGIN, top, TEXT_SCALE, color, slider.label);
+            // the value sits on the track, above the fill
+            let fill = (right - left) * slider.fraction(value) as f32;
+            let track_top = top + 7.0 * TEXT_SCALE - TRACK_HEIGHT;
+            overlay.rect(left, track_top, right - left, TRACK_HEIGHT, [1.0, 1.0, 1.0, 0.2]);
+            overlay.rect(left, track_top, fill, TRACK_HEIGHT, [0.4, 0.6, 1.0, 0.9]);
+            overlay.rect(left + fill - 2.0, track_top - 3.0, 4.0, TRACK_HEIGHT + 6.0, color);
+            overlay.text(left, top - 4.0, 1.0, color, &slider.format(value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sliders_map_values_onto_their_tracks() {
+        let mass = SLIDERS[0];
+        assert!((mass.fraction(1e5) - 5.0 / 11.0).abs() < 1e-12);
+        assert!((mass.value_at(mass.fraction(4.3e6)) - 4.3e6).abs() < 1e-3);
+        assert_eq!(mass.fraction(1e20), 1.0);
+        let warp = SLIDERS.iter().find(|slider| slider.param == Param::GridWarp).unwrap();
+        assert_eq!(warp.value_at(0.25), 4.0);
+        let steps = SLIDERS.iter().find(|slider| slider.param == Param::Steps).unwrap();
+        assert_eq!(steps.value_at(steps.fraction(30_000.0)), 30_000.0);
+    }
+
+    #[test]
+    fn the_cursor_steps_the_active_parameter_within_its_slider() {
+        let mut cursor = ParamCursor::default();
+        assert_eq!(cursor.active().param, Param::Mass);
+        assert_eq!(cursor.step(4.3e6, 1), 4.3e7);
+        assert_eq!(cursor.step(1e10, 5), 1e11);
+        cursor.cycle(1);
+        assert_eq!(cursor.describe(cursor.step(0.3, 1)), "SPIN 0.350");
+        assert_eq!(cursor.step(0.0, -1), 0.0);
+        cursor.cycle(-2);
+        assert_eq!(cursor.active().param, Param::Steps);
+        assert_eq!(cursor.step(60_000.0, 1), 120_000.0);
+        assert_eq!(cursor.step(150_000.0, 1), MAX_INTEGRATION_STEPS as f64);
+        // every stepped parameter has a slider to take its range from
+        for _ in STEPPED {
+            cursor.cycle(1);
+            cursor.slider();
+        }
+    }
+
+    #[test]
+    fn presses_pick_the_row_under_the_cursor() {
+        let mut panel = Panel { visible: true, ..Panel::default() };
+        let (left, right) = Panel::track(1000);
+        let y = MARGIN + MARGIN + ROW_HEIGHT * 1.5; // the middle of the second row
+        let (slider, fraction) = panel.press(1000, (left + right) / 2.0, y).unwrap();
+        assert_eq!(slider.param, Param::Spin);
+        assert!((fraction - 0.5).abs() < 1e-6);
+        // the drag follows the cursor past the panel's edge, clamped by the slider
+        assert!(panel.drag(1000, 0.0).unwrap().1 < 0.0);
+        assert!(panel.release());
+        assert!(!panel.release());
+        assert!(panel.press(1000, 10.0, y).is_none());
+    }
+}
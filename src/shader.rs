@@ -0,0 +1,194 @@
+use std::ffi::CString;
+use std::io::Read;
+use std::ptr;
+use gl::types::{GLchar, GLint, GLsizei};
+use log::debug;
+use crate::engine::EngineError;
+
+pub fn read(file: &str)->Result<String, std::io::Error>{
+    let mut content = String::new();
+    std::fs::File::open(file)?.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+pub fn read_shader(path: &str) -> Result<String, EngineError> {
+    read(path).map_err(|source| EngineError::Io { path: path.to_string(), source })
+}
+
+pub struct ShaderPaths {
+    pub main_vs: String,
+    pub main_fs: String,
+    pub bloom_fs: String,
+    pub flare_fs: String,
+    pub grid_vs: String,
+    pub grid_fs: String,
+    pub text_vs: String,
+    pub text_fs: String,
+    pub compute: String,
+}
+
+impl Default for ShaderPaths {
+    fn default() -> Self {
+        ShaderPaths {
+            main_vs: "./shaders/main_vs.glsl".to_string(),
+            main_fs: "./shaders/main_fs.glsl".to_string(),
+            bloom_fs: "./shaders/bloom_fs.glsl".to_string(),
+            flare_fs: "./shaders/flare_fs.glsl".to_string(),
+            grid_vs: "./shaders/grid_vs.glsl".to_string(),
+            grid_fs: "./shaders/grid_fs.glsl".to_string(),
+            text_vs: "./shaders/text_vs.glsl".to_string(),
+            text_fs: "./shaders/text_fs.glsl".to_string(),
+            compute: "./shaders/geodesic_cs.glsl".to_string(),
+        }
+    }
+}
+
+impl ShaderPaths {
+    #[cfg(feature = "hot-reload")]
+    pub fn all(&self) -> [&str; 9] {
+        [&self.main_vs, &self.main_fs, &self.bloom_fs, &self.flare_fs, &self.grid_vs, &self.grid_fs, &self.text_vs, &self.text_fs, &self.compute]
+    }
+
+    // Modification times of every shader source, used to detect edits on disk
+    #[cfg(feature = "hot-reload")]
+    pub fn stamps(&self) -> Vec<Option<std::time::SystemTime>> {
+        self.all().iter().map(|path| std::fs::metadata(path).and_then(|meta| meta.modified()).ok()).collect()
+    }
+}
+
+/// A compute shader the viewer can switch to, see `Engine::load_compute_variant`. Every variant
+/// reads the same blocks and uniforms as geodesic_cs.glsl and writes the same image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ComputeVariant {
+    pub name: String,
+    pub path: String,
+}
+
+impl ComputeVariant {
+    /// The variant named after the file at `path`, without its `_cs.glsl` or `.glsl` ending.
+    pub fn from_path(path: &str) -> Self {
+        let file = std::path::Path::new(path).file_name().map_or(path.to_string(), |name| name.to_string_lossy().into_owned());
+        let name = file.strip_suffix("_cs.glsl").or_else(|| file.strip_suffix(".glsl")).unwrap_or(&file);
+        ComputeVariant { name: name.to_string(), path: path.to_string() }
+    }
+}
+
+fn shader_stage_name(shader_type: u32) -> &'static str {
+    match shader_type {
+        gl::VERTEX_SHADER => "VERTEX",
+        gl::FRAGMENT_SHADER => "FRAGMENT",
+        gl::COMPUTE_SHADER => "COMPUTE",
+        _ => "UNKNOWN",
+    }
+}
+
+// Reads a shader or program info log sized by GL_INFO_LOG_LENGTH, so long driver messages aren't cut off
+fn info_log(object: gl::types::GLuint, is_program: bool) -> String {
+    unsafe {
+        let mut log_len: GLint = 0;
+        if is_program {
+            gl::GetProgramiv(object, gl::INFO_LOG_LENGTH, &mut log_len);
+        } else {
+            gl::GetShaderiv(object, gl::INFO_LOG_LENGTH, &mut log_len);
+        }
+        if log_len <= 0 {
+            return String::new();
+        }
+
+        let mut buf: Vec<u8> = vec![0; log_len as usize];
+        let mut written: GLsizei = 0;
+        if is_program {
+            gl::GetProgramInfoLog(object, log_len, &mut written, buf.as_mut_ptr() as *mut GLchar);
+        } else {
+            gl::GetShaderInfoLog(object, log_len, &mut written, buf.as_mut_ptr() as *mut GLchar);
+        }
+        buf.truncate(written.max(0) as usize); // drop the trailing null character
+        String::from_utf8_lossy(&buf).into_owned()
+    }
+}
+
+pub fn compile_shader(shader_type: u32, shader_source:&str, path: &str) -> Result<u32, EngineError>{
+    unsafe {
+        let shader = gl::CreateShader(shader_type);
+        let c_str = CString::new(shader_source.as_bytes()).unwrap();
+        gl::ShaderSource(shader, 1, &c_str.as_ptr(), ptr::null());
+        gl::CompileShader(shader);
+
+        // Check for shader compilation errors
+        let mut success = i32::from(gl::FALSE);
+        gl::GetShaderiv(shader, gl::COMPILE_STATUS, &mut success);
+        if success != i32::from(gl::TRUE) {
+            let log = info_log(shader, false);
+            gl::DeleteShader(shader);
+            return Err(EngineError::ShaderCompile(format!("ERROR::SHADER::{}::COMPILATION_FAILED ({})\n{}",
+                shader_stage_name(shader_type), path, log)));
+        }
+        debug!("Compiled {} shader {}", shader_stage_name(shader_type).to_lowercase(), path);
+
+        Ok(shader)
+    }
+}
+pub fn create_shader_program(verter_path: &str, frag_path: &str)-> Result<gl::types::GLuint, EngineError> {
+    let vertex_shader_source = read_shader(verter_path)?;
+    let fragment_shader_source = read_shader(frag_path)?;
+    unsafe {
+        // vertex shader
+        let vertex_shader = compile_shader(gl::VERTEX_SHADER, vertex_shader_source.as_str(), verter_path)?;
+        // fragment shader
+        let fragment_shader = match compile_shader(gl::FRAGMENT_SHADER, fragment_shader_source.as_str(), frag_path) {
+            Ok(shader) => shader,
+            Err(error) => {
+                gl::DeleteShader(vertex_shader);
+                return Err(error);
+            }
+        };
+        let sharder_program = gl::CreateProgram();
+        gl::AttachShader(sharder_program, vertex_shader);
+        gl::AttachShader(sharder_program, fragment_shader);
+
+        let mut success = i32::from(gl::FALSE);
+        gl::LinkProgram(sharder_program);
+        gl::GetProgramiv(sharder_program, gl::LINK_STATUS, &mut success);
+        if success != i32::from(gl::TRUE) {
+            let log = info_log(sharder_program, true);
+            gl::DeleteProgram(sharder_program);
+            gl::DeleteShader(vertex_shader);
+            gl::DeleteShader(fragment_shader);
+            return Err(EngineError::ShaderLink(format!("{} + {}\n{}", verter_path, frag_path, log)));
+        }
+
+        gl::DeleteShader(vertex_shader);
+        gl::DeleteShader(fragment_shader);
+        debug!("Linked {} + {}", verter_path, frag_path);
+
+        Ok(sharder_program)
+    }
+}
+
+pub fn create_compute_program(path: &str) -> Result<gl::types::GLuint, EngineError> {
+    // 1) read GLSL source
+    let src = read_shader(path)?;
+
+    // 2) compile
+    let cs = compile_shader(gl::COMPUTE_SHADER, src.as_str(), path)?;
+
+    // 3) link
+    let mut success = i32::from(gl::FALSE);
+    let compute_program = unsafe {
+        let prog = gl::CreateProgram();
+        gl::AttachShader(prog, cs);
+        gl::LinkProgram(prog);
+        gl::GetProgramiv(prog, gl::LINK_STATUS, &mut success);
+        if success != i32::from(gl::TRUE) {
+            let log = info_log(prog, true);
+            gl::DeleteProgram(prog);
+            gl::DeleteShader(cs);
+            return Err(EngineError::ShaderLink(format!("{}\n{}", path, log)));
+        }
+        gl::DeleteShader(cs);
+        prog
+    };
+    debug!("Linked {}", path);
+
+    Ok(compute_program)
+}
@@ -0,0 +1,251 @@
+use glm::{vec3, Vec3};
+use crate::config::parse_toml;
+use crate::engine::EngineError;
+use crate::shader::read;
+
+/// A camera pose the timeline passes through. Angles are in radians and lengths in meters, like
+/// the matching `Camera` fields.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraKeyframe {
+    pub time: f64, // seconds from the start of the timeline
+    pub azimuth: f64,
+    pub elevation: f64,
+    pub radius: f64,
+    pub target: Vec3,
+}
+
+/// The pose a timeline yields at some time between its keyframes.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InterpolatedState {
+    pub azimuth: f64,
+    pub elevation: f64,
+    pub radius: f64,
+    pub target: Vec3,
+}
+
+/// Scripted camera move through a list of keyframes.
+///
+/// Every channel is interpolated with a cubic Hermite spline whose tangents are the Catmull-Rom
+/// slopes between the neighbouring keyframes, so the speed carries smoothly through each keyframe.
+/// The first and last keyframe get a zero tangent and ease in and out, unless the timeline loops:
+/// then it should end on the pose it starts with, allowing the azimuth to end whole turns further
+/// along, and the tangents wrap around the seam. The radius is interpolated on a log scale so
+/// zooms over orders of magnitude keep an even pace.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CameraTimeline {
+    keyframes: Vec<CameraKeyframe>, // sorted by time
+    pub looping: bool,
+    pub fps: u32, // frame rate of offline recordings
+}
+
+impl CameraTimeline {
+    pub fn new(mut keyframes: Vec<CameraKeyframe>, looping: bool, fps: u32) -> Result<Self, String> {
+        keyframes.sort_by(|a, b| a.time.total_cmp(&b.time));
+        if keyframes.is_empty() {
+            return Err("a timeline needs at least one keyframe".to_string());
+        }
+        if let Some(pair) = keyframes.windows(2).find(|pair| pair[0].time == pair[1].time) {
+            return Err(format!("two keyframes share the time {}", pair[0].time));
+        }
+        if let Some(keyframe) = keyframes.iter().find(|keyframe| keyframe.radius <= 0.0) {
+            return Err(format!("the keyframe at {} s has a non-positive radius", keyframe.time));
+        }
+        if looping && keyframes.len() < 2 {
+            return Err("a looping timeline needs at least two keyframes".to_string());
+        }
+        Ok(CameraTimeline { keyframes, looping, fps: fps.max(1) })
+    }
+
+    /// Reads a timeline file: top-level `loop` and `fps` keys followed by one `[[keyframe]]`
+    /// table per keyframe holding `time`, `azimuth`, `elevation`, `radius` and an optional
+    /// `target = [x, y, z]`.
+    pub fn load(path: &str) -> Result<Self, EngineError> {
+        let src = read(path).map_err(|source| EngineError::Io { path: path.to_string(), source })?;
+        CameraTimeline::parse(&src).map_err(|message| EngineError::Config { path: path.to_string(), message })
+    }
+
+    pub fn parse(src: &str) -> Result<Self, String> {
+        let mut looping = false;
+        let mut fps = 30;
+        let mut keyframes: Vec<[Option<f64>; 4]> = Vec::new(); // time, azimuth, elevation, radius
+        let mut targets: Vec<Vec3> = Vec::new();
+        for (key, value, line) in parse_toml(src)? {
+            let number = || value.as_number().ok_or_else(|| format!("line {}: `{}` must be a number", line, key));
+            match key.split('.').collect::<Vec<_>>().as_slice() {
+                ["loop"] => looping = value.as_bool().ok_or_else(|| format!("line {}: `loop` must be true or false", line))?,
+                ["fps"] => {
+                    fps = value.as_u32().filter(|n| *n > 0).ok_or_else(|| format!("line {}: `fps` must be a positive integer", line))?
+                }
+                ["keyframe", index, field] => {
+                    // only `[[keyframe]]` tables are numbered
+                    let Ok(index) = index.parse::<usize>() else {
+                        return Err(format!("line {}: unknown key `{}`", line, key));
+                    };
+                    if keyframes.len() <= index {
+                        keyframes.resize(index + 1, [None; 4]);
+                        targets.resize(index + 1, vec3(0.0, 0.0, 0.0));
+                    }
+                    let slot = match *field {
+                        "time" => 0,
+                        "azimuth" => 1,
+                        "elevation" => 2,
+                        "radius" => 3,
+                        "target" => {
+                            let [x, y, z] = value.as_vec3().ok_or_else(|| format!("line {}: `{}` must be an array of 3 numbers", line, key))?;
+                            targets[index] = vec3(x as f32, y as f32, z as f32);
+                            continue;
+                        }
+                        _ => return Err(format!("line {}: unknown key `{}`", line, key)),
+                    };
+                    keyframes[index][slot] = Some(number()?);
+                }
+                _ => return Err(format!("line {}: unknown key `{}`", line, key)),
+            }
+        }
+
+        let keyframes = keyframes.iter().zip(&targets).enumerate().map(|(index, (fields, &target))| {
+            match *fields {
+                [Some(time), Some(azimuth), Some(elevation), Some(radius)] => Ok(CameraKeyframe { time, azimuth, elevation, radius, target }),
+                _ => Err(format!("keyframe {} needs time, azimuth, elevation and radius", index + 1)),
+            }
+        }).collect::<Result<Vec<_>, String>>()?;
+        CameraTimeline::new(keyframes, looping, fps)
+    }
+
+    pub fn start(&self) -> f64 {
+        self.keyframes[0].time
+    }
+
+    pub fn end(&self) -> f64 {
+        self.keyframes[self.keyframes.len() - 1].time
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.end() - self.start()
+    }
+
+    /// The pose at `time` seconds. Outside the keyframes it holds the first or last pose, or wraps
+    /// around when looping.
+    pub fn sample(&self, time: f64) -> InterpolatedState {
+        let keys = &self.keyframes;
+        let n = keys.len();
+        let time = if self.looping {
+            self.start() + (time - self.start()).rem_euclid(self.duration())
+        } else {
+            time.clamp(self.start(), self.end())
+        };
+        if n == 1 {
+            return keys[0].state();
+        }
+
+        let i = keys.windows(2).position(|pair| time <= pair[1].time).unwrap_or(n - 2);
+        let (t0, t1) = (keys[i].time, keys[i + 1].time);
+        let h = t1 - t0;
+        let s = (time - t0) / h;
+        let (p0, p1) = (keys[i].channels(), keys[i + 1].channels());
+        let (m0, m1) = (self.tangent(i), self.tangent(i + 1));
+
+        let h00 = 2.0 * s * s * s - 3.0 * s * s + 1.0;
+        let h10 = s * s * s - 2.0 * s * s + s;
+        let h01 = -2.0 * s * s * s + 3.0 * s * s;
+        let h11 = s * s * s - s * s;
+        let p: [f64; 6] = std::array::from_fn(|c| h00 * p0[c] + h10 * h * m0[c] + h01 * p1[c] + h11 * h * m1[c]);
+        InterpolatedState {
+            azimuth: p[0],
+            elevation: p[1],
+            radius: p[2].exp(),
+            target: vec3(p[3] as f32, p[4] as f32, p[5] as f32),
+        }
+    }
+
+    // Catmull-Rom slope of every channel at keyframe `i`
+    fn tangent(&self, i: usize) -> [f64; 6] {
+        let keys = &self.keyframes;
+        let last = keys.len() - 1;
+        // (time, channels) of the neighbours on either side
+        let (before, after) = if i > 0 && i < last {
+            ((keys[i - 1].time, keys[i - 1].channels()), (keys[i + 1].time, keys[i + 1].channels()))
+        } else if self.looping {
+            // the first and last keyframe are the same pose, continue through the seam with the
+            // one before the last shifted back by a period (and by any whole turns of azimuth)
+            let (first, end, previous) = (keys[0].channels(), keys[last].channels(), keys[last - 1].channels());
+            let shifted: [f64; 6] = std::array::from_fn(|c| previous[c] - (end[c] - first[c]));
+            ((keys[last - 1].time - self.duration(), shifted), (keys[1].time, keys[1].channels()))
+        } else {
+            return [0.0; 6];
+        };
+        std::array::from_fn(|c| (after.1[c] - before.1[c]) / (after.0 - before.0))
+    }
+}
+
+impl CameraKeyframe {
+    fn state(&self) -> InterpolatedState {
+        InterpolatedState { azimuth: self.azimuth, elevation: self.elevation, radius: self.radius, target: self.target }
+    }
+
+    // The interpolated channels: azimuth, elevation, log radius and the target coordinates
+    fn channels(&self) -> [f64; 6] {
+        [self.azimuth, self.elevation, self.radius.ln(), self.target.x as f64, self.target.y as f64, self.target.z as f64]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keyframe(time: f64, azimuth: f64, radius: f64) -> CameraKeyframe {
+        CameraKeyframe { time, azimuth, elevation: 1.0, radius, target: vec3(0.0, 0.0, 0.0) }
+    }
+
+    #[test]
+    fn sample_passes_through_keyframes_and_eases_at_the_ends() {
+        let timeline = CameraTimeline::new(vec![keyframe(0.0, 0.0, 1e10), keyframe(2.0, 1.0, 1e11), keyframe(4.0, 3.0, 1e10)], false, 30).unwrap();
+        for (time, azimuth, radius) in [(0.0, 0.0, 1e10), (2.0, 1.0, 1e11), (4.0, 3.0, 1e10)] {
+            let state = timeline.sample(time);
+            assert!((state.azimuth - azimuth).abs() < 1e-12);
+            assert!((state.radius - radius).abs() < radius * 1e-12);
+        }
+        // zero slope at the ends, and held outside of them
+        assert!((timeline.sample(0.01).azimuth - timeline.sample(0.0).azimuth).abs() < 1e-3);
+        assert_eq!(timeline.sample(-1.0), timeline.sample(0.0));
+        assert_eq!(timeline.sample(9.0), timeline.sample(4.0));
+    }
+
+    #[test]
+    fn looping_timeline_wraps_smoothly() {
+        let turn = std::f64::consts::TAU;
+        let timeline = CameraTimeline::new(vec![keyframe(0.0, 0.0, 1e10), keyframe(1.0, 2.0, 1e10), keyframe(2.0, turn, 1e10)], true, 30).unwrap();
+        assert!((timeline.sample(2.5).azimuth - timeline.sample(0.5).azimuth).abs() < 1e-12);
+        assert!(timeline.sample(1e-3).azimuth > 0.0, "keeps turning the same way past the seam");
+        // the slope just before the seam matches the one just after it
+        let before = timeline.sample(2.0 - 1e-4).azimuth - timeline.sample(2.0 - 2e-4).azimuth;
+        let after = timeline.sample(2e-4).azimuth - timeline.sample(1e-4).azimuth;
+        assert!(before > 0.0);
+        assert!((before - after).abs() < 1e-6);
+    }
+
+    #[test]
+    fn parses_keyframe_tables() {
+        let timeline = CameraTimeline::parse("
+            loop = true
+            [[keyframe]]
+            time = 0
+            azimuth = 0
+            elevation = 1.5
+            radius = 6e10
+            [[keyframe]]
+            time = 5
+            azimuth = 3.14
+            elevation = 1.2
+            radius = 3e10
+            target = [1e9, 0, 0]
+        ").unwrap();
+        assert!(timeline.looping);
+        assert_eq!(timeline.duration(), 5.0);
+        assert!((timeline.sample(5.0 - 1e-9).target.x - 1e9).abs() < 1e3);
+
+        assert!(CameraTimeline::parse("[[keyframe]]\ntime = 0\nazimuth = 0").unwrap_err().contains("radius"));
+        assert!(CameraTimeline::parse("[[keyframe]]\nzoom = 2").unwrap_err().contains("unknown key"));
+        assert!(CameraTimeline::parse("[keyframe.first]\ntime = 0").unwrap_err().contains("unknown key"));
+    }
+}
@@ -0,0 +1,135 @@
+use std::mem;
+use std::os::raw::c_void;
+use gl::types::{GLsizeiptr, GLuint};
+use crate::engine::MAX_OBJECTS;
+
+// std140 mirrors of the uniform blocks in geodesic_cs.glsl (the grid shader shares Objects and
+// BlackHole), field for field with the shader's names. A vec3 takes a vec4's 16 bytes, written
+// out as its own pad field, bools and ints are 4 bytes and every array element is padded to 16,
+// a float array included. With #[repr(C)] these structs are the bytes the GPU reads, which the
+// tests below hold to the offsets std140 gives.
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct CameraUbo {
+    pub cam_pos: [f32; 3], pub _pad0: f32,
+    pub cam_right: [f32; 3], pub _pad1: f32,
+    pub cam_up: [f32; 3], pub _pad2: f32,
+    pub cam_forward: [f32; 3], pub _pad3: f32,
+    pub tan_half_fov: f32,
+    pub aspect: f32,
+    pub moving: u32, // a GLSL bool
+    pub _pad4: i32,
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct DiskUbo {
+    pub disk_r1: f32,
+    pub disk_r2: f32,
+    pub disk_num: f32,
+    pub thickness: f32,
+    pub disk_color: [f32; 4], // rgb tint, w unused
+    pub disk_axis: [f32; 4], // orbital axis, w angular velocity
+    pub doppler_enabled: i32,
+    pub disk_profile: i32,
+    pub max_temperature: f32,
+    pub disk_model: i32,
+    pub density_scale: f32,
+    pub emission_scale: f32,
+    pub _disk_pad1: [i32; 2],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ObjectsUbo {
+    pub num_objects: i32,
+    pub _pad: [i32; 3],
+    pub obj_pos_radius: [[f32; 4]; MAX_OBJECTS],
+    pub obj_color: [[f32; 4]; MAX_OBJECTS],
+    pub mass: [[f32; 4]; MAX_OBJECTS], // float mass[16], only x of each element is read
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct BlackHoleUbo {
+    pub spin: f32,
+    pub rs: f32,
+    pub redshift_enabled: i32,
+    pub redshift_exaggeration: f32,
+    pub observer_region: i32,
+    pub _bh_pad: [i32; 3],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct IntegratorUbo {
+    pub max_steps: i32,
+    pub step_size: f32,
+    pub integration_mode: i32,
+    pub tolerance: f32,
+    pub horizon_epsilon: f32,
+    pub _integrator_pad: [f32; 3],
+}
+
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct SkyUbo {
+    pub star_density: f32,
+    pub star_brightness: f32,
+    pub star_seed: u32,
+    pub starfield_enabled: i32,
+    pub background_enabled: i32,
+    pub _sky_pad: [i32; 3],
+    pub background_color: [f32; 4], // rgb behind escaped rays when no panorama is bound, w unused
+}
+
+/// Creates a uniform buffer sized for `T` and binds it to `binding`, which has to match the
+/// block's `binding =` in the shaders.
+pub fn create<T>(binding: GLuint) -> GLuint {
+    let mut buffer = 0;
+    unsafe {
+        gl::GenBuffers(1, &mut buffer);
+        gl::BindBuffer(gl::UNIFORM_BUFFER, buffer);
+        gl::BufferData(gl::UNIFORM_BUFFER, mem::size_of::<T>() as GLsizeiptr, std::ptr::null(), gl::DYNAMIC_DRAW);
+        gl::BindBufferBase(gl::UNIFORM_BUFFER, binding, buffer);
+    }
+    buffer
+}
+
+/// Replaces the whole contents of a buffer made by `create::<T>`.
+pub fn upload<T>(buffer: GLuint, block: &T) {
+    unsafe {
+        gl::BindBuffer(gl::UNIFORM_BUFFER, buffer);
+        gl::BufferSubData(gl::UNIFORM_BUFFER, 0, mem::size_of::<T>() as GLsizeiptr, block as *const T as *const c_void);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::mem::{offset_of, size_of};
+    use super::*;
+
+    #[test]
+    fn blocks_follow_std140() {
+        assert_eq!((offset_of!(CameraUbo, cam_right), offset_of!(CameraUbo, cam_forward)), (16, 48));
+        assert_eq!((offset_of!(CameraUbo, tan_half_fov), offset_of!(CameraUbo, moving)), (64, 72));
+        assert_eq!(size_of::<CameraUbo>(), 80);
+
+        assert_eq!((offset_of!(DiskUbo, disk_color), offset_of!(DiskUbo, disk_axis)), (16, 32));
+        assert_eq!((offset_of!(DiskUbo, doppler_enabled), offset_of!(DiskUbo, max_temperature)), (48, 56));
+        assert_eq!((offset_of!(DiskUbo, density_scale), size_of::<DiskUbo>()), (64, 80));
+
+        // an int padded to the vec4 array, then three arrays of 16 elements with a 16 byte stride
+        assert_eq!(offset_of!(ObjectsUbo, obj_pos_radius), 16);
+        assert_eq!((offset_of!(ObjectsUbo, obj_color), offset_of!(ObjectsUbo, mass)), (272, 528));
+        assert_eq!(size_of::<ObjectsUbo>(), 784);
+
+        assert_eq!((offset_of!(BlackHoleUbo, redshift_enabled), offset_of!(BlackHoleUbo, observer_region)), (8, 16));
+        assert_eq!(size_of::<BlackHoleUbo>(), 32);
+        assert_eq!((offset_of!(IntegratorUbo, tolerance), offset_of!(IntegratorUbo, horizon_epsilon)), (12, 16));
+        assert_eq!(size_of::<IntegratorUbo>(), 32);
+        assert_eq!((offset_of!(SkyUbo, background_enabled), offset_of!(SkyUbo, background_color)), (16, 32));
+        assert_eq!(size_of::<SkyUbo>(), 48);
+    }
+}
@@ -0,0 +1,124 @@
+use std::ops::{Add, Div, Mul, MulAssign, Sub};
+use crate::engine::schwarzschild_radius;
+
+/// Meters in an astronomical unit, exact by the IAU's 2012 definition.
+pub const AU: f64 = 1.495978707e11;
+
+/// A distance, kept in meters. Scene files give lengths in meters, the viewer reads them out in
+/// Schwarzschild radii of the current black hole; going through `Length` says which one a
+/// number is. Lengths scale by plain numbers and divide into one another as ratios.
+#[derive(Clone, Copy, Debug, PartialEq, PartialOrd, Default)]
+pub struct Length(f64);
+
+impl Length {
+    pub const ZERO: Length = Length(0.0);
+
+    pub const fn meters(meters: f64) -> Self {
+        Length(meters)
+    }
+
+    /// One Schwarzschild radius of a black hole of `mass` kilograms, times a number for more.
+    pub fn rs(mass: f64) -> Self {
+        Length(schwarzschild_radius(mass))
+    }
+
+    #[allow(dead_code)] // scenes give meters, only the tests start from au so far
+    pub const fn au(au: f64) -> Self {
+        Length(au * AU)
+    }
+
+    pub const fn in_meters(self) -> f64 {
+        self.0
+    }
+
+    /// This length in Schwarzschild radii of a black hole of `mass` kilograms.
+    pub fn in_rs(self, mass: f64) -> f64 {
+        self / Length::rs(mass)
+    }
+
+    pub const fn in_au(self) -> f64 {
+        self.0 / AU
+    }
+
+    pub fn min(self, other: Length) -> Length {
+        Length(self.0.min(other.0))
+    }
+
+    pub fn max(self, other: Length) -> Length {
+        Length(self.0.max(other.0))
+    }
+
+    pub fn clamp(self, min: Length, max: Length) -> Length {
+        Length(self.0.clamp(min.0, max.0))
+    }
+}
+
+impl Add for Length {
+    type Output = Length;
+    fn add(self, other: Length) -> Length {
+        Length(self.0 + other.0)
+    }
+}
+
+impl Sub for Length {
+    type Output = Length;
+    fn sub(self, other: Length) -> Length {
+        Length(self.0 - other.0)
+    }
+}
+
+impl Mul<f64> for Length {
+    type Output = Length;
+    fn mul(self, factor: f64) -> Length {
+        Length(self.0 * factor)
+    }
+}
+
+impl MulAssign<f64> for Length {
+    fn mul_assign(&mut self, factor: f64) {
+        self.0 *= factor;
+    }
+}
+
+impl Div<f64> for Length {
+    type Output = Length;
+    fn div(self, divisor: f64) -> Length {
+        Length(self.0 / divisor)
+    }
+}
+
+// the ratio of two lengths is a plain number
+impl Div for Length {
+    type Output = f64;
+    fn div(self, other: Length) -> f64 {
+        self.0 / other.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::engine::{SAGA_RS, SOLAR_MASS};
+
+    #[test]
+    fn schwarzschild_radii_follow_the_mass() {
+        // 2GM/c^2 is just under 3 km for the sun
+        assert!((Length::rs(SOLAR_MASS).in_meters() - 2953.4).abs() < 1.0, "{:?}", Length::rs(SOLAR_MASS));
+        // and grows in proportion to the mass, Sagittarius A* at 4.3 million suns
+        let sgr_a = 8.54e36;
+        assert!((Length::rs(sgr_a).in_meters() / SAGA_RS - 1.0).abs() < 1e-3);
+        assert_eq!(Length::rs(2.0 * SOLAR_MASS), Length::rs(SOLAR_MASS) * 2.0);
+        let disk = Length::rs(sgr_a) * 2.2;
+        assert!((disk.in_rs(sgr_a) - 2.2).abs() < 1e-12);
+        assert!((disk.in_rs(2.0 * sgr_a) - 1.1).abs() < 1e-12);
+    }
+
+    #[test]
+    fn lengths_convert_between_units() {
+        assert_eq!(Length::au(1.0).in_meters(), 1.495978707e11);
+        assert!((Length::meters(7.479893535e10).in_au() - 0.5).abs() < 1e-12);
+        assert_eq!(Length::meters(3.0) + Length::meters(1.0), Length::meters(4.0));
+        assert_eq!(Length::meters(6.0) / Length::meters(4.0), 1.5);
+        assert_eq!(Length::meters(5.0).clamp(Length::ZERO, Length::meters(2.0)), Length::meters(2.0));
+    }
+}